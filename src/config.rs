@@ -5,6 +5,7 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::errors::TemplativeError;
+use crate::git;
 use crate::utilities;
 
 const CONFIG_VERSION: u32 = 1;
@@ -18,6 +19,17 @@ pub enum GitMode {
     NoGit,
 }
 
+impl GitMode {
+    /// The name as written in JSON and on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GitMode::Fresh => "fresh",
+            GitMode::Preserve => "preserve",
+            GitMode::NoGit => "no-git",
+        }
+    }
+}
+
 fn default_git_mode() -> GitMode {
     GitMode::Fresh
 }
@@ -42,6 +54,113 @@ fn default_write_mode() -> WriteMode {
 
 fn default_true() -> bool { true }
 
+/// Which template sources `init` refreshes before copying:
+///
+/// | variant    | URL cache | local git repo |
+/// |------------|-----------|----------------|
+/// | `Never`    | no        | no             |
+/// | `OnlyUrl`  | fetched   | no             |
+/// | `Always`   | fetched   | pulled         |
+///
+/// Local repos are the user's own working copies, so only `Always` may touch them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateOnInit {
+    Never,
+    #[default]
+    OnlyUrl,
+    Always,
+}
+
+impl UpdateOnInit {
+    pub fn updates_url_cache(&self) -> bool {
+        matches!(self, UpdateOnInit::OnlyUrl | UpdateOnInit::Always)
+    }
+
+    pub fn updates_local_repo(&self) -> bool {
+        matches!(self, UpdateOnInit::Always)
+    }
+}
+
+/// What to do with a template symlink when the destination cannot hold one
+/// (non-unix platforms, or filesystems where creating the link fails):
+/// - `Error`: abort the copy (the historic behavior).
+/// - `CopyTarget`: write the contents of the file the link resolves to. Broken links and
+///   links to directories are skipped with a warning.
+/// - `Skip`: leave the link out with a warning.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkFallback {
+    #[default]
+    Error,
+    CopyTarget,
+    Skip,
+}
+
+/// How copied symlinks point at their targets:
+/// - `Auto`: relative for targets inside the template, absolute for targets outside it.
+/// - `Relative` / `Absolute`: always that form.
+/// - `PreserveRaw`: the link text copied unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkMode {
+    #[default]
+    Auto,
+    Relative,
+    Absolute,
+    PreserveRaw,
+}
+
+/// How tokens are written in template file and folder names: `{{project}}` (`Braces`) or
+/// `__project__` (`Underscores`, for tools and shells that dislike braces in paths). `Off`,
+/// the default, leaves names as written, so templates with literal `{{…}}` names (say, for
+/// another templating tool) keep them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathTokenStyle {
+    #[default]
+    Off,
+    Braces,
+    Underscores,
+}
+
+impl PathTokenStyle {
+    /// `name` written as a token in this style, or `None` when tokens are off.
+    pub fn token(&self, name: &str) -> Option<String> {
+        match self {
+            PathTokenStyle::Off => None,
+            PathTokenStyle::Braces => Some(format!("{{{{{}}}}}", name)),
+            PathTokenStyle::Underscores => Some(format!("__{}__", name)),
+        }
+    }
+}
+
+/// How `GitMode::Preserve` clones the template repo:
+/// - `Full`: an independent clone with its own copy of the history.
+/// - `Shared`: `git clone --shared`, borrowing objects from the template repo or URL cache.
+///   The project breaks if that source is pruned or deleted.
+/// - `Reference`: `git clone --reference`, borrowing objects from the template repo or URL
+///   cache while cloning from the original location.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreserveStrategy {
+    #[default]
+    Full,
+    Shared,
+    Reference,
+}
+
+/// Where the pre-init hook runs:
+/// - `Target`: in the target directory, like post-init (usually empty at that point).
+/// - `Source`: in the template source being copied, where a script it ships lives.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookCwd {
+    #[default]
+    Target,
+    Source,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub version: u32,
@@ -53,6 +172,67 @@ pub struct Config {
     pub write_mode: WriteMode,
     #[serde(default = "default_true")]
     pub color: bool,
+    #[serde(default)]
+    pub update_on_init: UpdateOnInit,
+    #[serde(default)]
+    pub no_cache: bool,
+    #[serde(default)]
+    pub symlink_mode: SymlinkMode,
+    #[serde(default)]
+    pub path_token_style: PathTokenStyle,
+    #[serde(default)]
+    pub symlink_fallback: SymlinkFallback,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub pre_init_cwd: HookCwd,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub preserve_strategy: PreserveStrategy,
+    /// With `GitMode::Preserve`, delete the executable hooks in the clone's `.git/hooks`
+    /// (keeping `.sample`s). Clones don't copy the template's hooks, so these are the ones
+    /// git installed from the user's `init.templateDir`.
+    #[serde(default)]
+    pub clear_git_hooks: bool,
+    #[serde(default)]
+    pub git_retries: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+    #[serde(default)]
+    pub no_commit: bool,
+    /// Extra directories `init` refuses to write into, on top of the built-in system roots.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dangerous_paths: Vec<PathBuf>,
+    /// `.gitignore` for projects whose template doesn't ship one: a file path, or the content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitignore_template: Option<String>,
+    /// When `update_on_init` refreshes URL caches, skip the fetch for a cache fetched less
+    /// than this many seconds ago.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_secs: Option<u64>,
+    /// Skip template files larger than this many bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    /// Skip template files that look binary.
+    #[serde(default)]
+    pub exclude_binaries: bool,
+    /// Recreate template directories that hold no copied files. When false, directories left
+    /// empty (by exclusion or because they were empty to begin with) are not created.
+    #[serde(default = "default_true")]
+    pub keep_empty_dirs: bool,
+    /// The directory of the `--config` file, which the registry, snippets and a default cache
+    /// then live beside. Set by `load_explicit`, never read from or written to the file.
+    #[serde(skip)]
+    pub flag_config_dir: Option<PathBuf>,
+    /// `--registry-dir`. Not a config file setting.
+    #[serde(skip)]
+    pub flag_registry_dir: Option<PathBuf>,
+    /// `--progress`: stream git's progress while cloning. Not a config file setting.
+    #[serde(skip)]
+    pub show_progress: bool,
 }
 
 impl Config {
@@ -63,9 +243,37 @@ impl Config {
             exclude: default_exclude(),
             write_mode: WriteMode::Strict,
             color: true,
+            update_on_init: UpdateOnInit::OnlyUrl,
+            no_cache: false,
+            symlink_mode: SymlinkMode::Auto,
+            path_token_style: PathTokenStyle::Off,
+            symlink_fallback: SymlinkFallback::Error,
+            hook_timeout_secs: None,
+            pre_init_cwd: HookCwd::Target,
+            follow_symlinks: false,
+            preserve_strategy: PreserveStrategy::Full,
+            clear_git_hooks: false,
+            git_retries: 0,
+            cache_dir: None,
+            commit_message: None,
+            no_commit: false,
+            dangerous_paths: Vec::new(),
+            gitignore_template: None,
+            cache_ttl_secs: None,
+            max_file_size: None,
+            exclude_binaries: false,
+            keep_empty_dirs: true,
+            flag_config_dir: None,
+            flag_registry_dir: None,
+            show_progress: false,
         }
     }
 
+    /// How git commands that talk to a remote should behave under this config.
+    pub fn network(&self) -> git::NetworkOptions {
+        git::NetworkOptions { retries: self.git_retries, show_progress: self.show_progress }
+    }
+
     pub fn load() -> Result<Self> {
         let path = Self::config_path()?;
         let config = Self::load_from_path(&path)?;
@@ -84,6 +292,17 @@ impl Config {
         Ok(config)
     }
 
+    /// Loads the file given with `--config`. Unlike `load`, a missing file is an error and the
+    /// file is never rewritten. Its directory replaces the config directory.
+    pub fn load_explicit(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            anyhow::bail!("config file not found: {}", path.display());
+        }
+        let mut config = Self::load_from_path(path)?;
+        config.flag_config_dir = path.canonicalize().ok().and_then(|path| path.parent().map(PathBuf::from));
+        Ok(config)
+    }
+
     pub fn load_from_path(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::new());
@@ -112,7 +331,7 @@ impl Config {
     }
 
     fn config_path() -> Result<PathBuf> {
-        Ok(utilities::config_dir()?.join(CONFIG_FILENAME))
+        Ok(utilities::default_config_dir()?.join(CONFIG_FILENAME))
     }
 }
 
@@ -144,6 +363,17 @@ mod tests {
         assert_eq!(loaded.version, config.version);
     }
 
+    #[test]
+    fn load_explicit_requires_the_file_and_leaves_it_untouched() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("testing.json");
+        assert!(Config::load_explicit(&path).is_err());
+        std::fs::write(&path, r#"{"version":1,"git_retries":3}"#).unwrap();
+        let loaded = Config::load_explicit(&path).unwrap();
+        assert_eq!(loaded.git_retries, 3);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"version":1,"git_retries":3}"#);
+    }
+
     #[test]
     fn rejects_future_version() {
         let temp = tempfile::tempdir().unwrap();
@@ -211,6 +441,29 @@ mod tests {
             exclude: vec!["dist".into()],
             write_mode: WriteMode::Strict,
             color: true,
+            update_on_init: UpdateOnInit::OnlyUrl,
+            no_cache: false,
+            symlink_mode: SymlinkMode::Auto,
+            path_token_style: PathTokenStyle::Braces,
+            symlink_fallback: SymlinkFallback::Error,
+            hook_timeout_secs: None,
+            pre_init_cwd: HookCwd::Target,
+            follow_symlinks: false,
+            preserve_strategy: PreserveStrategy::Full,
+            clear_git_hooks: false,
+            git_retries: 0,
+            cache_dir: None,
+            commit_message: None,
+            no_commit: false,
+            dangerous_paths: Vec::new(),
+            gitignore_template: None,
+            cache_ttl_secs: None,
+            max_file_size: None,
+            exclude_binaries: false,
+            keep_empty_dirs: true,
+            flag_config_dir: None,
+            flag_registry_dir: None,
+            show_progress: false,
         };
         config.save_to_path(&path).unwrap();
         let loaded = Config::load_from_path(&path).unwrap();
@@ -287,6 +540,49 @@ mod tests {
         assert_eq!(config.write_mode, WriteMode::Strict);
     }
 
+    #[test]
+    fn old_config_without_update_on_init_defaults_to_only_url() {
+        let json = r#"{"version":1}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.update_on_init, UpdateOnInit::OnlyUrl);
+        assert!(!config.no_cache);
+    }
+
+    #[test]
+    fn update_on_init_roundtrip() {
+        let json = r#"{"version":1,"update_on_init":"only-url"}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.update_on_init, UpdateOnInit::OnlyUrl);
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(serialized.contains("only-url"));
+        let json = r#"{"version":1,"update_on_init":"always"}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.update_on_init, UpdateOnInit::Always);
+    }
+
+    #[test]
+    fn update_on_init_matrix() {
+        assert!(!UpdateOnInit::Never.updates_url_cache());
+        assert!(!UpdateOnInit::Never.updates_local_repo());
+        assert!(UpdateOnInit::OnlyUrl.updates_url_cache());
+        assert!(!UpdateOnInit::OnlyUrl.updates_local_repo());
+        assert!(UpdateOnInit::Always.updates_url_cache());
+        assert!(UpdateOnInit::Always.updates_local_repo());
+    }
+
+    #[test]
+    fn no_cache_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.json");
+        let mut config = Config::new();
+        config.no_cache = true;
+        config.update_on_init = UpdateOnInit::Never;
+        config.save_to_path(&path).unwrap();
+        let loaded = Config::load_from_path(&path).unwrap();
+        assert!(loaded.no_cache);
+        assert_eq!(loaded.update_on_init, UpdateOnInit::Never);
+    }
+
     #[test]
     fn write_mode_roundtrip() {
         let json = r#"{"version":1,"write_mode":"skip-overwrite"}"#;
@@ -8,18 +8,36 @@ pub enum TemplativeError {
     #[error("target directory is not empty")]
     TargetNotEmpty,
 
+    #[error("target {path:?} already exists; --new-dir-only needs a path that doesn't")]
+    TargetExists { path: PathBuf },
+
     #[error("template not found: {name}")]
     TemplateNotFound { name: String },
 
+    #[error("no templates match {pattern:?}")]
+    NoTemplatesMatch { pattern: String },
+
+    #[error("git is not installed or not on PATH; install it from https://git-scm.com/downloads (or your package manager) and try again")]
+    GitNotInstalled,
+
+    #[error("could not reach {url}; check the URL and your access to it\n\n{reason}")]
+    RemoteUnreachable { url: String, reason: String },
+
     #[error("template name already exists: {name}")]
     TemplateExists { name: String },
 
     #[error("template path missing or unreadable: {path:?}")]
     TemplatePathMissing { path: PathBuf },
 
+    #[error("environment variable {variable} in template location {location:?} is not set")]
+    LocationVariableUnset { variable: String, location: String },
+
+    #[error("template subdir {subdir:?} not found in {source_path:?}")]
+    SubdirMissing { subdir: String, source_path: PathBuf },
+
     #[error("unsupported registry version {found} (expected {expected}); delete {path} to start fresh")]
     UnsupportedRegistryVersion {
-        found: u32,
+        found: u64,
         expected: u32,
         path: String,
     },
@@ -27,6 +45,76 @@ pub enum TemplativeError {
     #[error("unsupported config version (expected 1)")]
     UnsupportedConfigVersion,
 
+    #[error("invalid exclude pattern {pattern:?}: {reason}")]
+    InvalidExcludePattern { pattern: String, reason: String },
+
+    #[error("template {source_path:?} produced no files; check your exclude patterns and file filters")]
+    NothingCopied { source_path: PathBuf },
+
+    #[error("exclude pattern {pattern:?} would exclude the whole template")]
+    ExcludeMatchesEverything { pattern: String },
+
+    #[error("template {name} doesn't match its pinned checksum (expected {expected}, found {actual}); \
+             if the change is expected, run 'templative checksum {name} --save'")]
+    ChecksumMismatch { name: String, expected: String, actual: String },
+
+    #[error("write mode ask needs a terminal to prompt on; pass --assume overwrite or --assume skip")]
+    AskNeedsTerminal,
+
+    #[error("rename {from:?} -> {to:?} must name a relative path inside the project")]
+    InvalidRename { from: String, to: String },
+
+    #[error("file name {name:?} fills in as {filled:?}, which isn't a usable name inside the project; pick another --var value")]
+    InvalidPathToken { name: String, filled: String },
+
+    #[error("renames {first:?} and {second:?} both write to {to:?}")]
+    DuplicateRename { first: String, second: String, to: String },
+
+    #[error("target subdir {subdir:?} must be a relative path inside the target, without `..`")]
+    InvalidTargetSubdir { subdir: String },
+
+    #[error("git mode preserve clones the template, but {path:?} is not a git repository; use --git fresh or run `git init` in the template")]
+    PreserveNeedsGitRepo { path: PathBuf },
+
+    #[error("this template needs templative {required} or newer (running {running}); upgrade templative to use it")]
+    TemplativeTooOld { required: String, running: String },
+
+    #[error("the template's .templative.toml wants to run these hooks:\n{}\npass --trust-hooks to run them, or --no-hooks to skip them",
+            hooks.iter().map(|hook| format!("  {}", hook)).collect::<Vec<_>>().join("\n"))]
+    UntrustedHooks { hooks: Vec<String> },
+
+    #[error("commit message is empty; set commit_message or pass --commit-message")]
+    EmptyCommitMessage,
+
     #[error("the following files would be overwritten:\n{}", paths.iter().map(|p| format!("  {}", p.display())).collect::<Vec<_>>().join("\n"))]
     FilesWouldBeOverwritten { paths: Vec<PathBuf> },
 }
+
+impl TemplativeError {
+    /// The process exit code for this error, so scripts can tell common failures apart.
+    /// Anything not listed here, and errors that aren't a `TemplativeError`, exit with 1. The
+    /// codes start at 10 to stay clear of clap, which exits with 2 on a usage error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TemplativeError::TemplateNotFound { .. } => 10,
+            TemplativeError::TargetNotEmpty | TemplativeError::TargetExists { .. } => 11,
+            TemplativeError::FilesWouldBeOverwritten { .. } => 12,
+            TemplativeError::DangerousPath { .. } => 13,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_code_survives_added_context() {
+        use anyhow::Context;
+        let err = Err::<(), _>(TemplativeError::TargetNotEmpty).context("while initializing").unwrap_err();
+        let code = err.chain().find_map(|cause| cause.downcast_ref::<TemplativeError>()).map(TemplativeError::exit_code);
+        assert_eq!(code, Some(11));
+        assert_eq!(TemplativeError::EmptyCommitMessage.exit_code(), 1);
+    }
+}
@@ -1,29 +1,72 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use dialoguer::Select;
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
 use walkdir::{DirEntry, WalkDir};
 
-use crate::config::WriteMode;
+use crate::config::{PathTokenStyle, SymlinkFallback, SymlinkMode, WriteMode};
 use crate::errors::TemplativeError;
+use crate::utilities;
+
+/// Exclude patterns split by gitignore anchoring rules:
+/// - a pattern without a slash (`config`, `*.log`) matches a name at any depth;
+/// - a pattern containing a slash (`/config`, `src/config`) is anchored to the template root
+///   and matched against the full relative path, with `*` not crossing `/`.
+///
+/// A trailing slash (`dist/`) is ignored for classification.
+struct ExcludeMatcher {
+    anywhere: GlobSet,
+    anchored: GlobSet,
+}
+
+/// Compiles one exclude pattern, returning whether it is anchored alongside the glob.
+fn compile_pattern(pattern: &str) -> Result<(bool, Glob), TemplativeError> {
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let glob = if anchored {
+        GlobBuilder::new(trimmed.trim_start_matches('/')).literal_separator(true).build()
+    } else {
+        Glob::new(trimmed)
+    };
+    glob.map(|glob| (anchored, glob)).map_err(|err| TemplativeError::InvalidExcludePattern {
+        pattern: pattern.to_string(),
+        reason: err.kind().to_string(),
+    })
+}
+
+/// Checks that every pattern compiles, so `add` and `change` can reject a typo up front
+/// instead of it surfacing at the next init.
+pub fn validate_exclude_patterns(patterns: &[String]) -> Result<(), TemplativeError> {
+    patterns
+        .iter()
+        // `!pattern` removes a pattern from the merged list; what follows still has to be one.
+        .map(|pattern| pattern.strip_prefix('!').unwrap_or(pattern))
+        .try_for_each(|pattern| compile_pattern(pattern).map(|_| ()))
+}
 
-fn build_globset(patterns: &[String]) -> Result<GlobSet> {
-    let mut builder = GlobSetBuilder::new();
+fn build_exclude_matcher(patterns: &[String]) -> Result<ExcludeMatcher> {
+    let mut anywhere = GlobSetBuilder::new();
+    let mut anchored = GlobSetBuilder::new();
     for pattern in patterns {
-        builder.add(
-            Glob::new(pattern)
-                .with_context(|| format!("invalid exclude pattern: {}", pattern))?,
-        );
+        match compile_pattern(pattern)? {
+            (true, glob) => anchored.add(glob),
+            (false, glob) => anywhere.add(glob),
+        };
     }
-    builder.build().context("failed to build exclude patterns")
+    Ok(ExcludeMatcher {
+        anywhere: anywhere.build().context("failed to build exclude patterns")?,
+        anchored: anchored.build().context("failed to build exclude patterns")?,
+    })
 }
 
 /// Returns true if this entry (or its path) should be skipped.
-/// `.git` is always excluded. Each path component and the full relative path
-/// are checked against `globset`.
-fn should_skip_entry(entry: &DirEntry, source_root: &Path, globset: &GlobSet) -> bool {
+/// `.git` is always excluded. Unanchored patterns are checked against each path component;
+/// anchored patterns against the full relative path. Directories that match are pruned by
+/// the walker, so their contents never need checking.
+fn should_skip_entry(entry: &DirEntry, source_root: &Path, matcher: &ExcludeMatcher) -> bool {
     let relative = match entry.path().strip_prefix(source_root) {
         Ok(rel) => rel,
         Err(_) => return false,
@@ -33,14 +76,11 @@ fn should_skip_entry(entry: &DirEntry, source_root: &Path, globset: &GlobSet) ->
         if part == ".git" {
             return true;
         }
-        if globset.is_match(part.as_ref()) {
+        if matcher.anywhere.is_match(part.as_ref()) {
             return true;
         }
     }
-    if globset.is_match(relative) {
-        return true;
-    }
-    false
+    matcher.anchored.is_match(relative)
 }
 
 enum FileChoice {
@@ -51,22 +91,79 @@ enum FileChoice {
     Abort,
 }
 
-fn prompt_file(dest_path: &Path) -> Result<FileChoice> {
+/// What would replace the file `prompt_file` asks about, so it can offer a diff.
+enum Incoming<'a> {
+    /// A template file to be copied over.
+    File(&'a Path),
+    /// A symlink, which has no contents worth diffing.
+    Symlink,
+}
+
+const SHOW_DIFF: &str = "Show diff";
+
+fn prompt_file(dest_path: &Path, incoming: Incoming) -> Result<FileChoice> {
     let prompt = format!("'{}' already exists. What would you like to do?", dest_path.display());
-    let options = &["Overwrite", "Skip", "Overwrite all", "Skip all", "Abort"];
-    let selection = Select::new()
-        .with_prompt(&prompt)
-        .items(options)
-        .default(0)
-        .interact()
-        .context("prompt failed")?;
-    Ok(match selection {
-        0 => FileChoice::Overwrite,
-        1 => FileChoice::Skip,
-        2 => FileChoice::OverwriteAll,
-        3 => FileChoice::SkipAll,
-        _ => FileChoice::Abort,
-    })
+    let mut options = vec!["Overwrite", "Skip", "Overwrite all", "Skip all", "Abort"];
+    if !matches!(incoming, Incoming::Symlink) {
+        options.insert(4, SHOW_DIFF);
+    }
+    loop {
+        let selection = Select::new()
+            .with_prompt(&prompt)
+            .items(&options)
+            .default(0)
+            .interact()
+            .context("prompt failed")?;
+        return Ok(match options[selection] {
+            "Overwrite" => FileChoice::Overwrite,
+            "Skip" => FileChoice::Skip,
+            "Overwrite all" => FileChoice::OverwriteAll,
+            "Skip all" => FileChoice::SkipAll,
+            SHOW_DIFF => {
+                // Only offered for files, so there's always something incoming to diff.
+                if let Incoming::File(source) = incoming {
+                    eprint!("{}", diff_against(dest_path, source)?);
+                }
+                continue;
+            }
+            _ => FileChoice::Abort,
+        });
+    }
+}
+
+/// What "Show diff" prints for `dest_path` against the template file `source`. A destination
+/// that can't be read as a file (a directory, a dangling symlink) is described instead, so the
+/// prompt can be answered rather than aborting.
+fn diff_against(dest_path: &Path, source: &Path) -> Result<String> {
+    let existing = match fs::read(dest_path) {
+        Ok(existing) => existing,
+        Err(_) if dest_path.is_dir() => return Ok("existing entry is a directory\n".to_string()),
+        Err(err) => return Ok(format!("existing entry is not readable: {}\n", err)),
+    };
+    let incoming = fs::read(source).with_context(|| format!("failed to read {}", source.display()))?;
+    Ok(render_diff(&existing, &incoming))
+}
+
+/// A unified diff from `existing` to `incoming`, or a size comparison when either isn't
+/// text (not UTF-8, or containing a NUL byte, as git judges it).
+fn render_diff(existing: &[u8], incoming: &[u8]) -> String {
+    fn as_text(bytes: &[u8]) -> Option<&str> {
+        std::str::from_utf8(bytes).ok().filter(|text| !text.contains('\0'))
+    }
+    let (Some(existing_text), Some(incoming_text)) = (as_text(existing), as_text(incoming)) else {
+        return format!(
+            "binary files differ (existing: {} bytes, incoming: {} bytes)\n",
+            existing.len(),
+            incoming.len()
+        );
+    };
+    if existing_text == incoming_text {
+        return "files are identical\n".to_string();
+    }
+    similar::TextDiff::from_lines(existing_text, incoming_text)
+        .unified_diff()
+        .header("existing", "incoming")
+        .to_string()
 }
 
 /// Computes a relative path from `from_dir` to `to`. Both must be absolute.
@@ -91,11 +188,29 @@ fn relative_path_between(from_dir: &Path, to: &Path) -> PathBuf {
     result
 }
 
-/// Copies a symlink from `source_path` to `dest_path`, adjusting the target:
-/// - If target resolves inside the template, keeps a relative symlink.
-/// - If target resolves outside the template, creates an absolute symlink.
-/// - If the target cannot be found (broken symlink), warns and preserves the original target.
-fn copy_symlink(source_path: &Path, dest_path: &Path, source_dir: &Path, dest_dir: &Path) -> Result<()> {
+/// Copies a symlink from `source_path` to `dest_path`, writing its target per `mode`:
+/// - `Auto`: a target inside the template stays relative (an absolute one is rewritten
+///   relative to its copy in the destination); a target outside it becomes absolute.
+/// - `Relative` / `Absolute`: always that form, pointing at the target's copy when the target
+///   is inside the template and at the original otherwise.
+/// - `PreserveRaw`: the link text unchanged.
+///
+/// A broken link (target not found) is always preserved as-is, with a warning.
+///
+/// When the link cannot be created (non-unix platforms, or a filesystem that refuses it),
+/// `fallback` decides whether to abort, copy the target's contents, or skip.
+///
+/// Only real links on disk get here. A template checked out with `core.symlinks=false` (the
+/// Windows default) has its links as plain files holding the link text, and those are copied
+/// as ordinary files.
+fn copy_symlink(
+    source_path: &Path,
+    dest_path: &Path,
+    source_dir: &Path,
+    dest_dir: &Path,
+    mode: &SymlinkMode,
+    fallback: &SymlinkFallback,
+) -> Result<()> {
     // Read the raw link target — may be relative or absolute, may or may not exist.
     let raw_target = fs::read_link(source_path)
         .with_context(|| format!("failed to read symlink: {}", source_path.display()))?;
@@ -110,75 +225,203 @@ fn copy_symlink(source_path: &Path, dest_path: &Path, source_dir: &Path, dest_di
     };
 
     let new_target: PathBuf = match absolute_target.canonicalize() {
+        _ if *mode == SymlinkMode::PreserveRaw => raw_target,
         Ok(canonical_target) => {
             // Canonicalize source_dir so we can reliably check whether the symlink target
             // lives inside the template (strip_prefix needs both paths fully resolved).
             let canonical_source = source_dir
                 .canonicalize()
                 .unwrap_or_else(|_| source_dir.to_path_buf());
-            if let Ok(target_rel) = canonical_target.strip_prefix(&canonical_source) {
-                // Target is inside the template tree.
-                if raw_target.is_relative() {
-                    // A relative-to-sibling link is structurally identical in the destination;
-                    // no adjustment needed — the same relative path resolves correctly.
-                    raw_target
-                } else {
-                    // An absolute link pointing inside the template must be rewritten as a
-                    // relative link inside the destination tree so it still resolves correctly
-                    // regardless of where the destination lives on disk.
-                    let dest_parent = dest_path.parent().unwrap_or(dest_dir);
-                    let target_in_dest = dest_dir.join(target_rel);
-                    relative_path_between(dest_parent, &target_in_dest)
+            let dest_parent = dest_path.parent().unwrap_or(dest_dir);
+            match canonical_target.strip_prefix(&canonical_source) {
+                Ok(target_rel) => {
+                    // Target is inside the template tree, so the link should follow its copy.
+                    // A link to the template root itself has an empty `target_rel`; point it at
+                    // the destination root rather than joining an empty path.
+                    let target_in_dest = if target_rel.as_os_str().is_empty() {
+                        dest_dir.to_path_buf()
+                    } else {
+                        dest_dir.join(target_rel)
+                    };
+                    match mode {
+                        // A relative-to-sibling link is structurally identical in the
+                        // destination; the same relative path resolves correctly.
+                        SymlinkMode::Auto if raw_target.is_relative() => raw_target,
+                        SymlinkMode::Absolute => target_in_dest,
+                        _ => relative_path_between(dest_parent, &target_in_dest),
+                    }
                 }
-            } else {
-                // Target is outside the template tree: keep the absolute canonical path so it
-                // still resolves to the same real file in the destination.
-                canonical_target
+                // Target is outside the template tree: the canonical path still resolves to
+                // the same real file from the destination.
+                Err(_) if *mode == SymlinkMode::Relative => relative_path_between(dest_parent, &canonical_target),
+                Err(_) => canonical_target,
             }
         }
         Err(_) => {
             // The link target does not exist (broken symlink). Preserve it as-is so the copy
             // faithfully reproduces the source, and warn so the user is not surprised.
-            eprintln!(
-                "warning: symlink '{}' points to '{}' which does not exist; creating anyway",
+            utilities::warn(format_args!(
+                "symlink '{}' points to '{}' which does not exist; creating anyway",
                 source_path.display(),
                 raw_target.display()
-            );
+            ));
             raw_target
         }
     };
 
     #[cfg(unix)]
-    std::os::unix::fs::symlink(&new_target, dest_path)
-        .with_context(|| format!("failed to create symlink: {}", dest_path.display()))?;
+    if let Err(err) = std::os::unix::fs::symlink(&new_target, dest_path) {
+        if *fallback == SymlinkFallback::Error {
+            return Err(err)
+                .with_context(|| format!("failed to create symlink: {}", dest_path.display()));
+        }
+        return apply_symlink_fallback(fallback, source_path, &absolute_target, dest_path);
+    }
 
     #[cfg(not(unix))]
     {
         let _ = new_target;
-        anyhow::bail!("symlinks are not supported on Windows");
+        apply_symlink_fallback(fallback, source_path, &absolute_target, dest_path)?;
     }
 
     Ok(())
 }
 
+/// Handles a symlink that could not be recreated at `dest_path`, per `fallback`.
+/// `absolute_target` is the link target resolved against the link's own directory. Links a
+/// `core.symlinks=false` checkout turned into plain files never reach the fallback.
+fn apply_symlink_fallback(
+    fallback: &SymlinkFallback,
+    source_path: &Path,
+    absolute_target: &Path,
+    dest_path: &Path,
+) -> Result<()> {
+    match fallback {
+        SymlinkFallback::Error => {
+            anyhow::bail!(
+                "symlinks are not supported on this platform: {} (set symlink_fallback to copy-target or skip)",
+                source_path.display()
+            );
+        }
+        SymlinkFallback::Skip => {
+            utilities::warn(format_args!("skipping symlink '{}'", source_path.display()));
+        }
+        SymlinkFallback::CopyTarget => {
+            if absolute_target.is_file() {
+                fs::copy(absolute_target, dest_path).with_context(|| {
+                    format!("failed to copy symlink target: {}", absolute_target.display())
+                })?;
+            } else if absolute_target.exists() {
+                utilities::warn(format_args!(
+                    "skipping symlink '{}': target '{}' is not a file",
+                    source_path.display(),
+                    absolute_target.display()
+                ));
+            } else {
+                utilities::warn(format_args!(
+                    "skipping symlink '{}': target '{}' does not exist",
+                    source_path.display(),
+                    absolute_target.display()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Unwraps a walker result. When following symlinks, a link back to one of its own ancestors
+/// or a broken link is warned about and skipped (`None`) rather than aborting the copy.
+fn walk_entry(entry: walkdir::Result<DirEntry>) -> Result<Option<DirEntry>> {
+    match entry {
+        Ok(entry) => Ok(Some(entry)),
+        Err(err) => {
+            if let Some(ancestor) = err.loop_ancestor() {
+                utilities::warn(format_args!(
+                    "skipping '{}': symlink loops back to '{}'",
+                    err.path().map(|path| path.display().to_string()).unwrap_or_default(),
+                    ancestor.display()
+                ));
+                return Ok(None);
+            }
+            if let Some(path) = err.path()
+                && path.is_symlink()
+                && !path.exists()
+            {
+                utilities::warn(format_args!(
+                    "skipping symlink '{}': target does not exist",
+                    path.display()
+                ));
+                return Ok(None);
+            }
+            Err(err).with_context(|| "walkdir entry error")
+        }
+    }
+}
+
+/// The walk `copy_template` makes over `source_dir`, shared by everything that needs to agree
+/// with it: `.git` and `exclude` matches are pruned, and with `follow_symlinks` linked
+/// directories are walked too. Yields `source_dir` itself first.
+fn walk_template<'a>(
+    source_dir: &'a Path,
+    matcher: &'a ExcludeMatcher,
+    follow_symlinks: bool,
+) -> impl Iterator<Item = walkdir::Result<DirEntry>> + 'a {
+    WalkDir::new(source_dir)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(move |entry| entry.path() == source_dir || !should_skip_entry(entry, source_dir, matcher))
+}
+
+/// Total bytes and number of files `copy_template` (or `copy_single_file`) would write for
+/// `source` with these `exclude` patterns and `options`: files `options.filter` rejects are
+/// left out, and symlinks count as files of no size unless `follow_symlinks` copies their
+/// targets.
+pub fn measure_template(source: &Path, exclude: &[String], options: &CopyOptions) -> Result<(u64, usize)> {
+    let metadata = fs::metadata(source).with_context(|| format!("failed to read {}", source.display()))?;
+    if metadata.is_file() {
+        if options.filter.skip_reason(source)?.is_some() {
+            return Ok((0, 0));
+        }
+        return Ok((metadata.len(), 1));
+    }
+    let matcher = build_exclude_matcher(exclude)?;
+    let (mut bytes, mut files) = (0u64, 0usize);
+    for entry in walk_template(source, &matcher, options.follow_symlinks) {
+        let Some(entry) = walk_entry(entry)? else { continue };
+        if entry.path() == source || entry.file_type().is_dir() {
+            continue;
+        }
+        if entry.file_type().is_file() {
+            if options.filter.skip_reason(entry.path())?.is_some() {
+                continue;
+            }
+            bytes += entry.metadata().with_context(|| format!("failed to read {}", entry.path().display()))?.len();
+        }
+        files += 1;
+    }
+    Ok((bytes, files))
+}
+
 /// Walks the source tree and returns the destination paths that already exist.
 /// Used by `copy_template` to pre-flight a `NoOverwrite` copy before writing anything.
-fn collect_collisions(source_dir: &Path, dest_dir: &Path, globset: &GlobSet) -> Result<Vec<PathBuf>> {
+fn collect_collisions(
+    source_dir: &Path,
+    dest_dir: &Path,
+    matcher: &ExcludeMatcher,
+    options: &CopyOptions,
+) -> Result<Vec<PathBuf>> {
     let mut collisions = Vec::new();
-    let walker = WalkDir::new(source_dir)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|entry| {
-            entry.path() == source_dir || !should_skip_entry(entry, source_dir, globset)
-        });
-    for entry in walker {
-        let entry = entry.with_context(|| "walkdir entry error")?;
+    for entry in walk_template(source_dir, matcher, options.follow_symlinks) {
+        let Some(entry) = walk_entry(entry)? else { continue };
         let path = entry.path();
         if path == source_dir || entry.file_type().is_dir() {
             continue;
         }
+        if entry.file_type().is_file() && options.filter.skip_reason(path)?.is_some() {
+            continue;
+        }
         let relative = path.strip_prefix(source_dir).with_context(|| "strip_prefix")?;
-        let dest_path = dest_dir.join(relative);
+        let dest_path = dest_dir.join(destination(relative, options)?);
         if dest_path.symlink_metadata().is_ok() {
             collisions.push(dest_path);
         }
@@ -186,25 +429,284 @@ fn collect_collisions(source_dir: &Path, dest_dir: &Path, globset: &GlobSet) ->
     Ok(collisions)
 }
 
+/// Content-based exclusions on top of the exclude patterns: files over `max_file_size` bytes,
+/// and with `exclude_binaries` files that look binary (a NUL byte in the first 8000 bytes,
+/// the same heuristic git uses).
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    pub max_file_size: Option<u64>,
+    pub exclude_binaries: bool,
+}
+
+impl FileFilter {
+    /// Why `path` should be left out, or `None` to copy it.
+    fn skip_reason(&self, path: &Path) -> Result<Option<String>> {
+        if let Some(limit) = self.max_file_size {
+            let size = fs::metadata(path).with_context(|| format!("failed to stat {}", path.display()))?.len();
+            if size > limit {
+                return Ok(Some(format!("{} bytes is over the {} byte limit", size, limit)));
+            }
+        }
+        if self.exclude_binaries && looks_binary(path)? {
+            return Ok(Some("looks like a binary file".to_string()));
+        }
+        Ok(None)
+    }
+}
+
+fn looks_binary(path: &Path) -> Result<bool> {
+    use std::io::Read;
+    let file = fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut head = Vec::with_capacity(8000);
+    file.take(8000)
+        .read_to_end(&mut head)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(head.contains(&0))
+}
+
+/// Files written or passed over by `copy_template`, as paths relative to the destination.
+/// Symlinks count as files.
+#[derive(Debug, Default)]
+pub struct CopySummary {
+    pub created: Vec<PathBuf>,
+    pub overwritten: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    /// The mode an `Ask` copy switched to when the user answered "overwrite all" or "skip
+    /// all", so the choice can be remembered for next time.
+    pub escalated_mode: Option<WriteMode>,
+}
+
+impl CopySummary {
+    fn record(&mut self, relative: PathBuf, existed: bool) {
+        trace_debug!(path = %relative.display(), overwritten = existed, "wrote file");
+        if existed {
+            self.overwritten.push(relative);
+        } else {
+            self.created.push(relative);
+        }
+    }
+
+    /// Every path the copy wrote, created or overwritten.
+    pub fn written(&self) -> Vec<&Path> {
+        self.created.iter().chain(&self.overwritten).map(PathBuf::as_path).collect()
+    }
+}
+
+/// Applies `source`'s permissions to `dest`. Returns false when `source` is executable but
+/// `dest` isn't afterwards, as happens on filesystems without unix modes (FAT, some network
+/// mounts), so the caller can warn instead of shipping scripts that no longer run.
+fn copy_permissions(source: &Path, dest: &Path) -> bool {
+    let Ok(metadata) = fs::metadata(source) else { return true };
+    let _ = fs::set_permissions(dest, metadata.permissions());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let is_executable = |mode: u32| mode & 0o111 != 0;
+        if is_executable(metadata.permissions().mode()) {
+            return fs::metadata(dest).is_ok_and(|dest_meta| is_executable(dest_meta.permissions().mode()));
+        }
+    }
+    true
+}
+
+fn warn_lost_executable_bits(paths: &[PathBuf]) {
+    if paths.is_empty() {
+        return;
+    }
+    utilities::warn(format_args!(
+        "could not keep the executable bit on {} file(s); the target filesystem may not support it:",
+        paths.len()
+    ));
+    for path in paths {
+        eprintln!("  {}", path.display());
+    }
+}
+
+/// Writes generated `contents` to `dest_dir/relative` (e.g. a `.gitignore` from config) and
+/// records it in `summary`, but only when nothing is there yet: a file the template brought
+/// along or the target already had always wins, whatever the write mode.
+pub fn write_generated_file(dest_dir: &Path, relative: &Path, contents: &str, summary: &mut CopySummary) -> Result<()> {
+    let dest_path = dest_dir.join(relative);
+    if dest_path.symlink_metadata().is_ok() {
+        return Ok(());
+    }
+    fs::write(&dest_path, contents).with_context(|| format!("failed to write {}", dest_path.display()))?;
+    summary.record(relative.to_path_buf(), false);
+    Ok(())
+}
+
+/// Copies a single-file template into `dest_dir`, keeping its file name and permissions and
+/// honoring `write_mode` if a file of that name already exists. A file `filter` rejects is
+/// skipped with a warning, as `copy_template` does.
+pub fn copy_single_file(
+    source_file: &Path,
+    dest_dir: &Path,
+    write_mode: &WriteMode,
+    filter: &FileFilter,
+) -> Result<CopySummary> {
+    let file_name = source_file
+        .file_name()
+        .with_context(|| format!("template has no file name: {}", source_file.display()))?;
+    let relative = PathBuf::from(file_name);
+    let mut summary = CopySummary::default();
+    if let Some(reason) = filter.skip_reason(source_file)? {
+        utilities::warn(format_args!("skipping {}: {}", relative.display(), reason));
+        summary.skipped.push(relative);
+        return Ok(summary);
+    }
+    fs::create_dir_all(dest_dir)
+        .with_context(|| format!("failed to create destination: {}", dest_dir.display()))?;
+    let dest_path = dest_dir.join(file_name);
+
+    let existed = dest_path.exists();
+    if existed {
+        let overwrite = match write_mode {
+            WriteMode::Strict | WriteMode::Overwrite => true,
+            WriteMode::NoOverwrite => {
+                return Err(TemplativeError::FilesWouldBeOverwritten { paths: vec![dest_path] }.into());
+            }
+            WriteMode::SkipOverwrite => false,
+            WriteMode::Ask => match prompt_file(&dest_path, Incoming::File(source_file))? {
+                FileChoice::Overwrite => true,
+                FileChoice::Skip => false,
+                FileChoice::OverwriteAll => {
+                    summary.escalated_mode = Some(WriteMode::Overwrite);
+                    true
+                }
+                FileChoice::SkipAll => {
+                    summary.escalated_mode = Some(WriteMode::SkipOverwrite);
+                    false
+                }
+                FileChoice::Abort => anyhow::bail!("aborted by user"),
+            },
+        };
+        if !overwrite {
+            summary.skipped.push(relative);
+            return Ok(summary);
+        }
+    }
+
+    fs::copy(source_file, &dest_path)
+        .with_context(|| format!("failed to copy {} -> {}", source_file.display(), dest_path.display()))?;
+    if !copy_permissions(source_file, &dest_path) {
+        warn_lost_executable_bits(std::slice::from_ref(&relative));
+    }
+    summary.record(relative, existed);
+    Ok(summary)
+}
+
+/// How `copy_template` treats what it finds, beyond exclusion and collisions.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    pub symlink_mode: SymlinkMode,
+    pub symlink_fallback: SymlinkFallback,
+    pub follow_symlinks: bool,
+    pub filter: FileFilter,
+    /// Create directories even when nothing is copied into them. Parents of copied files are
+    /// created either way.
+    pub keep_empty_dirs: bool,
+    /// Destination renames keyed by template-relative path (`/`-separated), e.g. `gitignore`
+    /// to `.gitignore`. Renaming a directory moves everything under it.
+    pub renames: BTreeMap<String, String>,
+    /// Values for tokens in destination file and folder names, e.g. `project` for a
+    /// `{{project}}.toml` with `PathTokenStyle::Braces` (or `__project__.toml` with
+    /// `Underscores`). Ignored while the style is `Off`.
+    pub path_tokens: BTreeMap<String, String>,
+    pub path_token_style: PathTokenStyle,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            symlink_mode: SymlinkMode::default(),
+            symlink_fallback: SymlinkFallback::default(),
+            follow_symlinks: false,
+            filter: FileFilter::default(),
+            keep_empty_dirs: true,
+            renames: BTreeMap::new(),
+            path_tokens: BTreeMap::new(),
+            path_token_style: PathTokenStyle::default(),
+        }
+    }
+}
+
+/// The destination path for template-relative `relative`: renamed, then with path tokens
+/// filled in. Separators in token values are replaced so a value can't add directories, and a
+/// name that fills in as empty, `.`, `..` or `.git` is refused so it can't leave the target
+/// or write into its repository.
+fn destination(relative: &Path, options: &CopyOptions) -> Result<PathBuf, TemplativeError> {
+    let relative = renamed(relative, &options.renames);
+    if options.path_tokens.is_empty() || options.path_token_style == PathTokenStyle::Off {
+        return Ok(relative);
+    }
+    relative
+        .components()
+        .map(|component| {
+            let Some(original) = component.as_os_str().to_str() else {
+                return Ok(component.as_os_str().to_os_string());
+            };
+            let mut name = original.to_string();
+            for (key, value) in &options.path_tokens {
+                if let Some(token) = options.path_token_style.token(key) {
+                    name = name.replace(&token, &value.replace(['/', '\\'], "-"));
+                }
+            }
+            if name != original && (matches!(name.as_str(), "" | "." | "..") || name.eq_ignore_ascii_case(".git")) {
+                return Err(TemplativeError::InvalidPathToken { name: original.to_string(), filled: name });
+            }
+            Ok(name.into())
+        })
+        .collect()
+}
+
+/// The destination path for template-relative `relative`, after the longest matching rename.
+fn renamed(relative: &Path, renames: &BTreeMap<String, String>) -> PathBuf {
+    if renames.is_empty() {
+        return relative.to_path_buf();
+    }
+    for prefix in relative.ancestors() {
+        let key = prefix.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>();
+        if let Some(new_prefix) = renames.get(&key.join("/")) {
+            let rest = relative.strip_prefix(prefix).unwrap_or(Path::new(""));
+            let new_prefix = PathBuf::from(new_prefix);
+            return if rest.as_os_str().is_empty() { new_prefix } else { new_prefix.join(rest) };
+        }
+    }
+    relative.to_path_buf()
+}
+
 /// Copy template from `source_dir` to `dest_dir`.
-/// `.git` is always excluded. `exclude` patterns are matched against each path
-/// component and the full relative path. Symlinks are recreated. Preserves file permissions.
+/// `.git` is always excluded. `exclude` patterns follow gitignore anchoring (see
+/// `ExcludeMatcher`). Symlinks are recreated, or with `follow_symlinks` replaced by copies of
+/// their targets (directory links are walked; loops and broken links are skipped with a
+/// warning). Files rejected by `filter` are skipped with a warning and listed as skipped, and
+/// `renames` moves paths in the destination. Preserves file permissions.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(source = %source_dir.display(), dest = %dest_dir.display())))]
 pub fn copy_template(
     source_dir: &Path,
     dest_dir: &Path,
     exclude: &[String],
     write_mode: &WriteMode,
-) -> Result<()> {
+    options: &CopyOptions,
+) -> Result<CopySummary> {
+    let CopyOptions {
+        ref symlink_mode,
+        ref symlink_fallback,
+        follow_symlinks,
+        ref filter,
+        keep_empty_dirs,
+        ..
+    } = *options;
     if !source_dir.is_dir() {
         anyhow::bail!("source is not a directory: {}", source_dir.display());
     }
     fs::create_dir_all(dest_dir)
         .with_context(|| format!("failed to create destination: {}", dest_dir.display()))?;
 
-    let globset = build_globset(exclude)?;
+    let matcher = build_exclude_matcher(exclude)?;
 
     if *write_mode == WriteMode::NoOverwrite {
-        let collisions = collect_collisions(source_dir, dest_dir, &globset)?;
+        let collisions = collect_collisions(source_dir, dest_dir, &matcher, options)?;
         if !collisions.is_empty() {
             return Err(TemplativeError::FilesWouldBeOverwritten { paths: collisions }.into());
         }
@@ -213,83 +715,107 @@ pub fn copy_template(
     // `copy_mode` starts as `write_mode` and may be escalated to Overwrite or SkipOverwrite
     // for the rest of the session when the user picks an "apply to all" option.
     let mut copy_mode = write_mode.clone();
-
-    let walker = WalkDir::new(source_dir)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|entry| {
-            let path = entry.path();
-            if path == source_dir {
-                return true;
-            }
-            !should_skip_entry(entry, source_dir, &globset)
-        });
-
-    for entry in walker {
-        let entry = entry.with_context(|| "walkdir entry error")?;
+    let mut summary = CopySummary::default();
+    let mut lost_executable = Vec::new();
+    // Files and symlinks kept because the destination already had them; with those written,
+    // they tell a copy that produced nothing from one that found everything in place.
+    let mut kept_existing = 0usize;
+
+    for entry in walk_template(source_dir, &matcher, follow_symlinks) {
+        let Some(entry) = walk_entry(entry)? else { continue };
         let path = entry.path();
         if path == source_dir {
             continue;
         }
-        let relative = path
-            .strip_prefix(source_dir)
-            .with_context(|| "strip_prefix")?;
+        let relative = destination(path.strip_prefix(source_dir).with_context(|| "strip_prefix")?, options)?;
+        let relative = relative.as_path();
         let dest_path = dest_dir.join(relative);
 
-        if path.is_symlink() {
+        if !follow_symlinks && path.is_symlink() {
             if let Some(parent) = dest_path.parent() {
                 fs::create_dir_all(parent)
                     .with_context(|| format!("failed to create parent: {}", parent.display()))?;
             }
-            if dest_path.symlink_metadata().is_ok() {
+            let existed = dest_path.symlink_metadata().is_ok();
+            if existed {
                 match copy_mode {
                     WriteMode::Strict | WriteMode::Overwrite | WriteMode::NoOverwrite => {
                         fs::remove_file(&dest_path)
                             .with_context(|| format!("failed to remove existing: {}", dest_path.display()))?;
                     }
-                    WriteMode::SkipOverwrite => continue,
-                    WriteMode::Ask => match prompt_file(&dest_path)? {
+                    WriteMode::SkipOverwrite => {
+                        summary.skipped.push(relative.to_path_buf());
+                        kept_existing += 1;
+                        continue;
+                    }
+                    WriteMode::Ask => match prompt_file(&dest_path, Incoming::Symlink)? {
                         FileChoice::Overwrite => {
                             fs::remove_file(&dest_path).ok();
                         }
-                        FileChoice::Skip => continue,
+                        FileChoice::Skip => {
+                            summary.skipped.push(relative.to_path_buf());
+                            kept_existing += 1;
+                            continue;
+                        }
                         FileChoice::OverwriteAll => {
                             copy_mode = WriteMode::Overwrite;
                             fs::remove_file(&dest_path).ok();
                         }
                         FileChoice::SkipAll => {
                             copy_mode = WriteMode::SkipOverwrite;
+                            summary.skipped.push(relative.to_path_buf());
+                            kept_existing += 1;
                             continue;
                         }
                         FileChoice::Abort => anyhow::bail!("aborted by user"),
                     },
                 }
             }
-            copy_symlink(path, &dest_path, source_dir, dest_dir)?;
+            copy_symlink(path, &dest_path, source_dir, dest_dir, symlink_mode, symlink_fallback)?;
+            summary.record(relative.to_path_buf(), existed);
             continue;
         }
 
         if entry.file_type().is_dir() {
+            if !keep_empty_dirs {
+                continue;
+            }
             fs::create_dir_all(&dest_path)
                 .with_context(|| format!("failed to create dir: {}", dest_path.display()))?;
         } else if entry.file_type().is_file() {
+            if let Some(reason) = filter.skip_reason(path)? {
+                utilities::warn(format_args!("skipping {}: {}", relative.display(), reason));
+                summary.skipped.push(relative.to_path_buf());
+                continue;
+            }
             if let Some(parent) = dest_path.parent() {
                 fs::create_dir_all(parent)
                     .with_context(|| format!("failed to create parent: {}", parent.display()))?;
             }
 
-            if dest_path.exists() {
+            let existed = dest_path.exists();
+            if existed {
                 match copy_mode {
                     WriteMode::Strict | WriteMode::Overwrite | WriteMode::NoOverwrite => {}
-                    WriteMode::SkipOverwrite => continue,
-                    WriteMode::Ask => match prompt_file(&dest_path)? {
+                    WriteMode::SkipOverwrite => {
+                        summary.skipped.push(relative.to_path_buf());
+                        kept_existing += 1;
+                        continue;
+                    }
+                    WriteMode::Ask => match prompt_file(&dest_path, Incoming::File(path))? {
                         FileChoice::Overwrite => {}
-                        FileChoice::Skip => continue,
+                        FileChoice::Skip => {
+                            summary.skipped.push(relative.to_path_buf());
+                            kept_existing += 1;
+                            continue;
+                        }
                         FileChoice::OverwriteAll => {
                             copy_mode = WriteMode::Overwrite;
                         }
                         FileChoice::SkipAll => {
                             copy_mode = WriteMode::SkipOverwrite;
+                            summary.skipped.push(relative.to_path_buf());
+                            kept_existing += 1;
                             continue;
                         }
                         FileChoice::Abort => anyhow::bail!("aborted by user"),
@@ -299,13 +825,72 @@ pub fn copy_template(
 
             fs::copy(path, &dest_path)
                 .with_context(|| format!("failed to copy {} -> {}", path.display(), dest_path.display()))?;
-            if let Ok(metadata) = fs::metadata(path) {
-                let _ = fs::set_permissions(&dest_path, metadata.permissions());
+            if !copy_permissions(path, &dest_path) {
+                lost_executable.push(relative.to_path_buf());
             }
+            summary.record(relative.to_path_buf(), existed);
         }
     }
 
-    Ok(())
+    warn_lost_executable_bits(&lost_executable);
+    if copy_mode != *write_mode {
+        summary.escalated_mode = Some(copy_mode);
+    }
+    if summary.written().is_empty() && kept_existing == 0 && has_files(source_dir)? {
+        return Err(TemplativeError::NothingCopied { source_path: source_dir.to_path_buf() }.into());
+    }
+    Ok(summary)
+}
+
+/// A `sha256:<hex>` hash of the tree at `root` (or of a single file), stable across machines:
+/// every file and symlink outside `.git`, in sorted order, contributes its `/`-separated
+/// relative path, its kind (file or symlink) and its contents (a symlink its target). File
+/// modes are left out, since there are none to read off unix. With `follow_symlinks` links are
+/// hashed as what they point to, the way `copy_template` copies them. Exclude patterns aren't
+/// applied, so the hash covers the source as fetched.
+pub fn tree_checksum(root: &Path, follow_symlinks: bool) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    let walker = WalkDir::new(root)
+        .follow_links(follow_symlinks)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.path() == root || entry.file_name() != ".git");
+    for entry in walker {
+        let Some(entry) = walk_entry(entry)? else { continue };
+        let (kind, contents): (&[u8], _) = if entry.file_type().is_symlink() {
+            let target = fs::read_link(entry.path())
+                .with_context(|| format!("failed to read link {}", entry.path().display()))?;
+            (b"link", target.to_string_lossy().into_owned().into_bytes())
+        } else if entry.file_type().is_file() {
+            let contents =
+                fs::read(entry.path()).with_context(|| format!("failed to read {}", entry.path().display()))?;
+            (b"file", contents)
+        } else {
+            continue;
+        };
+        let relative = entry.path().strip_prefix(root).with_context(|| "strip_prefix")?;
+        let relative: Vec<_> = relative.components().map(|part| part.as_os_str().to_string_lossy()).collect();
+        // Length-prefixed so no two trees can feed the hasher the same bytes.
+        for field in [relative.join("/").as_bytes(), kind, contents.as_slice()] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
+    }
+    Ok(format!("sha256:{:x}", hasher.finalize()))
+}
+
+/// Whether `dir` holds any file or symlink outside `.git`, at any depth.
+fn has_files(dir: &Path) -> Result<bool> {
+    let walker = WalkDir::new(dir).into_iter().filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry.with_context(|| format!("failed to walk {}", dir.display()))?;
+        if !entry.file_type().is_dir() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
 }
 
 #[cfg(test)]
@@ -339,7 +924,7 @@ mod tests {
         fs::create_dir_all(&source).unwrap();
         create_template_structure(&source);
 
-        copy_template(&source, &dest, &default_exclude(), &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &default_exclude(), &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         assert!(dest.join("src/main.rs").exists());
         assert!(dest.join("Cargo.toml").exists());
@@ -360,7 +945,7 @@ mod tests {
         fs::write(source.join("file.txt"), "content").unwrap();
         std::os::unix::fs::symlink("file.txt", source.join("link.txt")).unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         assert!(dest.join("file.txt").exists());
         let link_target = fs::read_link(dest.join("link.txt")).unwrap();
@@ -368,6 +953,59 @@ mod tests {
         assert_eq!(fs::read_to_string(dest.join("link.txt")).unwrap(), "content");
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn follow_symlinks_copies_file_contents() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        let shared = temp.path().join("shared.toml");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(&shared, "shared").unwrap();
+        std::os::unix::fs::symlink(&shared, source.join("config.toml")).unwrap();
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions { follow_symlinks: true, ..CopyOptions::default() }).unwrap();
+
+        assert!(!dest.join("config.toml").is_symlink());
+        assert_eq!(fs::read_to_string(dest.join("config.toml")).unwrap(), "shared");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn follow_symlinks_copies_directory_links_recursively() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        let shared = temp.path().join("shared");
+        fs::create_dir_all(shared.join("nested")).unwrap();
+        fs::create_dir_all(&source).unwrap();
+        fs::write(shared.join("nested/file.txt"), "deep").unwrap();
+        std::os::unix::fs::symlink(&shared, source.join("linked")).unwrap();
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions { follow_symlinks: true, ..CopyOptions::default() }).unwrap();
+
+        assert!(!dest.join("linked").is_symlink());
+        assert_eq!(fs::read_to_string(dest.join("linked/nested/file.txt")).unwrap(), "deep");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn follow_symlinks_skips_loops_and_broken_links() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub/file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink("..", source.join("sub/loop")).unwrap();
+        std::os::unix::fs::symlink("missing.txt", source.join("broken.txt")).unwrap();
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions { follow_symlinks: true, ..CopyOptions::default() }).unwrap();
+
+        assert_eq!(fs::read_to_string(dest.join("sub/file.txt")).unwrap(), "content");
+        assert!(!dest.join("sub/loop").exists());
+        assert!(dest.join("broken.txt").symlink_metadata().is_err());
+    }
+
     #[test]
     #[cfg(unix)]
     fn broken_symlink_creates_with_original_target() {
@@ -377,7 +1015,7 @@ mod tests {
         fs::create_dir_all(&source).unwrap();
         std::os::unix::fs::symlink("nonexistent.txt", source.join("broken.txt")).unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         let link_target = fs::read_link(dest.join("broken.txt")).unwrap();
         assert_eq!(link_target, Path::new("nonexistent.txt"));
@@ -386,6 +1024,99 @@ mod tests {
         assert!(dest.join("broken.txt").symlink_metadata().is_ok());
     }
 
+    #[test]
+    fn fallback_copy_target_writes_file_contents() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("real.txt"), "content").unwrap();
+        let dest = temp.path().join("copied.txt");
+        apply_symlink_fallback(
+            &SymlinkFallback::CopyTarget,
+            &temp.path().join("link.txt"),
+            &temp.path().join("real.txt"),
+            &dest,
+        )
+        .unwrap();
+        assert!(!dest.is_symlink());
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "content");
+    }
+
+    #[test]
+    fn fallback_copy_target_skips_broken_link() {
+        let temp = tempfile::tempdir().unwrap();
+        let dest = temp.path().join("copied.txt");
+        apply_symlink_fallback(
+            &SymlinkFallback::CopyTarget,
+            &temp.path().join("link.txt"),
+            &temp.path().join("missing.txt"),
+            &dest,
+        )
+        .unwrap();
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn fallback_skip_writes_nothing() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("real.txt"), "content").unwrap();
+        let dest = temp.path().join("copied.txt");
+        apply_symlink_fallback(
+            &SymlinkFallback::Skip,
+            &temp.path().join("link.txt"),
+            &temp.path().join("real.txt"),
+            &dest,
+        )
+        .unwrap();
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn fallback_error_bails() {
+        let temp = tempfile::tempdir().unwrap();
+        let result = apply_symlink_fallback(
+            &SymlinkFallback::Error,
+            &temp.path().join("link.txt"),
+            &temp.path().join("real.txt"),
+            &temp.path().join("copied.txt"),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn absolute_symlink_to_template_root_points_at_dest_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(&source, source.join("root")).unwrap();
+        std::os::unix::fs::symlink(&source, source.join("sub/up")).unwrap();
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
+
+        assert_eq!(fs::read_link(dest.join("root")).unwrap(), Path::new("."));
+        assert_eq!(fs::read_link(dest.join("sub/up")).unwrap(), Path::new(".."));
+        let dest_root = dest.canonicalize().unwrap();
+        assert_eq!(dest.join("root").canonicalize().unwrap(), dest_root);
+        assert_eq!(dest.join("sub/up").canonicalize().unwrap(), dest_root);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dot_symlink_is_preserved() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(".", source.join("here")).unwrap();
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
+
+        assert_eq!(fs::read_link(dest.join("here")).unwrap(), Path::new("."));
+        assert_eq!(fs::read_to_string(dest.join("here/file.txt")).unwrap(), "content");
+    }
+
     #[test]
     #[cfg(unix)]
     fn symlink_outside_template_becomes_absolute() {
@@ -397,13 +1128,153 @@ mod tests {
         fs::create_dir_all(&source).unwrap();
         std::os::unix::fs::symlink(&external, source.join("link.txt")).unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         let link_target = fs::read_link(dest.join("link.txt")).unwrap();
         assert!(link_target.is_absolute());
         assert_eq!(fs::read_to_string(dest.join("link.txt")).unwrap(), "external content");
     }
 
+    /// A template with `inner` linking to its own `file.txt` by absolute path and `outer`
+    /// linking to a file beside it, copied with `mode`. Returns the temp dir and destination.
+    #[cfg(unix)]
+    fn copy_links_with_mode(mode: SymlinkMode) -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::write(root.join("external.txt"), "external content").unwrap();
+        let source = root.join("template");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(source.join("file.txt"), source.join("sub/inner")).unwrap();
+        std::os::unix::fs::symlink(root.join("external.txt"), source.join("outer")).unwrap();
+        let dest = root.join("dest");
+        let options = CopyOptions { symlink_mode: mode, ..CopyOptions::default() };
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &options).unwrap();
+        (temp, dest)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn relative_symlink_mode_makes_every_link_relative() {
+        let (_temp, dest) = copy_links_with_mode(SymlinkMode::Relative);
+        assert_eq!(fs::read_link(dest.join("sub/inner")).unwrap(), Path::new("../file.txt"));
+        assert_eq!(fs::read_link(dest.join("outer")).unwrap(), Path::new("../external.txt"));
+        assert_eq!(fs::read_to_string(dest.join("outer")).unwrap(), "external content");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn absolute_symlink_mode_points_inside_links_at_their_copies() {
+        let (temp, dest) = copy_links_with_mode(SymlinkMode::Absolute);
+        assert_eq!(fs::read_link(dest.join("sub/inner")).unwrap(), dest.join("file.txt"));
+        let external = temp.path().canonicalize().unwrap().join("external.txt");
+        assert_eq!(fs::read_link(dest.join("outer")).unwrap(), external);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn preserve_raw_symlink_mode_copies_link_text_unchanged() {
+        let (temp, dest) = copy_links_with_mode(SymlinkMode::PreserveRaw);
+        let root = temp.path().canonicalize().unwrap();
+        assert_eq!(fs::read_link(dest.join("sub/inner")).unwrap(), root.join("template/file.txt"));
+        assert_eq!(fs::read_link(dest.join("outer")).unwrap(), root.join("external.txt"));
+    }
+
+    #[test]
+    fn measure_template_skips_git_and_excluded_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::create_dir_all(source.join("node_modules")).unwrap();
+        fs::write(source.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        fs::write(source.join("README.md"), "hello").unwrap();
+        fs::write(source.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(source.join("node_modules/big.js"), "x".repeat(1000)).unwrap();
+
+        let options = CopyOptions::default();
+        let measured = measure_template(source, &["node_modules".to_string()], &options).unwrap();
+        assert_eq!(measured, (17, 2));
+        assert_eq!(measure_template(&source.join("README.md"), &[], &options).unwrap(), (5, 1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn measure_template_follows_links_and_applies_the_filter_like_copy_template() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("small.txt"), "hi").unwrap();
+        fs::write(source.join("big.txt"), "x".repeat(100)).unwrap();
+        fs::write(temp.path().join("outside.txt"), "linked").unwrap();
+        std::os::unix::fs::symlink(temp.path().join("outside.txt"), source.join("link.txt")).unwrap();
+
+        let options = CopyOptions {
+            filter: FileFilter { max_file_size: Some(10), exclude_binaries: false },
+            ..CopyOptions::default()
+        };
+        assert_eq!(measure_template(&source, &[], &options).unwrap(), (2, 2));
+        let following = CopyOptions { follow_symlinks: true, ..options };
+        assert_eq!(measure_template(&source, &[], &following).unwrap(), (8, 2));
+
+        let dest = temp.path().join("dest");
+        let summary = copy_template(&source, &dest, &[], &WriteMode::Strict, &following).unwrap();
+        assert_eq!(summary.created.len(), 2);
+    }
+
+    #[test]
+    fn path_tokens_fill_names_in_the_configured_style() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("__name__")).unwrap();
+        fs::write(source.join("__name__/main.rs"), "fn main() {}").unwrap();
+        fs::write(source.join("{{name}}.toml"), "").unwrap();
+        let options = CopyOptions {
+            path_tokens: BTreeMap::from([("name".to_string(), "app".to_string())]),
+            path_token_style: PathTokenStyle::Underscores,
+            ..CopyOptions::default()
+        };
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &options).unwrap();
+
+        assert_eq!(fs::read_to_string(dest.join("app/main.rs")).unwrap(), "fn main() {}");
+        // Only the configured style is a token.
+        assert!(dest.join("{{name}}.toml").exists());
+
+        // Tokens are off unless a style is chosen.
+        let dest = temp.path().join("untouched");
+        let options = CopyOptions { path_token_style: PathTokenStyle::default(), ..options };
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &options).unwrap();
+        assert!(dest.join("__name__/main.rs").exists());
+        assert!(dest.join("{{name}}.toml").exists());
+    }
+
+    #[test]
+    fn path_tokens_that_fill_in_as_special_names_are_refused() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(source.join("__name__")).unwrap();
+        fs::write(source.join("__name__/config"), "").unwrap();
+        for value in ["", ".", "..", ".git", ".GIT"] {
+            let dest = temp.path().join("dest");
+            let options = CopyOptions {
+                path_tokens: BTreeMap::from([("name".to_string(), value.to_string())]),
+                path_token_style: PathTokenStyle::Underscores,
+                ..CopyOptions::default()
+            };
+            let err = copy_template(&source, &dest, &[], &WriteMode::Overwrite, &options).unwrap_err();
+            assert!(
+                matches!(err.downcast_ref(), Some(TemplativeError::InvalidPathToken { .. })),
+                "{:?}: {}",
+                value,
+                err
+            );
+            assert!(!temp.path().join("config").exists());
+            assert!(!dest.join(".git/config").exists());
+        }
+    }
+
     #[test]
     fn glob_pattern_excludes_matching_files() {
         let temp = tempfile::tempdir().unwrap();
@@ -414,7 +1285,7 @@ mod tests {
         fs::write(source.join("debug.log"), "log content").unwrap();
         fs::write(source.join("error.log"), "error content").unwrap();
 
-        copy_template(&source, &dest, &["*.log".into()], &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &["*.log".into()], &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         assert!(dest.join("main.rs").exists());
         assert!(!dest.join("debug.log").exists());
@@ -430,12 +1301,405 @@ mod tests {
         fs::write(source.join("index.html"), "hello").unwrap();
         fs::write(source.join("dist/bundle.js"), "bundle").unwrap();
 
-        copy_template(&source, &dest, &["dist".into()], &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &["dist".into()], &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         assert!(dest.join("index.html").exists());
         assert!(!dest.join("dist").exists());
     }
 
+    #[test]
+    fn summary_reports_created_overwritten_and_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(source.join("new.txt"), "new").unwrap();
+        fs::write(source.join("existing.txt"), "template").unwrap();
+        fs::write(dest.join("existing.txt"), "user").unwrap();
+
+        let summary = copy_template(&source, &dest, &[], &WriteMode::Overwrite, &CopyOptions::default()).unwrap();
+        assert_eq!(summary.created, vec![PathBuf::from("new.txt")]);
+        assert_eq!(summary.overwritten, vec![PathBuf::from("existing.txt")]);
+        assert!(summary.skipped.is_empty());
+
+        fs::write(dest.join("existing.txt"), "user").unwrap();
+        fs::remove_file(dest.join("new.txt")).unwrap();
+        let summary = copy_template(&source, &dest, &[], &WriteMode::SkipOverwrite, &CopyOptions::default()).unwrap();
+        assert_eq!(summary.created, vec![PathBuf::from("new.txt")]);
+        assert!(summary.overwritten.is_empty());
+        assert_eq!(summary.skipped, vec![PathBuf::from("existing.txt")]);
+        assert!(summary.escalated_mode.is_none());
+    }
+
+    #[test]
+    fn render_diff_shows_unified_diff_for_text() {
+        let diff = render_diff(b"one\ntwo\n", b"one\nthree\n");
+        assert!(diff.contains("--- existing"));
+        assert!(diff.contains("+++ incoming"));
+        assert!(diff.contains("-two"));
+        assert!(diff.contains("+three"));
+    }
+
+    #[test]
+    fn render_diff_reports_sizes_for_binary_files() {
+        let diff = render_diff(b"\x00\x01\x02", b"text");
+        assert_eq!(diff, "binary files differ (existing: 3 bytes, incoming: 4 bytes)\n");
+    }
+
+    #[test]
+    fn diff_against_describes_a_destination_it_cannot_read() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("source.txt");
+        fs::write(&source, "incoming\n").unwrap();
+        let directory = temp.path().join("dir");
+        fs::create_dir(&directory).unwrap();
+        assert_eq!(diff_against(&directory, &source).unwrap(), "existing entry is a directory\n");
+
+        let missing = temp.path().join("missing");
+        assert!(diff_against(&missing, &source).unwrap().starts_with("existing entry is not readable"));
+    }
+
+    #[test]
+    fn copy_single_file_into_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join(".editorconfig");
+        let dest = temp.path().join("dest");
+        fs::write(&source, "root = true").unwrap();
+
+        let summary = copy_single_file(&source, &dest, &WriteMode::Strict, &FileFilter::default()).unwrap();
+
+        assert_eq!(fs::read_to_string(dest.join(".editorconfig")).unwrap(), "root = true");
+        assert_eq!(summary.created, vec![PathBuf::from(".editorconfig")]);
+    }
+
+    #[test]
+    fn copy_single_file_applies_the_filter() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("tool");
+        let dest = temp.path().join("dest");
+        fs::write(&source, b"\x7fELF\0\0").unwrap();
+
+        let binaries = FileFilter { max_file_size: None, exclude_binaries: true };
+        let summary = copy_single_file(&source, &dest, &WriteMode::Strict, &binaries).unwrap();
+
+        assert!(!dest.join("tool").exists());
+        assert_eq!(summary.skipped, vec![PathBuf::from("tool")]);
+        assert!(summary.created.is_empty());
+    }
+
+    #[test]
+    fn copy_single_file_respects_write_mode() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("file.txt");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(&source, "template").unwrap();
+        fs::write(dest.join("file.txt"), "user").unwrap();
+
+        let summary = copy_single_file(&source, &dest, &WriteMode::SkipOverwrite, &FileFilter::default()).unwrap();
+        assert_eq!(summary.skipped, vec![PathBuf::from("file.txt")]);
+        assert_eq!(fs::read_to_string(dest.join("file.txt")).unwrap(), "user");
+
+        let result = copy_single_file(&source, &dest, &WriteMode::NoOverwrite, &FileFilter::default());
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::FilesWouldBeOverwritten { .. })
+        ));
+
+        copy_single_file(&source, &dest, &WriteMode::Overwrite, &FileFilter::default()).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("file.txt")).unwrap(), "template");
+    }
+
+    #[test]
+    fn unanchored_pattern_excludes_at_any_depth() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("config")).unwrap();
+        fs::create_dir_all(source.join("src/config")).unwrap();
+        fs::write(source.join("config/root.toml"), "").unwrap();
+        fs::write(source.join("src/config/nested.toml"), "").unwrap();
+        fs::write(source.join("src/main.rs"), "").unwrap();
+
+        copy_template(&source, &dest, &["config".into()], &WriteMode::Strict, &CopyOptions::default()).unwrap();
+
+        assert!(!dest.join("config").exists());
+        assert!(!dest.join("src/config").exists());
+        assert!(dest.join("src/main.rs").exists());
+    }
+
+    #[test]
+    fn leading_slash_anchors_pattern_to_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("config")).unwrap();
+        fs::create_dir_all(source.join("src/config")).unwrap();
+        fs::write(source.join("config/root.toml"), "").unwrap();
+        fs::write(source.join("src/config/nested.toml"), "").unwrap();
+
+        copy_template(&source, &dest, &["/config".into()], &WriteMode::Strict, &CopyOptions::default()).unwrap();
+
+        assert!(!dest.join("config").exists());
+        assert!(dest.join("src/config/nested.toml").exists());
+    }
+
+    #[test]
+    fn middle_slash_pattern_is_anchored_and_star_does_not_cross_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("src/gen")).unwrap();
+        fs::create_dir_all(source.join("lib/src")).unwrap();
+        fs::write(source.join("src/a.log"), "").unwrap();
+        fs::write(source.join("src/gen/b.log"), "").unwrap();
+        fs::write(source.join("lib/src/c.log"), "").unwrap();
+
+        copy_template(&source, &dest, &["src/*.log".into()], &WriteMode::Strict, &CopyOptions::default()).unwrap();
+
+        assert!(!dest.join("src/a.log").exists());
+        assert!(dest.join("src/gen/b.log").exists());
+        assert!(dest.join("lib/src/c.log").exists());
+    }
+
+    #[test]
+    fn invalid_pattern_is_a_typed_error() {
+        let result = build_exclude_matcher(&["src/[".to_string()]);
+        let err = result.err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::InvalidExcludePattern { pattern, .. }) if pattern == "src/["
+        ));
+        assert!(validate_exclude_patterns(&["*.log".to_string(), "a{b".to_string()]).is_err());
+        assert!(validate_exclude_patterns(&["*.log".to_string(), "/dist/".to_string()]).is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn executable_bit_is_preserved() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(source.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
+
+        let mode = fs::metadata(dest.join("run.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert!(copy_permissions(&source.join("run.sh"), &dest.join("run.sh")));
+    }
+
+    #[test]
+    fn excluding_everything_is_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::write(source.join("README.md"), "hello").unwrap();
+        fs::write(source.join("src/lib.rs"), "").unwrap();
+
+        let exclude = ["*".to_string()];
+        let result = copy_template(&source, &dest, &exclude, &WriteMode::Strict, &CopyOptions::default());
+
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::NothingCopied { .. })
+        ));
+    }
+
+    #[test]
+    fn excludes_that_leave_only_directories_are_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::write(source.join("src/lib.rs"), "").unwrap();
+
+        let exclude = ["*.rs".to_string()];
+        for keep_empty_dirs in [true, false] {
+            let options = CopyOptions { keep_empty_dirs, ..CopyOptions::default() };
+            let dest = temp.path().join(format!("dest-{}", keep_empty_dirs));
+            let result = copy_template(&source, &dest, &exclude, &WriteMode::Strict, &options);
+            assert!(matches!(
+                result.unwrap_err().downcast_ref::<TemplativeError>(),
+                Some(TemplativeError::NothingCopied { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn a_filter_that_skips_every_file_is_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("big.txt"), "x".repeat(100)).unwrap();
+
+        let options = CopyOptions {
+            filter: FileFilter { max_file_size: Some(10), exclude_binaries: false },
+            ..CopyOptions::default()
+        };
+        let result = copy_template(&source, &temp.path().join("dest"), &[], &WriteMode::Strict, &options);
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::NothingCopied { .. })
+        ));
+    }
+
+    #[test]
+    fn files_already_in_place_are_not_nothing_copied() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(source.join("file.txt"), "template").unwrap();
+        fs::write(dest.join("file.txt"), "mine").unwrap();
+
+        let summary = copy_template(&source, &dest, &[], &WriteMode::SkipOverwrite, &CopyOptions::default()).unwrap();
+        assert_eq!(summary.skipped, vec![PathBuf::from("file.txt")]);
+    }
+
+    #[test]
+    fn file_filter_skips_large_and_binary_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("fixture.bin"), vec![b'x'; 10 * 1024 * 1024]).unwrap();
+        fs::write(source.join("small.txt"), "hello").unwrap();
+        fs::write(source.join("tool"), b"\x7fELF\0\0").unwrap();
+
+        let size_only = FileFilter { max_file_size: Some(1024 * 1024), exclude_binaries: false };
+        let options = CopyOptions { filter: size_only, ..CopyOptions::default() };
+        let summary = copy_template(&source, &dest, &[], &WriteMode::Strict, &options).unwrap();
+        assert_eq!(summary.skipped, vec![PathBuf::from("fixture.bin")]);
+        assert!(!dest.join("fixture.bin").exists());
+        assert!(dest.join("small.txt").exists());
+        assert!(dest.join("tool").exists());
+
+        let dest = temp.path().join("dest2");
+        let binaries = FileFilter { max_file_size: None, exclude_binaries: true };
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions { filter: binaries, ..CopyOptions::default() }).unwrap();
+        assert!(!dest.join("tool").exists());
+        assert!(dest.join("fixture.bin").exists());
+        assert!(dest.join("small.txt").exists());
+    }
+
+    #[test]
+    fn renames_move_files_and_directories_in_the_destination() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("dot-config")).unwrap();
+        fs::write(source.join("dot-gitignore"), "target/\n").unwrap();
+        fs::write(source.join("dot-config/settings.toml"), "").unwrap();
+        fs::write(source.join("README.md"), "").unwrap();
+
+        let renames = BTreeMap::from([
+            ("dot-gitignore".to_string(), ".gitignore".to_string()),
+            ("dot-config".to_string(), ".config".to_string()),
+        ]);
+        let options = CopyOptions { renames, ..CopyOptions::default() };
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &options).unwrap();
+        assert_eq!(fs::read_to_string(dest.join(".gitignore")).unwrap(), "target/\n");
+        assert!(dest.join(".config/settings.toml").is_file());
+        assert!(dest.join("README.md").is_file());
+        assert!(!dest.join("dot-gitignore").exists());
+        assert!(!dest.join("dot-config").exists());
+
+        // The pre-flight for NoOverwrite looks at the renamed paths.
+        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite, &options);
+        let err = result.unwrap_err();
+        let Some(TemplativeError::FilesWouldBeOverwritten { paths }) = err.downcast_ref() else { panic!("{err}") };
+        assert!(paths.contains(&dest.join(".gitignore")));
+    }
+
+    #[test]
+    fn empty_directories_are_kept_unless_disabled() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(source.join("logs")).unwrap();
+        fs::create_dir_all(source.join("cache")).unwrap();
+        fs::write(source.join("cache/.gitkeep"), "").unwrap();
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::write(source.join("src/main.rs"), "fn main() {}").unwrap();
+        let exclude = vec![".gitkeep".to_string()];
+
+        let dest = temp.path().join("kept");
+        copy_template(&source, &dest, &exclude, &WriteMode::Strict, &CopyOptions::default()).unwrap();
+        assert!(dest.join("logs").is_dir());
+        assert!(dest.join("cache").is_dir());
+        assert!(!dest.join("cache/.gitkeep").exists());
+
+        let dest = temp.path().join("pruned");
+        let options = CopyOptions { keep_empty_dirs: false, ..CopyOptions::default() };
+        copy_template(&source, &dest, &exclude, &WriteMode::Strict, &options).unwrap();
+        assert!(!dest.join("logs").exists());
+        assert!(!dest.join("cache").exists());
+        assert!(dest.join("src/main.rs").is_file());
+    }
+
+    #[test]
+    fn tree_checksum_tracks_paths_and_contents_but_not_git() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        create_template_structure(&source);
+        let original = tree_checksum(&source, false).unwrap();
+        assert!(original.starts_with("sha256:"));
+
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        assert_eq!(tree_checksum(&source, false).unwrap(), original);
+
+        fs::write(source.join("src/main.rs"), "fn main() { println!(); }").unwrap();
+        let edited = tree_checksum(&source, false).unwrap();
+        assert_ne!(edited, original);
+
+        fs::rename(source.join("src/main.rs"), source.join("src/lib.rs")).unwrap();
+        assert_ne!(tree_checksum(&source, false).unwrap(), edited);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tree_checksum_ignores_the_executable_bit_and_tracks_followed_link_targets() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::write(temp.path().join("shared.txt"), "v1").unwrap();
+        std::os::unix::fs::symlink(temp.path().join("shared.txt"), source.join("shared.txt")).unwrap();
+
+        let plain = tree_checksum(&source, false).unwrap();
+        fs::set_permissions(source.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(tree_checksum(&source, false).unwrap(), plain);
+
+        let followed = tree_checksum(&source, true).unwrap();
+        let unfollowed = tree_checksum(&source, false).unwrap();
+        fs::write(temp.path().join("shared.txt"), "v2").unwrap();
+        assert_ne!(tree_checksum(&source, true).unwrap(), followed);
+        assert_eq!(tree_checksum(&source, false).unwrap(), unfollowed);
+    }
+
+    #[test]
+    fn empty_source_is_not_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("template");
+        fs::create_dir_all(source.join(".git")).unwrap();
+        let summary = copy_template(
+            &source,
+            &temp.path().join("dest"),
+            &[],
+            &WriteMode::Strict,
+            &CopyOptions::default(),
+        )
+        .unwrap();
+        assert!(summary.written().is_empty());
+    }
+
     #[test]
     fn git_always_excluded_with_empty_exclude_list() {
         let temp = tempfile::tempdir().unwrap();
@@ -445,7 +1709,7 @@ mod tests {
         fs::write(source.join("file.txt"), "content").unwrap();
         fs::write(source.join(".git/config"), "[core]").unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::Strict).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::Strict, &CopyOptions::default()).unwrap();
 
         assert!(dest.join("file.txt").exists());
         assert!(!dest.join(".git").exists());
@@ -461,7 +1725,7 @@ mod tests {
         fs::write(source.join("file.txt"), "new content").unwrap();
         fs::write(dest.join("file.txt"), "original content").unwrap();
 
-        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite);
+        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite, &CopyOptions::default());
 
         assert!(result.is_err());
         assert!(matches!(
@@ -482,7 +1746,7 @@ mod tests {
         fs::write(source.join("collision.txt"), "new content").unwrap();
         fs::write(dest.join("collision.txt"), "original").unwrap();
 
-        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite);
+        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite, &CopyOptions::default());
 
         assert!(result.is_err());
         // new.txt must not have been written — error was raised before any writes
@@ -500,7 +1764,7 @@ mod tests {
         fs::write(source.join("new.txt"), "brand new").unwrap();
         fs::write(dest.join("existing.txt"), "original content").unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::SkipOverwrite).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::SkipOverwrite, &CopyOptions::default()).unwrap();
 
         assert_eq!(fs::read_to_string(dest.join("existing.txt")).unwrap(), "original content");
         assert_eq!(fs::read_to_string(dest.join("new.txt")).unwrap(), "brand new");
@@ -516,7 +1780,7 @@ mod tests {
         fs::write(source.join("file.txt"), "new content").unwrap();
         fs::write(dest.join("file.txt"), "original content").unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::Overwrite).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::Overwrite, &CopyOptions::default()).unwrap();
 
         assert_eq!(fs::read_to_string(dest.join("file.txt")).unwrap(), "new content");
     }
@@ -533,7 +1797,7 @@ mod tests {
         std::os::unix::fs::symlink("file.txt", source.join("link.txt")).unwrap();
         std::os::unix::fs::symlink("file.txt", dest.join("link.txt")).unwrap();
 
-        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite);
+        let result = copy_template(&source, &dest, &[], &WriteMode::NoOverwrite, &CopyOptions::default());
 
         assert!(result.is_err());
         assert!(matches!(
@@ -555,7 +1819,7 @@ mod tests {
         // Existing symlink points elsewhere
         std::os::unix::fs::symlink("other.txt", dest.join("link.txt")).unwrap();
 
-        copy_template(&source, &dest, &[], &WriteMode::SkipOverwrite).unwrap();
+        copy_template(&source, &dest, &[], &WriteMode::SkipOverwrite, &CopyOptions::default()).unwrap();
 
         assert_eq!(fs::read_link(dest.join("link.txt")).unwrap(), Path::new("other.txt"));
     }
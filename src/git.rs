@@ -1,8 +1,28 @@
 use std::path::Path;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 
+use crate::errors::TemplativeError;
+use crate::utilities;
+
+/// Whether `git --version` ran, checked once per process.
+static GIT_AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+/// Fails with `GitNotInstalled` when there's no `git` to run, so commands that need it say so
+/// up front instead of failing on their first git call. Other spawn errors pass, and are
+/// reported by that call.
+pub fn ensure_available() -> Result<(), TemplativeError> {
+    let available = *GIT_AVAILABLE.get_or_init(|| {
+        let result = Command::new("git").arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status();
+        !matches!(result, Err(ref err) if err.kind() == std::io::ErrorKind::NotFound)
+    });
+    if available { Ok(()) } else { Err(TemplativeError::GitNotInstalled) }
+}
+
 fn git_config_get(key: &str) -> Result<String> {
     let output = Command::new("git")
         .args(["config", key])
@@ -33,18 +53,172 @@ pub fn check_user_config() -> Result<()> {
     Ok(())
 }
 
-fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<()> {
-    let mut cmd = Command::new("git");
-    cmd.args(args);
-    if let Some(path) = dir {
-        cmd.current_dir(path);
+/// How git commands that talk to a remote behave; see `Config::network`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkOptions {
+    /// Extra attempts after a failure that looks like a flaky connection (`git_retries`).
+    pub retries: u32,
+    /// Stream git's progress to the terminal while cloning (`--progress`).
+    pub show_progress: bool,
+}
+
+#[cfg(not(test))]
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+#[cfg(test)]
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(1);
+
+/// Stderr fragments that indicate a flaky connection rather than a permanent failure such as
+/// a missing repository or rejected credentials.
+const TRANSIENT_ERRORS: &[&str] = &[
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "connection timed out",
+    "operation timed out",
+    "connection reset",
+    "connection refused",
+    "failed to connect",
+    "the remote end hung up unexpectedly",
+    "early eof",
+    "rpc failed",
+    "gnutls_handshake",
+    "ssl_connect",
+];
+
+fn is_transient_failure(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    TRANSIENT_ERRORS.iter().any(|fragment| stderr.contains(fragment))
+}
+
+/// Stderr fragments git prints when a remote rejects or asks for credentials.
+const AUTH_ERRORS: &[&str] = &[
+    "authentication failed",
+    "could not read username",
+    "could not read password",
+    "permission denied (publickey)",
+    "error: 403",
+    "403 forbidden",
+];
+
+fn is_auth_failure(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    AUTH_ERRORS.iter().any(|fragment| stderr.contains(fragment))
+}
+
+const AUTH_HINT: &str = "the remote rejected the credentials or needs some; for a private HTTPS \
+repository configure a credential helper (`git config --global credential.helper ...`) or use \
+an SSH URL such as git@github.com:owner/repo.git";
+
+/// Progress lines git writes to stderr while cloning and fetching, with or without a `remote: `
+/// prefix.
+const PROGRESS_PREFIXES: &[&str] = &[
+    "Cloning into",
+    "Counting objects",
+    "Compressing objects",
+    "Enumerating objects",
+    "Receiving objects",
+    "Resolving deltas",
+    "Total ",
+    "Updating files",
+    "Checking out files",
+    "Filtering content",
+    "Unpacking objects",
+];
+
+/// Boils git's stderr down to what went wrong: progress lines are dropped, and when git wrote
+/// `fatal:`/`error:` lines only those are kept, each once. The full text is logged at debug
+/// level with `--features tracing`.
+pub fn summarize_git_stderr(stderr: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    // Progress is redrawn with carriage returns, so each `\r` starts a line of its own.
+    for line in stderr.split(['\n', '\r']).map(str::trim) {
+        let message = line.strip_prefix("remote:").map(str::trim_start).unwrap_or(line);
+        if message.is_empty() || PROGRESS_PREFIXES.iter().any(|prefix| message.starts_with(prefix)) {
+            continue;
+        }
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
     }
-    let output = cmd.output().context("failed to execute git")?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git {} failed: {}", args.join(" "), stderr);
+    let errors: Vec<&str> =
+        lines.iter().copied().filter(|line| line.starts_with("fatal:") || line.starts_with("error:")).collect();
+    if !errors.is_empty() {
+        return errors.join("\n");
     }
-    Ok(())
+    let start = lines.len().saturating_sub(3);
+    lines[start..].join("\n")
+}
+
+/// Runs `program` with `args`, retrying up to `retries` times with exponential backoff when
+/// stderr looks like a network hiccup. With `echo_stderr`, git's stderr is also passed through
+/// to ours as it's written, so progress shows up live.
+fn run_git_with(program: &Path, dir: Option<&Path>, args: &[&str], retries: u32, echo_stderr: bool) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        trace_debug!(dir = ?dir, attempt, "git {}", args.join(" "));
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        if let Some(path) = dir {
+            cmd.current_dir(path);
+        }
+        let (success, stderr) = if echo_stderr {
+            run_echoing_stderr(&mut cmd)?
+        } else {
+            let output = cmd.output().context("failed to execute git")?;
+            (output.status.success(), String::from_utf8_lossy(&output.stderr).into_owned())
+        };
+        if success {
+            return Ok(());
+        }
+        trace_debug!(stderr = %stderr, "git {} failed", args.join(" "));
+        let summary = summarize_git_stderr(&stderr);
+        if attempt < retries && is_transient_failure(&stderr) {
+            let delay = RETRY_BASE_DELAY * 2u32.pow(attempt);
+            utilities::warn(format_args!(
+                "git {} failed ({}); retrying in {}ms",
+                args.join(" "),
+                summary,
+                delay.as_millis()
+            ));
+            std::thread::sleep(delay);
+            attempt += 1;
+            continue;
+        }
+        if is_auth_failure(&stderr) {
+            anyhow::bail!("git {} failed: {}\n\ngit output: {}", args.join(" "), AUTH_HINT, summary);
+        }
+        anyhow::bail!("git {} failed: {}", args.join(" "), summary);
+    }
+}
+
+/// Runs `cmd` with its stderr copied to ours as it arrives, returning whether it succeeded
+/// and everything it wrote to stderr.
+fn run_echoing_stderr(cmd: &mut Command) -> Result<(bool, String)> {
+    let mut child = cmd.stderr(Stdio::piped()).spawn().context("failed to execute git")?;
+    let mut captured = Vec::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = pipe.read(&mut buffer).context("failed to read git output")?;
+            if read == 0 {
+                break;
+            }
+            let mut stderr = std::io::stderr().lock();
+            let _ = stderr.write_all(&buffer[..read]);
+            let _ = stderr.flush();
+            captured.extend_from_slice(&buffer[..read]);
+        }
+    }
+    let status = child.wait().context("failed to wait for git")?;
+    Ok((status.success(), String::from_utf8_lossy(&captured).into_owned()))
+}
+
+fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<()> {
+    run_git_with(Path::new("git"), dir, args, 0, false)
+}
+
+/// `run_git` for operations that talk to a remote; retried per `network.retries`.
+fn run_git_network(dir: Option<&Path>, args: &[&str], network: NetworkOptions) -> Result<()> {
+    run_git_with(Path::new("git"), dir, args, network.retries, false)
 }
 
 pub fn init_repo(target_path: &Path) -> Result<()> {
@@ -55,14 +229,23 @@ pub fn add_all(target_path: &Path) -> Result<()> {
     run_git(Some(target_path), &["add", "-A"]).context("git add -A failed")
 }
 
-pub fn initial_commit(target_path: &Path, template_name: &str) -> Result<()> {
-    let message = format!("adding template {}", template_name);
-    run_git(Some(target_path), &["commit", "-m", &message]).context("git commit failed")
+pub fn initial_commit(target_path: &Path, message: &str) -> Result<()> {
+    run_git(Some(target_path), &["commit", "-m", message]).context("git commit failed")
 }
 
-pub fn clone_repo(url: &str, dest: &Path) -> Result<()> {
+pub fn clone_repo(url: &str, dest: &Path, network: NetworkOptions) -> Result<()> {
+    if network.show_progress {
+        return clone_repo_streaming(url, dest, network.retries);
+    }
     let dest_str = dest.to_string_lossy().into_owned();
-    run_git(None, &["clone", url, &dest_str])
+    run_git_network(None, &["clone", url, &dest_str], network)
+}
+
+/// Clones with git's progress passed through to the terminal so large repos show it live.
+/// Retries and failure messages are the same as for a quiet clone.
+pub fn clone_repo_streaming(url: &str, dest: &Path, retries: u32) -> Result<()> {
+    let dest_str = dest.to_string_lossy().into_owned();
+    run_git_with(Path::new("git"), None, &["clone", "--progress", url, &dest_str], retries, true)
 }
 
 pub fn clone_local(source: &Path, dest: &Path) -> Result<()> {
@@ -71,22 +254,113 @@ pub fn clone_local(source: &Path, dest: &Path) -> Result<()> {
     run_git(None, &["clone", &src_str, &dest_str])
 }
 
+/// Clones with `--shared`: the new repo borrows `source`'s objects through
+/// `.git/objects/info/alternates` instead of copying them, so `source` must outlive it.
+pub fn clone_shared(source: &Path, dest: &Path) -> Result<()> {
+    let src_str = source.to_string_lossy().into_owned();
+    let dest_str = dest.to_string_lossy().into_owned();
+    run_git(None, &["clone", "--shared", &src_str, &dest_str])
+}
+
+/// Clones `source` (a URL or path) with `--reference`, borrowing any objects already present
+/// in `reference` and fetching only the rest.
+pub fn clone_reference(source: &str, reference: &Path, dest: &Path) -> Result<()> {
+    let reference_str = reference.to_string_lossy().into_owned();
+    let dest_str = dest.to_string_lossy().into_owned();
+    run_git(None, &["clone", "--reference", &reference_str, source, &dest_str])
+}
+
 pub fn set_remote_url(repo: &Path, url: &str) -> Result<()> {
     run_git(Some(repo), &["remote", "set-url", "origin", url])
 }
 
-pub fn fetch_origin(repo: &Path) -> Result<()> {
-    run_git(Some(repo), &["fetch", "origin"])
+pub fn fetch_origin(repo: &Path, network: NetworkOptions) -> Result<()> {
+    run_git_network(Some(repo), &["fetch", "origin"], network)
+}
+
+/// Resets to `origin/<branch>`, or `origin/HEAD` when no default branch is known.
+pub fn reset_hard_origin(repo: &Path, default_branch: Option<&str>) -> Result<()> {
+    let target = format!("origin/{}", default_branch.unwrap_or("HEAD"));
+    run_git(Some(repo), &["reset", "--hard", &target])
 }
 
-pub fn reset_hard_origin(repo: &Path) -> Result<()> {
-    run_git(Some(repo), &["reset", "--hard", "origin/HEAD"])
+/// Returns the remote's default branch: `refs/remotes/origin/HEAD` when set, otherwise the
+/// branch checked out in `repo` (which for a fresh clone is the remote default). No network call.
+pub fn default_branch(repo: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .current_dir(repo)
+        .output()
+        .context("failed to execute git")?;
+    if output.status.success() {
+        let short = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if let Some(branch) = short.strip_prefix("origin/") {
+            return Ok(branch.to_string());
+        }
+    }
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--short", "HEAD"])
+        .current_dir(repo)
+        .output()
+        .context("failed to execute git")?;
+    if !output.status.success() {
+        anyhow::bail!("could not determine default branch of {}", repo.display());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `git ls-remote --exit-code url [patterns]`: whether the remote advertises any matching ref.
+/// Never prompts for credentials. Errors if the remote can't be reached.
+fn ls_remote(url: &str, patterns: &[&str]) -> Result<bool> {
+    let output = Command::new("git")
+        .args(["ls-remote", "--exit-code", url])
+        .args(patterns)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .context("failed to execute git")?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        // --exit-code: the remote answered but nothing matched.
+        Some(2) => Ok(false),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git ls-remote {} failed: {}", url, summarize_git_stderr(&stderr))
+        }
+    }
+}
+
+/// Asks the remote whether it has a branch or tag named `git_ref`, without cloning. Commit SHAs
+/// aren't advertised, so they always come back `false`. Errors if the remote can't be reached.
+pub fn ls_remote_has_ref(url: &str, git_ref: &str) -> Result<bool> {
+    ls_remote(url, &[git_ref])
+}
+
+/// Checks a remote without cloning it: `false` when it answers but has no refs at all (an
+/// empty repo). Errors if it doesn't exist or can't be reached.
+pub fn remote_exists(url: &str) -> Result<bool> {
+    ls_remote(url, &[])
 }
 
 pub fn checkout_ref(repo: &Path, git_ref: &str) -> Result<()> {
     run_git(Some(repo), &["checkout", git_ref])
 }
 
+/// Adds a worktree of `repo` at `path`, detached at `commitish`.
+pub fn worktree_add_detached(repo: &Path, path: &Path, commitish: &str) -> Result<()> {
+    let path_str = path.to_string_lossy().into_owned();
+    run_git(Some(repo), &["worktree", "add", "--detach", &path_str, commitish])
+}
+
+/// Moves an existing checkout to `commitish` as a detached HEAD, discarding local changes.
+pub fn checkout_detached(repo: &Path, commitish: &str) -> Result<()> {
+    run_git(Some(repo), &["checkout", "--force", "--detach", commitish])
+}
+
+/// Forgets worktrees of `repo` whose directories are gone.
+pub fn worktree_prune(repo: &Path) -> Result<()> {
+    run_git(Some(repo), &["worktree", "prune"])
+}
+
 pub fn ref_exists(repo: &Path, git_ref: &str) -> bool {
     Command::new("git")
         .args(["cat-file", "-e", git_ref])
@@ -96,12 +370,24 @@ pub fn ref_exists(repo: &Path, git_ref: &str) -> bool {
         .unwrap_or(false)
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RefKind {
     Branch,
     Tag,
     Commit,
 }
 
+impl RefKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RefKind::Branch => "branch",
+            RefKind::Tag => "tag",
+            RefKind::Commit => "commit",
+        }
+    }
+}
+
 pub fn classify_ref(repo: &Path, git_ref: &str) -> RefKind {
     let check = |args: &[&str]| {
         Command::new("git")
@@ -120,18 +406,90 @@ pub fn classify_ref(repo: &Path, git_ref: &str) -> RefKind {
     RefKind::Commit
 }
 
-pub fn init_and_commit(target_path: &Path, template_name: &str) -> Result<()> {
+/// `init_and_commit` without the commit, for `--no-commit`.
+pub fn init_and_stage(target_path: &Path) -> Result<()> {
+    init_repo(target_path)?;
+    add_all(target_path)
+}
+
+pub fn init_and_commit(target_path: &Path, message: &str) -> Result<()> {
     check_user_config()?;
     init_repo(target_path)?;
     add_all(target_path)?;
-    initial_commit(target_path, template_name)?;
+    initial_commit(target_path, message)?;
     Ok(())
 }
 
-pub fn add_and_commit(target_path: &Path, template_name: &str) -> Result<()> {
+/// Stages and commits only `paths` (relative to `target_path`), leaving any other
+/// working-tree or index changes out of the template commit. Paths are passed on stdin so
+/// large templates don't hit argument-length limits.
+pub fn add_paths_and_commit(target_path: &Path, paths: &[&Path], message: &str) -> Result<()> {
     check_user_config()?;
-    add_all(target_path)?;
-    initial_commit(target_path, template_name)?;
+    add_paths(target_path, paths)?;
+    run_git_with_stdin(
+        target_path,
+        &["commit", "-m", message, "--pathspec-from-file=-", "--pathspec-file-nul"],
+        &nul_separated(paths),
+    )
+    .context("git commit failed")
+}
+
+/// `add_paths_and_commit`, but folds `paths` into the current HEAD commit (keeping its
+/// message) instead of making a new one.
+pub fn add_paths_and_amend(target_path: &Path, paths: &[&Path]) -> Result<()> {
+    check_user_config()?;
+    add_paths(target_path, paths)?;
+    run_git_with_stdin(
+        target_path,
+        &["commit", "--amend", "--no-edit", "--pathspec-from-file=-", "--pathspec-file-nul"],
+        &nul_separated(paths),
+    )
+    .context("git commit --amend failed")
+}
+
+/// Whether the repo at `dir` has a HEAD commit (a freshly initialized one doesn't).
+pub fn has_commits(dir: &Path) -> bool {
+    git_rev_parse(dir, "HEAD").is_ok()
+}
+
+/// Stages only `paths` (relative to `target_path`).
+pub fn add_paths(target_path: &Path, paths: &[&Path]) -> Result<()> {
+    run_git_with_stdin(
+        target_path,
+        &["add", "--pathspec-from-file=-", "--pathspec-file-nul"],
+        &nul_separated(paths),
+    )
+    .context("git add failed")
+}
+
+fn nul_separated(paths: &[&Path]) -> Vec<u8> {
+    let mut pathspec = Vec::new();
+    for path in paths {
+        pathspec.extend_from_slice(path.to_string_lossy().as_bytes());
+        pathspec.push(0);
+    }
+    pathspec
+}
+
+fn run_git_with_stdin(dir: &Path, args: &[&str], input: &[u8]) -> Result<()> {
+    trace_debug!(dir = %dir.display(), "git {}", args.join(" "));
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to execute git")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input).context("failed to write to git")?;
+    }
+    let output = child.wait_with_output().context("failed to execute git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        trace_debug!(stderr = %stderr, "git {} failed", args.join(" "));
+        anyhow::bail!("git {} failed: {}", args.join(" "), summarize_git_stderr(&stderr));
+    }
     Ok(())
 }
 
@@ -174,6 +532,55 @@ pub fn is_behind_remote(repo: &Path) -> bool {
     local_sha != upstream_sha
 }
 
+/// Counts commits only in HEAD (ahead) and only upstream (behind), comparing against
+/// `@{u}` then `origin/HEAD`. Errors if neither upstream can be resolved. No network call.
+pub fn ahead_behind(repo: &Path) -> Result<(usize, usize)> {
+    let upstream = upstream_ref(repo).context("no upstream to compare against")?;
+    count_left_right(repo, &format!("HEAD...{}", upstream))
+}
+
+/// The upstream `ahead_behind` compares against: `@{u}`, else `origin/HEAD`, if either resolves.
+pub fn upstream_ref(repo: &Path) -> Option<&'static str> {
+    ["@{u}", "origin/HEAD"].into_iter().find(|upstream| git_rev_parse(repo, upstream).is_ok())
+}
+
+/// `git log --oneline from..to`: the commits reachable from `to` but not `from`, newest
+/// first, as "<short sha> <subject>" lines.
+pub fn log_between(repo: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+    let range = format!("{}..{}", from, to);
+    let output = Command::new("git")
+        .args(["log", "--oneline", "--no-decorate", &range])
+        .current_dir(repo)
+        .output()
+        .context("failed to execute git")?;
+    if !output.status.success() {
+        anyhow::bail!("git log {} failed: {}", range, summarize_git_stderr(&String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect())
+}
+
+/// `ahead_behind` for a local branch against `origin/<branch>`, whether or not it's checked out.
+pub fn branch_ahead_behind(repo: &Path, branch: &str) -> Result<(usize, usize)> {
+    count_left_right(repo, &format!("refs/heads/{0}...refs/remotes/origin/{0}", branch))
+}
+
+fn count_left_right(repo: &Path, range: &str) -> Result<(usize, usize)> {
+    let output = Command::new("git")
+        .args(["rev-list", "--left-right", "--count", range])
+        .current_dir(repo)
+        .output()
+        .context("failed to execute git")?;
+    if !output.status.success() {
+        anyhow::bail!("git rev-list {} failed", range);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut counts = stdout.split_whitespace().map(str::parse::<usize>);
+    match (counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind))) => Ok((ahead, behind)),
+        _ => anyhow::bail!("unexpected git rev-list output: {}", stdout.trim()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,6 +606,201 @@ mod tests {
         git_test(dir, &["commit", "-m", "initial"]);
     }
 
+    /// Writes a fake `git` that logs each call to `calls` and fails with `stderr`.
+    #[cfg(unix)]
+    fn failing_git(dir: &Path, stderr: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let script = dir.join("git");
+        let calls = dir.join("calls");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho call >> '{}'\necho '{}' >&2\nexit 128\n", calls.display(), stderr),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn network_failure_retries_then_gives_up() {
+        let dir = tempdir().unwrap();
+        let script = failing_git(dir.path(), "fatal: unable to access 'x': Could not resolve host: x");
+        let result = run_git_with(&script, None, &["fetch", "origin"], 2, false);
+        assert!(result.is_err());
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert_eq!(calls.lines().count(), 3);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn auth_failure_adds_credential_hint() {
+        let dir = tempdir().unwrap();
+        let script = failing_git(
+            dir.path(),
+            "fatal: could not read Username for https://github.com: terminal prompts disabled",
+        );
+        let message = run_git_with(&script, None, &["clone", "x", "y"], 2, false).unwrap_err().to_string();
+        assert!(message.contains("credential helper"));
+        assert!(message.contains("could not read Username"));
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert_eq!(calls.lines().count(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn echoed_stderr_still_retries_and_adds_the_credential_hint() {
+        let dir = tempdir().unwrap();
+        let script = failing_git(dir.path(), "fatal: unable to access 'x': Could not resolve host: x");
+        assert!(run_git_with(&script, None, &["clone", "--progress", "x", "y"], 2, true).is_err());
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert_eq!(calls.lines().count(), 3);
+
+        let dir = tempdir().unwrap();
+        let script = failing_git(dir.path(), "remote: HTTP Basic: Access denied\nfatal: Authentication failed for 'x'");
+        let message = run_git_with(&script, None, &["clone", "--progress", "x", "y"], 2, true).unwrap_err().to_string();
+        assert!(message.contains("credential helper"));
+    }
+
+    #[test]
+    fn stderr_summary_keeps_only_the_errors() {
+        let stderr = "Cloning into 'x'...\nremote: Enumerating objects: 5, done.\n\
+            Receiving objects:  40% (2/5)\rReceiving objects: 100% (5/5), done.\n\
+            error: RPC failed; curl 92\nfatal: early EOF\nfatal: early EOF\n";
+        assert_eq!(summarize_git_stderr(stderr), "error: RPC failed; curl 92\nfatal: early EOF");
+        assert_eq!(summarize_git_stderr("remote: Repository not found.\n"), "remote: Repository not found.");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn permanent_failure_is_not_retried() {
+        let dir = tempdir().unwrap();
+        let script = failing_git(dir.path(), "remote: Repository not found.");
+        let result = run_git_with(&script, None, &["fetch", "origin"], 2, false);
+        assert!(result.is_err());
+        let calls = std::fs::read_to_string(dir.path().join("calls")).unwrap();
+        assert_eq!(calls.lines().count(), 1);
+    }
+
+    #[test]
+    fn add_paths_and_commit_leaves_other_changes_alone() {
+        let repo = tempdir().unwrap();
+        setup_repo(repo.path());
+        git_test(repo.path(), &["config", "user.name", "Test"]);
+        git_test(repo.path(), &["config", "user.email", "test@test.com"]);
+        std::fs::write(repo.path().join("file.txt"), "user edit").unwrap();
+        std::fs::write(repo.path().join("notes.txt"), "untracked").unwrap();
+        std::fs::create_dir_all(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/main.rs"), "fn main() {}").unwrap();
+
+        add_paths_and_commit(repo.path(), &[Path::new("src/main.rs")], "adding template tmpl").unwrap();
+
+        let committed = Command::new("git")
+            .args(["show", "--name-only", "--format=", "HEAD"])
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&committed.stdout).trim(), "src/main.rs");
+        let status = Command::new("git")
+            .args(["status", "--porcelain"])
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+        let status = String::from_utf8_lossy(&status.stdout);
+        assert!(status.contains(" M file.txt"));
+        assert!(status.contains("?? notes.txt"));
+    }
+
+    #[test]
+    fn default_branch_reads_origin_head() {
+        let remote = tempdir().unwrap();
+        setup_repo(remote.path());
+        git_test(remote.path(), &["branch", "-M", "trunk"]);
+        let parent = tempdir().unwrap();
+        let clone = parent.path().join("clone");
+        clone_local(remote.path(), &clone).unwrap();
+        assert_eq!(default_branch(&clone).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn reset_hard_origin_uses_default_branch_without_origin_head() {
+        let remote = tempdir().unwrap();
+        setup_repo(remote.path());
+        git_test(remote.path(), &["branch", "-M", "trunk"]);
+        let parent = tempdir().unwrap();
+        let clone = parent.path().join("clone");
+        clone_local(remote.path(), &clone).unwrap();
+        git_test(&clone, &["remote", "set-head", "origin", "--delete"]);
+        std::fs::write(remote.path().join("file.txt"), "v2").unwrap();
+        git_test(remote.path(), &["commit", "-am", "update"]);
+        fetch_origin(&clone, NetworkOptions::default()).unwrap();
+
+        assert!(reset_hard_origin(&clone, None).is_err());
+        reset_hard_origin(&clone, Some("trunk")).unwrap();
+        assert_eq!(std::fs::read_to_string(clone.join("file.txt")).unwrap(), "v2");
+    }
+
+    #[test]
+    fn clone_repo_streaming_clones_and_reports_failure() {
+        let remote = tempdir().unwrap();
+        setup_repo(remote.path());
+        let parent = tempdir().unwrap();
+        let dest = parent.path().join("clone");
+        clone_repo_streaming(remote.path().to_str().unwrap(), &dest, 0).unwrap();
+        assert!(is_git_repo(&dest));
+        let missing = parent.path().join("missing");
+        assert!(clone_repo_streaming(missing.to_str().unwrap(), &parent.path().join("other"), 0).is_err());
+    }
+
+    #[test]
+    fn ls_remote_has_ref_checks_branches_and_tags() {
+        let remote = tempdir().unwrap();
+        setup_repo(remote.path());
+        git_test(remote.path(), &["tag", "v1.0"]);
+        let url = remote.path().to_str().unwrap();
+        assert!(ls_remote_has_ref(url, "v1.0").unwrap());
+        assert!(!ls_remote_has_ref(url, "v9.9").unwrap());
+        assert!(ls_remote_has_ref(&remote.path().join("missing").to_string_lossy(), "v1.0").is_err());
+    }
+
+    #[test]
+    fn clone_shared_borrows_source_objects() {
+        let source = tempdir().unwrap();
+        setup_repo(source.path());
+        let parent = tempdir().unwrap();
+        let dest = parent.path().join("clone");
+        clone_shared(source.path(), &dest).unwrap();
+        assert_eq!(std::fs::read_to_string(dest.join("file.txt")).unwrap(), "v1");
+        assert!(dest.join(".git/objects/info/alternates").exists());
+    }
+
+    #[test]
+    fn clone_reference_borrows_reference_objects() {
+        let source = tempdir().unwrap();
+        setup_repo(source.path());
+        let parent = tempdir().unwrap();
+        let dest = parent.path().join("clone");
+        clone_reference(source.path().to_str().unwrap(), source.path(), &dest).unwrap();
+        assert_eq!(std::fs::read_to_string(dest.join("file.txt")).unwrap(), "v1");
+        let alternates = std::fs::read_to_string(dest.join(".git/objects/info/alternates")).unwrap();
+        assert!(!alternates.trim().is_empty());
+    }
+
+    #[test]
+    fn remote_exists_tells_empty_and_missing_repos_apart() {
+        let repo = tempdir().unwrap();
+        git_test(repo.path(), &["init"]);
+        let url = repo.path().to_str().unwrap();
+        assert!(!remote_exists(url).unwrap());
+
+        std::fs::write(repo.path().join("file.txt"), "content").unwrap();
+        git_test(repo.path(), &["add", "-A"]);
+        git_test(repo.path(), &["commit", "-m", "initial"]);
+        assert!(remote_exists(url).unwrap());
+
+        assert!(remote_exists(repo.path().join("missing").to_str().unwrap()).is_err());
+    }
+
     #[test]
     fn is_git_repo_returns_false_for_plain_dir() {
         let dir = tempdir().unwrap();
@@ -265,7 +867,43 @@ mod tests {
         git_test(remote.path(), &["add", "-A"]);
         git_test(remote.path(), &["commit", "-m", "update"]);
         // Fetch without merging
-        fetch_origin(local.path()).unwrap();
+        fetch_origin(local.path(), NetworkOptions::default()).unwrap();
         assert!(is_behind_remote(local.path()));
     }
+
+    #[test]
+    fn ahead_behind_errors_when_no_remote() {
+        let dir = tempdir().unwrap();
+        setup_repo(dir.path());
+        assert!(ahead_behind(dir.path()).is_err());
+    }
+
+    #[test]
+    fn ahead_behind_counts_both_directions() {
+        let remote = tempdir().unwrap();
+        setup_repo(remote.path());
+        let local = tempdir().unwrap();
+        git_test(
+            local.path().parent().unwrap(),
+            &[
+                "clone",
+                remote.path().to_str().unwrap(),
+                local.path().to_str().unwrap(),
+            ],
+        );
+        for content in ["v2", "v3"] {
+            std::fs::write(remote.path().join("file.txt"), content).unwrap();
+            git_test(remote.path(), &["commit", "-am", content]);
+        }
+        std::fs::write(local.path().join("local.txt"), "local").unwrap();
+        git_test(local.path(), &["add", "-A"]);
+        git_test(local.path(), &["commit", "-m", "local"]);
+        fetch_origin(local.path(), NetworkOptions::default()).unwrap();
+        assert_eq!(ahead_behind(local.path()).unwrap(), (1, 2));
+
+        let upstream = upstream_ref(local.path()).unwrap();
+        let incoming = log_between(local.path(), "HEAD", upstream).unwrap();
+        assert_eq!(incoming.len(), 2);
+        assert!(incoming[0].ends_with(" v3") && incoming[1].ends_with(" v2"), "{:?}", incoming);
+    }
 }
@@ -1,20 +1,429 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use fs2::FileExt;
 
+use crate::config::Config;
+use crate::registry::Template;
 use crate::{git, utilities};
 
+fn has_entries(dir: &Path) -> bool {
+    std::fs::read_dir(dir).map(|mut entries| entries.next().is_some()).unwrap_or(false)
+}
+
+/// Left in the legacy cache directory when moving it failed, so the move isn't retried (and
+/// warned about) on every run.
+const MOVE_FAILED_FILE: &str = ".templative-move-failed";
+
+/// Moves clones from `legacy_cache_dir()` (under the config directory) to the XDG cache
+/// directory the first time a version using it runs. Only when the cache is at its default
+/// and the new location doesn't exist yet. A failed move is warned about once and recorded;
+/// from then on `note_orphaned_caches` points at the old clones. Returns whether it warned,
+/// so the caller can skip that note on the same run.
+pub fn migrate_legacy_cache(config: &Config) -> bool {
+    let (Ok(current), Ok(default), Ok(legacy)) =
+        (utilities::cache_dir(config), utilities::default_cache_dir(config), utilities::legacy_cache_dir(config))
+    else {
+        return false;
+    };
+    if current != default
+        || default == legacy
+        || default.exists()
+        || !has_entries(&legacy)
+        || legacy.join(MOVE_FAILED_FILE).exists()
+    {
+        return false;
+    }
+    let moved = default
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| move_dir(&legacy, &default));
+    match moved {
+        Ok(()) => {
+            eprintln!("note: moved cached templates from {} to {}", legacy.display(), default.display());
+            false
+        }
+        Err(err) => {
+            utilities::warn(format_args!(
+                "could not move cached templates from {} to {}: {}",
+                legacy.display(),
+                default.display(),
+                err
+            ));
+            let _ = std::fs::write(legacy.join(MOVE_FAILED_FILE), err.to_string());
+            true
+        }
+    }
+}
+
+/// Renames `from` to `to`, or copies and then removes it when they're on different
+/// filesystems. The copy goes to a staging directory first, so `to` never holds a partial
+/// cache; if `from` can't be removed afterwards, `note_orphaned_caches` reports it.
+fn move_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {}
+        result => return result,
+    }
+    let mut staging_name = to.file_name().unwrap_or_default().to_os_string();
+    staging_name.push(".partial");
+    let staging = to.with_file_name(staging_name);
+    let _ = std::fs::remove_dir_all(&staging);
+    if let Err(err) = copy_tree(from, &staging).and_then(|()| std::fs::rename(&staging, to)) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+    let _ = std::fs::remove_dir_all(from);
+    Ok(())
+}
+
+/// Copies the tree at `from` to `to` as is: `.git` included and symlinks kept as links.
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(from).expect("walkdir yields paths under its root");
+        let dest = to.join(relative);
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&dest)?;
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &dest)?;
+            #[cfg(not(unix))]
+            std::fs::copy(entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints a note when the cache directory has been moved (via `cache_dir`,
+/// `TEMPLATIVE_CACHE_DIR` or the switch to the XDG cache directory) but clones remain in an
+/// old location, so they aren't orphaned silently.
+pub fn note_orphaned_caches(config: &Config) {
+    let (Ok(current), Ok(default), Ok(legacy)) =
+        (utilities::cache_dir(config), utilities::default_cache_dir(config), utilities::legacy_cache_dir(config))
+    else {
+        return;
+    };
+    let mut old_locations = vec![default];
+    if !old_locations.contains(&legacy) {
+        old_locations.push(legacy);
+    }
+    for old in old_locations.iter().filter(|old| **old != current && has_entries(old)) {
+        eprintln!(
+            "note: cached templates remain in {} but the cache is now {}; move or delete them",
+            old.display(),
+            current.display()
+        );
+    }
+}
+
+/// Unix time of the cache's last clone or fetch, kept inside `.git` so it's never copied and
+/// doesn't depend on filesystem mtimes.
+const LAST_FETCH_FILE: &str = "templative-last-fetch";
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0)
+}
+
+/// Stamps the cache as just fetched. Best effort: a cache without a stamp only costs an
+/// extra fetch.
+pub fn record_fetch(cache_path: &Path) {
+    let _ = std::fs::write(cache_path.join(".git").join(LAST_FETCH_FILE), unix_now().to_string());
+}
+
+/// Whether the cache was cloned or fetched less than `ttl` ago.
+pub fn fetched_within(cache_path: &Path, ttl: Duration) -> bool {
+    std::fs::read_to_string(cache_path.join(".git").join(LAST_FETCH_FILE))
+        .ok()
+        .and_then(|stamp| stamp.trim().parse::<u64>().ok())
+        .is_some_and(|fetched| unix_now().saturating_sub(fetched) < ttl.as_secs())
+}
+
 /// Returns the cache path, cloning from the URL if not already present.
-pub fn ensure_cached(url: &str) -> Result<PathBuf> {
-    let cache_path = utilities::cache_path_for_url(url)?;
+pub fn ensure_cached(config: &Config, url: &str) -> Result<PathBuf> {
+    let cache_path = utilities::cache_path_for_url(config, url)?;
     if !cache_path.exists() {
-        git::clone_repo(url, &cache_path)?;
+        git::clone_repo(url, &cache_path, config.network())?;
+        record_fetch(&cache_path);
     }
     Ok(cache_path)
 }
 
-/// Fetch and attempt reset to origin/HEAD. Fully non-fatal: network or ref errors are ignored.
-pub fn update_cache(cache_path: &Path) {
-    let _ = git::fetch_origin(cache_path);
-    let _ = git::reset_hard_origin(cache_path);
+/// Where the per-ref worktrees of `cache_path` live: `worktrees/<cache name>` beside it.
+fn worktrees_root(cache_path: &Path) -> PathBuf {
+    let name = cache_path.file_name().unwrap_or_default();
+    cache_path.with_file_name(WORKTREES_DIR).join(name)
+}
+
+/// Cache entries holding worktrees rather than a clone.
+const WORKTREES_DIR: &str = "worktrees";
+
+/// The worktree directory for `git_ref`: the ref made filename-safe, plus a hash so refs that
+/// sanitize alike (`a/b` and `a-b`) stay apart.
+fn worktree_path(cache_path: &Path, git_ref: &str) -> PathBuf {
+    let safe: String = git_ref
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.') { ch } else { '-' })
+        .collect();
+    let hash = utilities::fnv1a_hash(git_ref) as u32;
+    worktrees_root(cache_path).join(format!("{}-{:08x}", safe.trim_start_matches('.'), hash))
+}
+
+/// `<worktree>` -> `<worktree>.lock`, beside it.
+fn worktree_lock_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".lock");
+    path.with_file_name(file_name)
+}
+
+fn open_worktree_lock(path: &Path) -> Result<File> {
+    let lock_path = worktree_lock_path(path);
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("failed to open worktree lock: {}", lock_path.display()))
+}
+
+/// A ref's worktree, locked until dropped so another templative process can't check out a
+/// different commit into it while it's being copied from.
+#[derive(Debug)]
+pub struct RefWorktree {
+    pub path: PathBuf,
+    _lock: File,
+}
+
+/// A checkout of `git_ref` from the cache at `cache_path`, kept as a git worktree per ref so
+/// templates sharing a URL but pinned differently don't move each other's checkout. Branches
+/// follow `origin/<branch>`, which the cache's fetches keep current. Blocks while another
+/// process holds the same ref's worktree.
+pub fn worktree_for_ref(cache_path: &Path, git_ref: &str) -> Result<RefWorktree> {
+    let remote_branch = format!("refs/remotes/origin/{}", git_ref);
+    let commitish = if git::ref_exists(cache_path, &remote_branch) { remote_branch.as_str() } else { git_ref };
+    let path = worktree_path(cache_path, git_ref);
+    let lock = open_worktree_lock(&path)?;
+    lock.lock_exclusive()
+        .with_context(|| format!("failed to lock worktree: {}", path.display()))?;
+    if path.join(".git").exists() {
+        git::checkout_detached(&path, commitish)?;
+    } else {
+        // A directory without its `.git` link is a leftover git no longer tracks.
+        if path.exists() {
+            std::fs::remove_dir_all(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        let _ = git::worktree_prune(cache_path);
+        git::worktree_add_detached(cache_path, &path, commitish)?;
+    }
+    Ok(RefWorktree { path, _lock: lock })
+}
+
+/// Whether `name` is a cache key as `cache_path_for_url` writes it: 16 lowercase hex digits.
+fn is_cache_key(name: &OsStr) -> bool {
+    name.to_str()
+        .is_some_and(|name| name.len() == 16 && name.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')))
+}
+
+/// Whether `path` is a clone `ensure_cached` made, so `prune` never touches anything else a
+/// shared `cache_dir` holds.
+fn is_cached_clone(path: &Path) -> bool {
+    path.file_name().is_some_and(is_cache_key) && path.join(".git").is_dir()
+}
+
+/// Whether `path` is a worktree `worktree_for_ref` made: `<ref>-<8 hex digits>` with the
+/// `.git` file git writes into a worktree.
+fn is_ref_worktree(path: &Path) -> bool {
+    let is_worktree_name = path.file_name().and_then(OsStr::to_str).and_then(|name| name.rsplit_once('-')).is_some_and(
+        |(_, hash)| hash.len() == 8 && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')),
+    );
+    is_worktree_name && path.join(".git").is_file()
+}
+
+/// What `prune` removed, or would remove on a dry run.
+#[derive(Debug, Default)]
+pub struct Pruned {
+    pub caches: Vec<PathBuf>,
+    pub worktrees: Vec<PathBuf>,
+}
+
+/// Deletes cached clones no registered URL template uses, and worktrees for refs no template
+/// pins anymore. Only entries templative made are considered, and a worktree another process
+/// is using is left alone. With `dry_run` nothing is deleted.
+pub fn prune(config: &Config, templates: &[Template], dry_run: bool) -> Result<Pruned> {
+    let cache_dir = utilities::cache_dir(config)?;
+    let mut wanted_caches = HashSet::new();
+    let mut wanted_worktrees = HashSet::new();
+    for template in templates.iter().filter(|template| utilities::is_git_url(&template.location)) {
+        let cache_path = utilities::cache_path_for_url(config, &template.location)?;
+        if let Some(git_ref) = template.commit.as_deref().or(template.git_ref.as_deref()) {
+            wanted_worktrees.insert(worktree_path(&cache_path, git_ref));
+        }
+        wanted_caches.insert(cache_path);
+    }
+
+    let mut pruned = Pruned::default();
+    for cache_path in subdirs(&cache_dir) {
+        if !is_cached_clone(&cache_path) || wanted_caches.contains(&cache_path) {
+            continue;
+        }
+        if !dry_run {
+            std::fs::remove_dir_all(&cache_path)
+                .with_context(|| format!("failed to remove {}", cache_path.display()))?;
+        }
+        pruned.caches.push(cache_path);
+    }
+    let worktree_roots = subdirs(&cache_dir.join(WORKTREES_DIR));
+    for root in worktree_roots.iter().filter(|root| root.file_name().is_some_and(is_cache_key)) {
+        for worktree in subdirs(root) {
+            if !is_ref_worktree(&worktree) || wanted_worktrees.contains(&worktree) {
+                continue;
+            }
+            let lock = open_worktree_lock(&worktree)?;
+            if lock.try_lock_exclusive().is_err() {
+                continue;
+            }
+            if !dry_run {
+                std::fs::remove_dir_all(&worktree)
+                    .with_context(|| format!("failed to remove {}", worktree.display()))?;
+                drop(lock);
+                let _ = std::fs::remove_file(worktree_lock_path(&worktree));
+            }
+            pruned.worktrees.push(worktree);
+        }
+        if dry_run {
+            continue;
+        }
+        let cache_path = cache_dir.join(root.file_name().unwrap_or_default());
+        if cache_path.is_dir() {
+            let _ = git::worktree_prune(&cache_path);
+        }
+        if !has_entries(root) {
+            let _ = std::fs::remove_dir(root);
+        }
+    }
+    Ok(pruned)
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect()
+}
+
+/// Fetch and attempt reset to `origin/<default_branch>` (or origin/HEAD). Fully non-fatal:
+/// network or ref errors are ignored.
+pub fn update_cache(cache_path: &Path, default_branch: Option<&str>, network: git::NetworkOptions) {
+    if git::fetch_origin(cache_path, network).is_ok() {
+        record_fetch(cache_path);
+    }
+    let _ = git::reset_hard_origin(cache_path, default_branch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_legacy_cache_moves_clones_to_xdg_cache() {
+        let _lock = crate::test_env::ENV_LOCK.lock().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let legacy = temp.path().join("config/templative/cache");
+        std::fs::create_dir_all(legacy.join("0123456789abcdef")).unwrap();
+        let saved_config_dir = std::env::var_os("TEMPLATIVE_CONFIG_DIR");
+        unsafe {
+            std::env::remove_var("TEMPLATIVE_CONFIG_DIR");
+            std::env::set_var("XDG_CONFIG_HOME", temp.path().join("config"));
+            std::env::set_var("XDG_CACHE_HOME", temp.path().join("cache"));
+        }
+        assert!(!migrate_legacy_cache(&Config::new()));
+        unsafe {
+            std::env::remove_var("XDG_CONFIG_HOME");
+            std::env::remove_var("XDG_CACHE_HOME");
+            if let Some(dir) = saved_config_dir {
+                std::env::set_var("TEMPLATIVE_CONFIG_DIR", dir);
+            }
+        }
+        assert!(temp.path().join("cache/templative/0123456789abcdef").is_dir());
+        assert!(!legacy.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_tree_keeps_git_dirs_and_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let from = temp.path().join("from");
+        std::fs::create_dir_all(from.join("0123456789abcdef/.git")).unwrap();
+        std::fs::write(from.join("0123456789abcdef/.git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::os::unix::fs::symlink(".git/HEAD", from.join("0123456789abcdef/head")).unwrap();
+
+        let to = temp.path().join("to");
+        copy_tree(&from, &to).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(to.join("0123456789abcdef/.git/HEAD")).unwrap(),
+            "ref: refs/heads/main\n"
+        );
+        assert_eq!(std::fs::read_link(to.join("0123456789abcdef/head")).unwrap(), Path::new(".git/HEAD"));
+    }
+
+    #[test]
+    fn fetch_stamp_expires_after_ttl() {
+        let cache = tempfile::tempdir().unwrap();
+        std::fs::create_dir(cache.path().join(".git")).unwrap();
+        assert!(!fetched_within(cache.path(), Duration::from_secs(60)));
+
+        record_fetch(cache.path());
+        assert!(fetched_within(cache.path(), Duration::from_secs(60)));
+        assert!(!fetched_within(cache.path(), Duration::ZERO));
+
+        let stale = unix_now() - 120;
+        std::fs::write(cache.path().join(".git").join(LAST_FETCH_FILE), stale.to_string()).unwrap();
+        assert!(!fetched_within(cache.path(), Duration::from_secs(60)));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@test.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn refs_get_separate_worktrees_of_one_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let origin = temp.path().join("origin");
+        std::fs::create_dir(&origin).unwrap();
+        git(&origin, &["init", "-q"]);
+        std::fs::write(origin.join("version"), "1").unwrap();
+        git(&origin, &["add", "."]);
+        git(&origin, &["commit", "-qm", "one"]);
+        git(&origin, &["tag", "v1"]);
+        git(&origin, &["checkout", "-qb", "next"]);
+        std::fs::write(origin.join("version"), "2").unwrap();
+        git(&origin, &["commit", "-qam", "two"]);
+        let cache_path = temp.path().join("cache/0123456789abcdef");
+        git::clone_local(&origin, &cache_path).unwrap();
+
+        let tagged = worktree_for_ref(&cache_path, "v1").unwrap().path;
+        let branch = worktree_for_ref(&cache_path, "next").unwrap().path;
+        assert_ne!(tagged, branch);
+        assert!(tagged.starts_with(temp.path().join("cache/worktrees/0123456789abcdef")));
+        assert_eq!(std::fs::read_to_string(tagged.join("version")).unwrap(), "1");
+        assert_eq!(std::fs::read_to_string(branch.join("version")).unwrap(), "2");
+
+        // Asking again reuses the worktree.
+        assert_eq!(worktree_for_ref(&cache_path, "v1").unwrap().path, tagged);
+        assert_eq!(std::fs::read_to_string(tagged.join("version")).unwrap(), "1");
+    }
 }
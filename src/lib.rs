@@ -0,0 +1,39 @@
+//! templative's core, for embedding without shelling out to the CLI.
+//!
+//! `init_template` creates a project from a registered template and returns what it did,
+//! notes included, only prompting through the callbacks in its `InitOptions`. The `cmd_*`
+//! functions in `ops` are the CLI commands: they print their results and may prompt.
+
+/// `tracing::debug!` with the `tracing` feature, nothing without it.
+macro_rules! trace_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+pub mod config;
+pub mod errors;
+pub mod fs_copy;
+pub mod git;
+pub mod git_cache;
+pub mod manifest;
+pub mod ops;
+pub mod registry;
+pub mod resolved;
+pub mod utilities;
+
+pub use config::Config;
+pub use errors::TemplativeError;
+pub use fs_copy::{copy_template, CopySummary};
+pub use ops::{init_template, InitNote, InitOptions, InitOutcome};
+pub use registry::{Registry, Template};
+pub use resolved::{InitFlags, ResolvedOptions};
+
+/// Shared test infrastructure. All tests that touch `TEMPLATIVE_CONFIG_DIR`
+/// must hold this lock to prevent parallel tests in different modules from
+/// racing on the same environment variable.
+#[cfg(test)]
+pub mod test_env {
+    pub static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+}
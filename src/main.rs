@@ -1,20 +1,14 @@
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 
-mod config;
-mod errors;
-mod fs_copy;
-mod git;
-mod git_cache;
-mod ops;
-mod registry;
-mod resolved;
-mod utilities;
-
-use config::{GitMode, WriteMode};
-use ops::{ChangeOptions, Shell};
+use templative::config::{self, GitMode, WriteMode};
+use templative::registry::ListSort;
+use templative::resolved::InitFlags;
+use templative::ops::{self, AddOptions, AssumeAnswer, ChangeOptions, CompletionsAction, InitOptions, ListOptions, Shell, UpdateMode};
+use templative::{git_cache, utilities, TemplativeError};
 
 /// `--git fresh|preserve|no-git` for init and add
 #[derive(clap::ValueEnum, Clone)]
@@ -35,6 +29,30 @@ enum GitModeChangeArg {
     Unset,
 }
 
+/// `--assume overwrite|skip` for init
+#[derive(clap::ValueEnum, Clone)]
+enum AssumeArg {
+    Overwrite,
+    Skip,
+}
+
+/// `--format text|json` for init
+#[derive(clap::ValueEnum, Clone, Default, PartialEq)]
+enum FormatArg {
+    #[default]
+    Text,
+    Json,
+}
+
+/// `--sort name|location|added` for list
+#[derive(clap::ValueEnum, Clone, Default)]
+enum SortArg {
+    #[default]
+    Name,
+    Location,
+    Added,
+}
+
 /// `--write-mode strict|no-overwrite|skip-overwrite|overwrite|ask`
 #[derive(clap::ValueEnum, Clone)]
 enum WriteModeArg {
@@ -60,13 +78,38 @@ enum WriteModeChangeArg {
     Unset,
 }
 
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  1   any other error
+  2   invalid command-line usage
+  10  template not found
+  11  target directory is not empty (or exists, with --new-dir-only)
+  12  files would be overwritten (write mode no-overwrite)
+  13  refusing to write into a dangerous path";
+
 #[derive(Parser)]
 #[command(name = "templative")]
 #[command(about = "Instantiate project templates from local directories or git URLs")]
 #[command(version, disable_version_flag = true)]
+#[command(after_help = EXIT_CODES_HELP)]
 struct Cli {
     #[arg(short = 'v', long, action = clap::ArgAction::Version)]
     version: Option<bool>,
+    /// Show git's progress while cloning (useful for large templates)
+    #[arg(long, global = true)]
+    progress: bool,
+    /// Use this config file; the registry and cache live next to it
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Keep the template registry (templates.json) in this directory instead of beside the config
+    #[arg(long = "registry-dir", global = true, value_name = "DIR")]
+    registry_dir: Option<PathBuf>,
+    /// Force coloured output, even when not writing to a terminal
+    #[arg(long, global = true, overrides_with = "no_color")]
+    color: bool,
+    /// Disable coloured output
+    #[arg(long = "no-color", global = true, overrides_with = "color")]
+    no_color: bool,
     #[command(subcommand)]
     command: Command,
 }
@@ -75,7 +118,7 @@ struct Cli {
 enum Command {
     /// Copy template into PATH
     Init {
-        /// Template name (as registered with add)
+        /// Template name (as registered with add), or a git URL or path to use without adding it
         template_name: String,
         /// Target path (default: current directory)
         #[arg(default_value = ".")]
@@ -86,6 +129,60 @@ enum Command {
         /// Write mode: how to handle file collisions in the target directory
         #[arg(long = "write-mode")]
         write_mode: Option<WriteModeArg>,
+        /// Use this git ref (branch, tag, or SHA) instead of the template's pinned ref
+        #[arg(long = "ref")]
+        git_ref: Option<String>,
+        /// Don't ask before overwriting files in a non-empty target
+        #[arg(short, long)]
+        yes: bool,
+        /// With write mode ask, answer every prompt with overwrite or skip (needed without a terminal)
+        #[arg(long)]
+        assume: Option<AssumeArg>,
+        /// Extra patterns to exclude for this init (added to config and template patterns)
+        #[arg(long, num_args = 1..)]
+        exclude: Vec<String>,
+        /// Use only the --exclude patterns, ignoring config and template patterns
+        #[arg(long = "exclude-only")]
+        exclude_only: bool,
+        /// Don't run the template's pre/post-init hooks
+        #[arg(long = "no-hooks", conflicts_with = "only_hooks")]
+        no_hooks: bool,
+        /// Run hooks from the template's .templative.toml without asking
+        #[arg(long = "trust-hooks", conflicts_with = "no_hooks")]
+        trust_hooks: bool,
+        /// Only run the template's hooks against an existing target (no copy or git steps)
+        #[arg(long = "only-hooks")]
+        only_hooks: bool,
+        /// Error if the target path exists at all, even as an empty directory
+        #[arg(long = "new-dir-only", conflicts_with = "only_hooks")]
+        new_dir_only: bool,
+        /// Set a variable for path tokens and the manifest's conditional paths (NAME alone means true)
+        #[arg(long = "var", value_name = "NAME[=VALUE]", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+        /// Create and init this directory inside PATH (the template's target_subdir nests inside it)
+        #[arg(long = "output-name")]
+        output_name: Option<String>,
+        /// Copy the contents of symlink targets instead of recreating the links
+        #[arg(long = "follow-symlinks")]
+        follow_symlinks: bool,
+        /// Commit message for the template commit ({name}, {date} and {ref} are filled in)
+        #[arg(long = "commit-message", conflicts_with = "no_commit")]
+        commit_message: Option<String>,
+        /// Initialize the repo and stage the files, but leave the first commit to you
+        #[arg(long = "no-commit")]
+        no_commit: bool,
+        /// In an existing repo, add the template files to the HEAD commit instead of a new one
+        #[arg(long, conflicts_with_all = ["no_commit", "commit_message"])]
+        amend: bool,
+        /// With write mode ask, save an "overwrite all" or "skip all" answer as the template's write mode
+        #[arg(long)]
+        remember: bool,
+        /// Output format: text, or json for a machine-readable report (errors too)
+        #[arg(long, value_enum, default_value_t)]
+        format: FormatArg,
+        /// Show the output of pre/post-init hooks as they run (text format only)
+        #[arg(long)]
+        verbose: bool,
     },
     /// Register a directory or git URL as a template
     Add {
@@ -104,18 +201,66 @@ enum Command {
         /// Pin to a specific git ref (branch, tag, or SHA)
         #[arg(long = "git-ref")]
         git_ref: Option<String>,
+        /// Pin to a commit; takes precedence over --git-ref and is never moved by update
+        #[arg(long)]
+        commit: Option<String>,
         /// Additional patterns to exclude during init (e.g. dist *.log)
         #[arg(long, num_args = 0..)]
         exclude: Vec<String>,
         /// Write mode: how to handle file collisions in the target directory
         #[arg(long = "write-mode")]
         write_mode: Option<WriteModeArg>,
+        /// Tag for grouping templates (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Replace an existing template with the same name
+        #[arg(short, long)]
+        force: bool,
+        /// Use this folder inside the repo or directory as the template (e.g. templates/rust-lib)
+        #[arg(long, alias = "template-subdir")]
+        subdir: Option<String>,
+        /// Read a single-file template from stdin (needs --name); it's kept in the config directory
+        #[arg(long, requires = "name", conflicts_with_all = ["path", "git_ref", "commit", "subdir"])]
+        stdin: bool,
+        /// Register a local path even if it doesn't exist yet
+        #[arg(long = "allow-empty", conflicts_with = "stdin")]
+        allow_empty: bool,
+        /// .gitignore to add on init when there's none: a file path, or the content itself
+        #[arg(long = "gitignore-template", value_name = "PATH_OR_CONTENT")]
+        gitignore_template: Option<String>,
+        /// Skip template files larger than this many bytes (overrides the config)
+        #[arg(long = "max-file-size", value_name = "BYTES")]
+        max_file_size: Option<u64>,
+        /// Skip template files that look binary (overrides the config; --exclude-binaries=false keeps them)
+        #[arg(long = "exclude-binaries", value_name = "BOOL", num_args = 0..=1, require_equals = true,
+              default_missing_value = "true")]
+        exclude_binaries: Option<bool>,
+    },
+    /// Register every subdirectory of a directory as a template named after the folder
+    AddAll {
+        /// Directory whose subdirectories are templates
+        dir: String,
+        /// Git mode for every added template
+        #[arg(long)]
+        git: Option<GitModeArg>,
+        /// Write mode for every added template
+        #[arg(long = "write-mode")]
+        write_mode: Option<WriteModeArg>,
     },
     /// Remove one or more templates from the registry
     Remove {
         /// Template name(s)
-        #[arg(required = true, num_args = 1..)]
+        #[arg(required_unless_present_any = ["all", "missing"], num_args = 1..)]
         template_names: Vec<String>,
+        /// Remove every template (asks for confirmation)
+        #[arg(long, conflicts_with_all = ["template_names", "missing"])]
+        all: bool,
+        /// Remove templates whose local location no longer exists
+        #[arg(long, conflicts_with = "template_names")]
+        missing: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Update fields on a registered template
     Change {
@@ -130,9 +275,9 @@ enum Command {
         /// Clear the description
         #[arg(long = "unset-description")]
         unset_description: bool,
-        /// New location
+        /// New location: a local path or a git URL
         #[arg(long)]
-        location: Option<PathBuf>,
+        location: Option<String>,
         /// Git mode: fresh, preserve, no-git, or unset to remove override
         #[arg(long)]
         git: Option<GitModeChangeArg>,
@@ -154,44 +299,201 @@ enum Command {
         /// Clear the pinned git ref
         #[arg(long = "unset-git-ref")]
         unset_git_ref: bool,
+        /// Pin to a commit; takes precedence over the git ref and is never moved by update
+        #[arg(long, conflicts_with = "unset_commit")]
+        commit: Option<String>,
+        /// Clear the pinned commit
+        #[arg(long = "unset-commit")]
+        unset_commit: bool,
+        /// Text printed after a successful init ({{project}}, {{template}} and {{target}} are filled in)
+        #[arg(long = "next-steps", conflicts_with = "unset_next_steps")]
+        next_steps: Option<String>,
+        /// Clear the next-steps text
+        #[arg(long = "unset-next-steps")]
+        unset_next_steps: bool,
+        /// .gitignore to add on init when there's none: a file path, or the content itself
+        #[arg(long = "gitignore-template", value_name = "PATH_OR_CONTENT", conflicts_with = "unset_gitignore_template")]
+        gitignore_template: Option<String>,
+        /// Fall back to the config's gitignore_template again
+        #[arg(long = "unset-gitignore-template")]
+        unset_gitignore_template: bool,
+        /// Skip template files larger than this many bytes (overrides the config)
+        #[arg(long = "max-file-size", value_name = "BYTES", conflicts_with = "unset_max_file_size")]
+        max_file_size: Option<u64>,
+        /// Fall back to the config's max_file_size again
+        #[arg(long = "unset-max-file-size")]
+        unset_max_file_size: bool,
+        /// Skip template files that look binary (overrides the config; --exclude-binaries=false keeps them)
+        #[arg(long = "exclude-binaries", value_name = "BOOL", num_args = 0..=1, require_equals = true,
+              default_missing_value = "true", conflicts_with = "unset_exclude_binaries")]
+        exclude_binaries: Option<bool>,
+        /// Fall back to the config's exclude_binaries again
+        #[arg(long = "unset-exclude-binaries")]
+        unset_exclude_binaries: bool,
         /// Replace template-level exclude patterns (e.g. --exclude dist --exclude "*.log")
         #[arg(long, num_args = 1..)]
         exclude: Vec<String>,
         /// Clear all template-level exclude patterns
         #[arg(long = "clear-exclude")]
         clear_exclude: bool,
+        /// Append a pattern to the template-level exclude list (repeatable)
+        #[arg(long = "add-exclude", conflicts_with_all = ["exclude", "clear_exclude"])]
+        add_exclude: Vec<String>,
+        /// Remove a pattern from the template-level exclude list (repeatable)
+        #[arg(long = "remove-exclude", conflicts_with_all = ["exclude", "clear_exclude"])]
+        remove_exclude: Vec<String>,
         /// Write mode override, or unset to remove template-level override
         #[arg(long = "write-mode")]
         write_mode: Option<WriteModeChangeArg>,
+        /// Subdirectory of the target path to init into (e.g. docs)
+        #[arg(long = "target-subdir")]
+        target_subdir: Option<String>,
+        /// Clear the target subdirectory
+        #[arg(long = "unset-target-subdir")]
+        unset_target_subdir: bool,
+        /// Replace template tags (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Clear all template tags
+        #[arg(long = "clear-tags")]
+        clear_tags: bool,
+        /// Folder inside the repo or directory to use as the template
+        #[arg(long, alias = "template-subdir")]
+        subdir: Option<String>,
+        /// Use the whole repo or directory as the template again
+        #[arg(long = "unset-subdir", conflicts_with = "subdir")]
+        unset_subdir: bool,
     },
     /// List registered templates and their paths
     List {
         /// Print only template names, one per line
         #[arg(long = "names-only")]
         names_only: bool,
-        /// Force coloured output
-        #[arg(long, overrides_with = "no_color")]
-        color: bool,
-        /// Disable coloured output
-        #[arg(long = "no-color", overrides_with = "color")]
-        no_color: bool,
+        /// Only show templates with this tag (repeatable; all must match)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Row order: name, location, or added (newest first)
+        #[arg(long, value_enum, default_value_t)]
+        sort: SortArg,
+        /// Print the templates as a JSON array
+        #[arg(long, conflicts_with = "names_only")]
+        json: bool,
+        /// Check pinned refs of uncached git templates against the remote (network)
+        #[arg(long)]
+        remote: bool,
+        /// Add a MODE column with the git mode each template would init with
+        #[arg(long = "show-mode")]
+        show_mode: bool,
+    },
+    /// Show a template's details merged with the config, and where each setting comes from
+    Info {
+        /// Template name
+        template_name: String,
+        /// Print the details as JSON
+        #[arg(long)]
+        json: bool,
+        /// Also report the total size and file count init would copy (fetches URL templates)
+        #[arg(long)]
+        size: bool,
+        /// With --size, measure as if init got this --var (NAME alone means true)
+        #[arg(long = "var", value_name = "NAME[=VALUE]", value_parser = parse_var, requires = "size")]
+        vars: Vec<(String, String)>,
+    },
+    /// Print the checksum of a template's source, for pinning it
+    Checksum {
+        /// Template name
+        template_name: String,
+        /// Pin the template to this checksum; init then refuses a source that changed
+        #[arg(long)]
+        save: bool,
     },
     /// Generate a shell completion script
     Completions {
         /// Shell to generate completions for
         shell: Shell,
         /// Check if an installed script is up to date
-        #[arg(long)]
+        #[arg(long, conflicts_with_all = ["install", "print_path"])]
         check: Option<PathBuf>,
+        /// Write the script to the shell's usual completions directory
+        #[arg(long, conflicts_with = "print_path")]
+        install: bool,
+        /// Print where --install would write the script
+        #[arg(long = "print-path")]
+        print_path: bool,
+        /// Print a script generated from the CLI definition (no template-name completion)
+        #[arg(long, conflicts_with_all = ["check", "install", "print_path"])]
+        generate: bool,
+    },
+    /// Write the template registry as JSON
+    Export {
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Load templates from an exported registry file
+    Import {
+        /// Registry file to import
+        file: PathBuf,
+        /// Add imported templates alongside existing ones (default)
+        #[arg(long, conflicts_with = "replace")]
+        merge: bool,
+        /// Replace the whole registry with the imported templates
+        #[arg(long)]
+        replace: bool,
+        /// Error on name collisions instead of skipping them
+        #[arg(long, conflicts_with = "replace")]
+        strict: bool,
     },
     /// Update cached git templates
     Update {
-        /// Template name (omit to update all)
+        /// Template name or glob such as 'web-*' (omit to update all)
         template_name: Option<String>,
+        /// Only update templates with this tag (repeatable; all must match)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
         /// Check for updates without applying them
         #[arg(long)]
         check: bool,
+        /// With --check, list the commits each template is behind by
+        #[arg(long, requires = "check")]
+        log: bool,
+        /// Fetch, then print the git commands an update would run without running them
+        #[arg(long = "dry-run", conflicts_with = "check")]
+        dry_run: bool,
+        /// Stop at the first template that fails to update
+        #[arg(long = "fail-fast", overrides_with = "keep_going")]
+        fail_fast: bool,
+        /// Attempt every template and report all failures at the end (the default)
+        #[arg(long = "keep-going", overrides_with = "fail_fast")]
+        keep_going: bool,
+    },
+    /// Manage the clones of URL templates
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
     },
+    /// Restore the registry from before the last add, change or remove
+    Undo,
+}
+
+#[derive(clap::Subcommand)]
+enum CacheCommand {
+    /// Delete cached clones and per-ref checkouts no registered template uses
+    Prune {
+        /// List what would be deleted without deleting it
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+}
+
+/// `--var NAME=VALUE`, or `--var NAME` for a true flag.
+fn parse_var(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg.split_once('=').unwrap_or((arg, "true"));
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("variable name is empty".to_string());
+    }
+    Ok((name.to_string(), value.to_string()))
 }
 
 fn git_mode_arg_to_mode(arg: GitModeArg) -> GitMode {
@@ -202,6 +504,14 @@ fn git_mode_arg_to_mode(arg: GitModeArg) -> GitMode {
     }
 }
 
+fn sort_arg_to_sort(arg: SortArg) -> ListSort {
+    match arg {
+        SortArg::Name => ListSort::Name,
+        SortArg::Location => ListSort::Location,
+        SortArg::Added => ListSort::Added,
+    }
+}
+
 fn write_mode_arg_to_mode(arg: WriteModeArg) -> WriteMode {
     match arg {
         WriteModeArg::Strict => WriteMode::Strict,
@@ -212,48 +522,139 @@ fn write_mode_arg_to_mode(arg: WriteModeArg) -> WriteMode {
     }
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
-    let config = config::Config::load()?;
+impl Cli {
+    /// Whether errors should be reported as `{"error": "..."}` rather than plain text.
+    fn wants_json_errors(&self) -> bool {
+        matches!(self.command, Command::Init { format: FormatArg::Json, .. })
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut config = match cli.config {
+        Some(ref path) => config::Config::load_explicit(path)?,
+        None => config::Config::load()?,
+    };
+    config.flag_registry_dir = cli.registry_dir.clone();
+    config.show_progress = cli.progress;
+    // `list` colours stdout and warnings go to stderr; each is checked on its own.
+    let color =
+        utilities::ColorChoice::resolve(cli.color, cli.no_color, config.color, std::io::stdout().is_terminal());
+    utilities::set_color_choice(utilities::ColorChoice::resolve(
+        cli.color,
+        cli.no_color,
+        config.color,
+        std::io::stderr().is_terminal(),
+    ));
+    if !git_cache::migrate_legacy_cache(&config) {
+        git_cache::note_orphaned_caches(&config);
+    }
     match cli.command {
         Command::Init {
             template_name,
             target_path,
             git,
             write_mode,
-        } => {
-            let git_flag = git.map(git_mode_arg_to_mode);
-            let write_mode_flag = write_mode.map(write_mode_arg_to_mode);
-            ops::cmd_init(
-                config,
-                template_name,
-                target_path,
-                git_flag,
-                write_mode_flag,
-            )
-        }
+            git_ref,
+            yes,
+            assume,
+            exclude,
+            exclude_only,
+            no_hooks,
+            trust_hooks,
+            only_hooks,
+            new_dir_only,
+            output_name,
+            vars,
+            follow_symlinks,
+            commit_message,
+            no_commit,
+            amend,
+            remember,
+            format,
+            verbose,
+        } => ops::cmd_init(
+            config,
+            template_name,
+            target_path,
+            InitOptions {
+                flags: InitFlags {
+                    git: git.map(git_mode_arg_to_mode),
+                    write_mode: write_mode.map(write_mode_arg_to_mode),
+                    git_ref,
+                    exclude,
+                    exclude_only,
+                    follow_symlinks,
+                    commit_message,
+                    no_commit,
+                    vars: vars.into_iter().collect(),
+                },
+                yes,
+                confirm_overwrite: None,
+                interactive: false,
+                no_hooks,
+                trust_hooks,
+                approve_hooks: None,
+                only_hooks,
+                new_dir_only,
+                output_name,
+                json: format == FormatArg::Json,
+                remember,
+                amend,
+                assume: assume.map(|assume| match assume {
+                    AssumeArg::Overwrite => AssumeAnswer::Overwrite,
+                    AssumeArg::Skip => AssumeAnswer::Skip,
+                }),
+                // Hook output on stdout would corrupt the JSON report.
+                verbose: verbose && format != FormatArg::Json,
+            },
+        ),
         Command::Add {
             path,
             name,
             description,
             git,
             git_ref,
+            commit,
             exclude,
             write_mode,
-        } => {
-            let git_flag = git.map(git_mode_arg_to_mode);
-            let write_mode_flag = write_mode.map(write_mode_arg_to_mode);
-            ops::cmd_add(
-                path,
+            tags,
+            force,
+            subdir,
+            stdin,
+            allow_empty,
+            gitignore_template,
+            max_file_size,
+            exclude_binaries,
+        } => ops::cmd_add(
+            &config,
+            path,
+            AddOptions {
                 name,
                 description,
-                git_flag,
+                git: git.map(git_mode_arg_to_mode),
                 git_ref,
                 exclude,
-                write_mode_flag,
-            )
+                write_mode: write_mode.map(write_mode_arg_to_mode),
+                tags,
+                force,
+                subdir,
+                commit,
+                stdin,
+                allow_empty,
+                gitignore_template,
+                max_file_size,
+                exclude_binaries,
+            },
+        ),
+        Command::AddAll { dir, git, write_mode } => {
+            ops::cmd_add_all(&config, dir, git.map(git_mode_arg_to_mode), write_mode.map(write_mode_arg_to_mode))
         }
-        Command::Remove { template_names } => ops::cmd_remove(template_names),
+        Command::Remove {
+            template_names,
+            all,
+            missing,
+            yes,
+        } => ops::cmd_remove(&config, template_names, all, missing, yes),
         Command::Change {
             template_name,
             name,
@@ -267,9 +668,27 @@ fn run() -> Result<()> {
             unset_post_init,
             git_ref,
             unset_git_ref,
+            commit,
+            unset_commit,
+            next_steps,
+            unset_next_steps,
+            gitignore_template,
+            unset_gitignore_template,
+            max_file_size,
+            unset_max_file_size,
+            exclude_binaries,
+            unset_exclude_binaries,
             exclude,
             clear_exclude,
+            add_exclude,
+            remove_exclude,
             write_mode,
+            target_subdir,
+            unset_target_subdir,
+            tags,
+            clear_tags,
+            subdir,
+            unset_subdir,
         } => {
             let git_override = git.map(|git_arg| match git_arg {
                 GitModeChangeArg::Fresh => Some(GitMode::Fresh),
@@ -284,6 +703,13 @@ fn run() -> Result<()> {
             } else {
                 None
             };
+            let tags_change = if clear_tags {
+                Some(None)
+            } else if !tags.is_empty() {
+                Some(Some(tags))
+            } else {
+                None
+            };
             let write_mode_change = write_mode.map(|arg| match arg {
                 WriteModeChangeArg::Unset => None,
                 WriteModeChangeArg::Strict => Some(WriteMode::Strict),
@@ -293,6 +719,7 @@ fn run() -> Result<()> {
                 WriteModeChangeArg::Ask => Some(WriteMode::Ask),
             });
             ops::cmd_change(
+                &config,
                 template_name,
                 ChangeOptions {
                     name,
@@ -318,37 +745,162 @@ fn run() -> Result<()> {
                     } else {
                         git_ref.map(Some)
                     },
+                    commit: if unset_commit {
+                        Some(None)
+                    } else {
+                        commit.map(Some)
+                    },
+                    next_steps: if unset_next_steps {
+                        Some(None)
+                    } else {
+                        next_steps.map(Some)
+                    },
+                    gitignore_template: if unset_gitignore_template {
+                        Some(None)
+                    } else {
+                        gitignore_template.map(Some)
+                    },
+                    max_file_size: if unset_max_file_size {
+                        Some(None)
+                    } else {
+                        max_file_size.map(Some)
+                    },
+                    exclude_binaries: if unset_exclude_binaries {
+                        Some(None)
+                    } else {
+                        exclude_binaries.map(Some)
+                    },
                     exclude: exclude_change,
+                    add_exclude,
+                    remove_exclude,
                     write_mode: write_mode_change,
+                    target_subdir: if unset_target_subdir {
+                        Some(None)
+                    } else {
+                        target_subdir.map(Some)
+                    },
+                    tags: tags_change,
+                    subdir: if unset_subdir {
+                        Some(None)
+                    } else {
+                        subdir.map(Some)
+                    },
                 },
             )
         }
-        Command::Completions { shell, check } => ops::cmd_completions(shell, check),
-        Command::List { names_only, color, no_color } => {
-            let color = if no_color { false }
-                else if color { true }
-                else if std::env::var_os("NO_COLOR").is_some() { false }
-                else { config.color };
-            ops::cmd_list(color, names_only)
+        Command::Completions { shell, check, install, print_path, generate } => {
+            let action = match check {
+                Some(path) => CompletionsAction::Check(path),
+                None if generate => CompletionsAction::Generate(Box::new(Cli::command())),
+                None if install => CompletionsAction::Install,
+                None if print_path => CompletionsAction::PrintPath,
+                None => CompletionsAction::Print,
+            };
+            ops::cmd_completions(shell, action)
+        }
+        Command::List { names_only, tags, sort, json, remote, show_mode } => ops::cmd_list(
+            &config,
+            color,
+            ListOptions { names_only, tags, sort: sort_arg_to_sort(sort), json, remote, show_mode },
+        ),
+        Command::Info { template_name, json, size, vars } => {
+            ops::cmd_info(config, template_name, json, size, vars.into_iter().collect())
         }
+        Command::Checksum { template_name, save } => ops::cmd_checksum(config, template_name, save),
+        Command::Cache { action: CacheCommand::Prune { dry_run } } => ops::cmd_cache_prune(&config, dry_run),
+        Command::Undo => ops::cmd_undo(&config),
+        Command::Export { output } => ops::cmd_export(&config, output),
+        Command::Import {
+            file,
+            merge: _,
+            replace,
+            strict,
+        } => ops::cmd_import(&config, file, replace, strict),
         Command::Update {
             template_name,
+            tags,
             check,
-        } => ops::cmd_update(template_name, check),
+            log,
+            dry_run,
+            fail_fast,
+            keep_going: _,
+        } => {
+            let mode = if check {
+                UpdateMode::Check { log }
+            } else if dry_run {
+                UpdateMode::DryRun
+            } else {
+                UpdateMode::Apply
+            };
+            ops::cmd_update(&config, template_name, tags, mode, fail_fast)
+        }
     }
 }
 
+/// Logs to stderr, filtered by `RUST_LOG`, so `--format json` output stays clean.
+#[cfg(feature = "tracing")]
+fn init_tracing() {
+    if std::env::var_os("RUST_LOG").is_none() {
+        return;
+    }
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+}
+
 fn main() {
-    if let Err(err) = run() {
-        eprintln!("{:#}", err);
-        std::process::exit(1);
+    #[cfg(feature = "tracing")]
+    init_tracing();
+    let cli = Cli::parse();
+    let json_errors = cli.wants_json_errors();
+    if let Err(err) = run(cli) {
+        if json_errors {
+            eprintln!("{}", serde_json::json!({ "error": format!("{:#}", err) }));
+        } else {
+            eprintln!("{:#}", err);
+        }
+        let code = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<TemplativeError>())
+            .map_or(1, TemplativeError::exit_code);
+        std::process::exit(code);
     }
 }
 
-/// Shared test infrastructure. All tests that touch `TEMPLATIVE_CONFIG_DIR`
-/// must hold this lock to prevent parallel tests in different modules from
-/// racing on the same environment variable.
 #[cfg(test)]
-pub mod test_env {
-    pub static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+mod tests {
+    use super::*;
+
+    /// Every long flag of every subcommand, as `(subcommand, flag)`.
+    fn cli_flags() -> Vec<(String, String)> {
+        let command = Cli::command();
+        let global = command.get_arguments().filter_map(|arg| arg.get_long()).map(|long| ("".to_string(), long.to_string()));
+        let subcommands = command.get_subcommands().flat_map(|sub| {
+            sub.get_arguments()
+                .filter_map(|arg| arg.get_long())
+                .map(|long| (sub.get_name().to_string(), long.to_string()))
+                .collect::<Vec<_>>()
+        });
+        global.chain(subcommands).filter(|(_, long)| long != "help").collect()
+    }
+
+    #[test]
+    fn hand_written_completions_cover_every_flag() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let script = ops::completion_script(&shell);
+            let missing: Vec<_> = cli_flags()
+                .into_iter()
+                .filter(|(_, long)| {
+                    let needle = match shell {
+                        Shell::Fish => format!("-l {}", long),
+                        _ => format!("--{}", long),
+                    };
+                    !script.contains(&needle)
+                })
+                .collect();
+            let name = clap::ValueEnum::to_possible_value(&shell).unwrap().get_name().to_string();
+            assert!(missing.is_empty(), "{} completions are missing {:?}", name, missing);
+        }
+    }
 }
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::config::{Config, GitMode, PathTokenStyle};
+use crate::errors::TemplativeError;
+use crate::registry::Template;
+use crate::utilities;
+
+/// File at the template root where template authors ship their own defaults.
+pub const MANIFEST_FILE: &str = ".templative.toml";
+
+/// Defaults declared inside a template by its author. They sit between the config and the
+/// registry entry: flag > template field > manifest > config. The manifest itself is never
+/// copied into projects.
+#[derive(Debug, Default, Deserialize)]
+pub struct Manifest {
+    /// Added to the config's exclude patterns, like the template's own.
+    pub exclude: Option<Vec<String>>,
+    pub pre_init: Option<String>,
+    pub post_init: Option<String>,
+    pub git: Option<GitMode>,
+    /// Destination names for template paths, e.g. `gitignore = ".gitignore"` so a dotfile
+    /// template can keep its files visible.
+    pub renames: Option<BTreeMap<String, String>>,
+    /// How tokens in this template's file names are written; see `PathTokenStyle`.
+    pub path_token_style: Option<PathTokenStyle>,
+    /// Paths copied only when an `init --var` is true, written as `[[conditional]]` tables.
+    #[serde(rename = "conditional")]
+    pub conditionals: Option<Vec<Conditional>>,
+}
+
+/// The manifest key naming the oldest templative that understands the template, e.g.
+/// `min_templative_version = "0.5.0"`. It is read before the rest of the manifest.
+const MIN_VERSION_KEY: &str = "min_templative_version";
+
+/// A template path (file or folder, relative to the template root) that is only copied when
+/// the variable named by `when` is true.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Conditional {
+    pub path: String,
+    pub when: String,
+}
+
+impl Manifest {
+    /// Reads `dir/.templative.toml`; `None` when `dir` has none. `min_templative_version` is
+    /// checked on the raw TOML first, so a manifest using settings this version doesn't know
+    /// fails with `TemplativeTooOld` rather than a parse error.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents =
+            std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let table: toml::Table = toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))?;
+        if let Some(required) = table.get(MIN_VERSION_KEY) {
+            let required = required
+                .as_str()
+                .with_context(|| format!("{} in {} must be a string", MIN_VERSION_KEY, path.display()))?;
+            check_version(required)?;
+        }
+        let manifest = toml::Value::Table(table).try_into().with_context(|| format!("invalid {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    /// The manifest of `template` as it sits on disk now, without fetching or checking out
+    /// anything: the local folder, or a URL template's cache if it has been cloned. For
+    /// commands like `info` that don't resolve the template source.
+    pub fn load_for(config: &Config, template: &Template) -> Result<Option<Self>> {
+        let root = if utilities::is_git_url(&template.location) {
+            utilities::cache_path_for_url(config, &template.location)?
+        } else {
+            utilities::expand_location(&template.location)?
+        };
+        let root = match template.subdir {
+            Some(ref subdir) => root.join(subdir),
+            None => root,
+        };
+        if !root.is_dir() {
+            return Ok(None);
+        }
+        Self::load(&root)
+    }
+}
+
+/// Errors when the running templative is older than `required`, instead of quietly ignoring
+/// manifest features it doesn't know.
+fn check_version(required: &str) -> Result<()> {
+    let minimum = semver::Version::parse(required.trim())
+        .with_context(|| format!("invalid {} {:?} in {}", MIN_VERSION_KEY, required, MANIFEST_FILE))?;
+    let running = semver::Version::parse(env!("CARGO_PKG_VERSION")).expect("crate version is semver");
+    if running < minimum {
+        return Err(TemplativeError::TemplativeTooOld {
+            required: minimum.to_string(),
+            running: running.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_reads_fields_and_ignores_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Manifest::load(dir.path()).unwrap().is_none());
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE),
+            "exclude = [\"dist\"]\npost_init = \"npm install\"\ngit = \"no-git\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::load(dir.path()).unwrap().unwrap();
+        assert_eq!(manifest.exclude, Some(vec!["dist".to_string()]));
+        assert_eq!(manifest.post_init.as_deref(), Some("npm install"));
+        assert_eq!(manifest.git, Some(GitMode::NoGit));
+        assert!(manifest.pre_init.is_none());
+    }
+
+    #[test]
+    fn check_version_rejects_newer_requirements() {
+        assert!(check_version("0.0.1").is_ok());
+        assert!(check_version(env!("CARGO_PKG_VERSION")).is_ok());
+        let err = check_version("999.0.0").unwrap_err();
+        assert!(matches!(err.downcast_ref::<TemplativeError>(), Some(TemplativeError::TemplativeTooOld { .. })));
+        assert!(check_version("soon").unwrap_err().to_string().contains("invalid"));
+    }
+
+    #[test]
+    fn load_checks_the_version_before_settings_it_cant_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE),
+            "min_templative_version = \"999.0.0\"\ngit = \"some-future-mode\"\n",
+        )
+        .unwrap();
+        let err = Manifest::load(dir.path()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<TemplativeError>(), Some(TemplativeError::TemplativeTooOld { .. })));
+
+        std::fs::write(dir.path().join(MANIFEST_FILE), "min_templative_version = 1\n").unwrap();
+        assert!(Manifest::load(dir.path()).unwrap_err().to_string().contains("must be a string"));
+    }
+
+    #[test]
+    fn load_reports_invalid_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(MANIFEST_FILE), "git = [").unwrap();
+        assert!(Manifest::load(dir.path()).is_err());
+    }
+}
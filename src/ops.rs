@@ -1,17 +1,29 @@
 mod add;
+mod cache;
 mod change;
+mod checksum;
 mod completions;
+mod export;
+mod import;
+mod info;
 mod init;
 mod list;
 mod remove;
+mod undo;
 mod update;
 #[cfg(test)]
 mod tests;
 
-pub use add::cmd_add;
+pub use add::{cmd_add, cmd_add_all, AddOptions};
+pub use cache::cmd_cache_prune;
 pub use change::{cmd_change, ChangeOptions};
-pub use completions::{cmd_completions, Shell};
-pub use init::cmd_init;
-pub use list::cmd_list;
+pub use checksum::cmd_checksum;
+pub use completions::{all_completion_versions, cmd_completions, script as completion_script, CompletionsAction, Shell};
+pub use export::cmd_export;
+pub use import::cmd_import;
+pub use info::cmd_info;
+pub use init::{cmd_init, ApproveHooks, ConfirmOverwrite, init_template, AssumeAnswer, InitNote, InitOptions, InitOutcome};
+pub use list::{cmd_list, ListOptions};
 pub use remove::cmd_remove;
-pub use update::cmd_update;
+pub use undo::cmd_undo;
+pub use update::{cmd_update, UpdateMode};
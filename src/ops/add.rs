@@ -1,48 +1,222 @@
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
-use crate::config::{GitMode, WriteMode};
+use crate::config::{Config, GitMode, WriteMode};
+use crate::errors::TemplativeError;
+use crate::fs_copy;
+use crate::git::{self, RefKind};
 use crate::git_cache;
 use crate::registry::{Registry, Template};
 use crate::utilities;
 
-pub fn cmd_add(
+#[derive(Default)]
+pub struct AddOptions {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub git: Option<GitMode>,
+    pub git_ref: Option<String>,
+    pub exclude: Vec<String>,
+    pub write_mode: Option<WriteMode>,
+    pub tags: Vec<String>,
+    /// Replace an existing template with the same name instead of erroring.
+    pub force: bool,
+    /// Folder inside the source to use as the template root.
+    pub subdir: Option<String>,
+    /// Commit to pin to; must exist in the template's repo.
+    pub commit: Option<String>,
+    /// Read a single-file template from stdin into the snippets directory.
+    pub stdin: bool,
+    /// Register a local path that doesn't exist yet, stored as an absolute path.
+    pub allow_empty: bool,
+    /// The template's `.gitignore` to add on init: a file path or the content itself.
+    pub gitignore_template: Option<String>,
+    /// Overrides the config's `max_file_size` for this template.
+    pub max_file_size: Option<u64>,
+    /// Overrides the config's `exclude_binaries` for this template.
+    pub exclude_binaries: Option<bool>,
+}
+
+pub fn cmd_add(config: &Config, path: String, options: AddOptions) -> Result<()> {
+    add_reading(config, path, options, &mut std::io::stdin())
+}
+
+/// `cmd_add`, with a `--stdin` snippet read from `input`. The snippet file is only written
+/// once the registry is saved, so a failed add leaves nothing behind.
+pub(super) fn add_reading(config: &Config, path: String, options: AddOptions, input: &mut dyn Read) -> Result<()> {
+    let force = options.force;
+    let (template, snippet) = build_template(config, path, options, input)?;
+    let mut registry = Registry::load_for_update(config)?;
+    let (template_name, location) = (template.name.clone(), template.location.clone());
+    let pin = describe_template_pin(&template);
+    let replaced = registry.get(&template_name).map(|existing| existing.location.clone());
+    if force {
+        registry.add_or_replace(template);
+    } else {
+        registry.add(template)?;
+    }
+    registry.save()?;
+    if let Some(contents) = snippet {
+        write_snippet(Path::new(&location), &contents)?;
+    }
+    if let Some(replaced) = replaced
+        && replaced != location
+    {
+        remove_snippet(config, &replaced);
+    }
+    println!("added {} -> {}", template_name, location);
+    if let Some(pin) = pin {
+        println!("{}", pin);
+    }
+    Ok(())
+}
+
+/// Registers every immediate subdirectory of `dir` as a template named after the folder,
+/// skipping hidden folders and names that are already registered.
+pub fn cmd_add_all(config: &Config, dir: String, git: Option<GitMode>, write_mode: Option<WriteMode>) -> Result<()> {
+    let dir = PathBuf::from(&dir)
+        .canonicalize()
+        .with_context(|| format!("path not found: {}", dir))?;
+    let entries = std::fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    let mut subdirs = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("failed to read {}", dir.display()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with('.') && entry.path().is_dir() {
+            subdirs.push((name, entry.path()));
+        }
+    }
+    subdirs.sort();
+
+    let mut registry = Registry::load_for_update(config)?;
+    // Printed once the save succeeds, so nothing claims to be added when it wasn't.
+    let mut lines = Vec::new();
+    let (mut added, mut skipped) = (0, 0);
+    for (name, path) in subdirs {
+        if registry.get(&name).is_some() {
+            lines.push(format!("skipped {} (already registered)", name));
+            skipped += 1;
+            continue;
+        }
+        let options = AddOptions { name: Some(name), git: git.clone(), write_mode: write_mode.clone(), ..AddOptions::default() };
+        let path = path.to_string_lossy().into_owned();
+        let (template, _) = build_template(config, path, options, &mut std::io::empty())?;
+        let line = format!("added {} -> {}", template.name, template.location);
+        registry.add(template)?;
+        lines.push(line);
+        added += 1;
+    }
+    registry.save()?;
+    for line in lines {
+        println!("{}", line);
+    }
+    println!("{} added, {} skipped", added, skipped);
+    Ok(())
+}
+
+/// Checks `path` and builds the template `add` would register for it, without touching the
+/// registry (other than refusing a `--stdin` name that's taken). With `--stdin`, also returns
+/// the snippet read from `input`, for the caller to write to the template's location.
+fn build_template(
+    config: &Config,
     path: String,
-    name: Option<String>,
-    description: Option<String>,
-    git: Option<GitMode>,
-    git_ref: Option<String>,
-    exclude: Vec<String>,
-    write_mode: Option<WriteMode>,
-) -> Result<()> {
-    let (location, template_name) = if utilities::is_git_url(&path) {
-        git_cache::ensure_cached(&path)?;
-        let name = name.unwrap_or_else(|| {
-            path.trim_end_matches('/')
-                .rsplit('/')
-                .next()
-                .unwrap_or("template")
-                .trim_end_matches(".git")
-                .to_string()
-        });
+    options: AddOptions,
+    input: &mut dyn Read,
+) -> Result<(Template, Option<Vec<u8>>)> {
+    let AddOptions {
+        name,
+        description,
+        git,
+        git_ref,
+        exclude,
+        write_mode,
+        tags,
+        force,
+        subdir,
+        commit,
+        stdin,
+        allow_empty,
+        gitignore_template,
+        max_file_size,
+        exclude_binaries,
+    } = options;
+    fs_copy::validate_exclude_patterns(&exclude)?;
+    let mut default_branch = None;
+    let mut git_ref_kind = None;
+    let mut snippet = None;
+    let (location, template_name) = if stdin {
+        let name = name.context("--stdin needs --name to name the snippet")?;
+        if !force && Registry::load(config)?.get(&name).is_some() {
+            return Err(TemplativeError::TemplateExists { name }.into());
+        }
+        let path = snippet_path(config, &name)?;
+        let mut contents = Vec::new();
+        input.read_to_end(&mut contents).context("failed to read stdin")?;
+        snippet = Some(contents);
+        (path.to_string_lossy().into_owned(), name)
+    } else if utilities::is_git_url(&path) {
+        git::ensure_available()?;
+        // A quick ls-remote gives a clearer answer for a wrong URL than a failed clone would.
+        // An existing cache is used as is, so it isn't needed (nor the network) then.
+        if !utilities::cache_path_for_url(config, &path)?.exists() {
+            match git::remote_exists(&path) {
+                Ok(true) => {}
+                Ok(false) => {
+                    utilities::warn(format_args!(
+                        "{} has no branches or tags yet; push a commit before using it",
+                        path
+                    ));
+                }
+                Err(err) => {
+                    return Err(TemplativeError::RemoteUnreachable { url: path, reason: format!("{:#}", err) }.into());
+                }
+            }
+        }
+        let cache_path = git_cache::ensure_cached(config, &path)?;
+        default_branch = git::default_branch(&cache_path).ok();
+        git_ref_kind = classify_pinned_ref(&cache_path, git_ref.as_deref());
+        if let Some(ref commit) = commit {
+            verify_commit(&cache_path, commit)?;
+        }
+        let name = name.unwrap_or_else(|| utilities::name_from_location(&path));
         (path, name)
     } else {
-        let canonical = PathBuf::from(&path)
-            .canonicalize()
-            .with_context(|| format!("path not found: {}", path))?;
+        let expanded = utilities::expand_location(&path)?;
+        let canonical = match expanded.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(_) if allow_empty => {
+                let absolute =
+                    std::path::absolute(&expanded).with_context(|| format!("invalid path: {}", path))?;
+                eprintln!("note: {} doesn't exist yet; create it before using the template", absolute.display());
+                absolute
+            }
+            Err(err) => return Err(err).with_context(|| format!("path not found: {}", path)),
+        };
+        if git::is_git_repo(&canonical) {
+            git_ref_kind = classify_pinned_ref(&canonical, git_ref.as_deref());
+            if let Some(ref commit) = commit {
+                verify_commit(&canonical, commit)?;
+            }
+        } else if commit.is_some() {
+            anyhow::bail!("--commit needs a git template; {} is not a git repository", canonical.display());
+        }
         let name = name.unwrap_or_else(|| {
             canonical
                 .file_name()
                 .map(|os_str| os_str.to_string_lossy().into_owned())
                 .unwrap_or_else(|| "template".to_string())
         });
-        (canonical.to_string_lossy().into_owned(), name)
+        if utilities::location_uses_variables(&path) {
+            (path, name)
+        } else {
+            (canonical.to_string_lossy().into_owned(), name)
+        }
     };
 
     let template = Template {
-        name: template_name.clone(),
-        location: location.clone(),
+        name: template_name,
+        location,
         git,
         description,
         pre_init: None,
@@ -50,10 +224,123 @@ pub fn cmd_add(
         git_ref,
         exclude: if exclude.is_empty() { None } else { Some(exclude) },
         write_mode,
+        target_subdir: None,
+        tags: if tags.is_empty() { None } else { Some(tags) },
+        default_branch,
+        created_at: Some(utilities::now_rfc3339()),
+        last_used: None,
+        subdir,
+        git_ref_kind,
+        gitignore_template,
+        commit,
+        next_steps: None,
+        max_file_size,
+        exclude_binaries,
+        checksum: None,
     };
-    let mut registry = Registry::load()?;
-    registry.add(template)?;
-    registry.save()?;
-    println!("added {} -> {}", template_name, location);
+    Ok((template, snippet))
+}
+
+/// The pin line `add` prints after registering `template`, if it's pinned to anything.
+fn describe_template_pin(template: &Template) -> Option<String> {
+    if let Some(ref commit) = template.commit {
+        Some(describe_pin(RefKind::Commit, commit))
+    } else if let (Some(git_ref), Some(kind)) = (&template.git_ref, template.git_ref_kind) {
+        Some(describe_pin(kind, git_ref))
+    } else {
+        None
+    }
+}
+
+/// Where the snippet `name` is stored: `snippets/<name>` in the config directory.
+fn snippet_path(config: &Config, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        anyhow::bail!("invalid snippet name {:?}: it's used as a file name", name);
+    }
+    Ok(utilities::snippets_dir(config)?.join(name))
+}
+
+/// Writes `contents` to the snippet file at `path`, replacing any earlier snippet there.
+fn write_snippet(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Deletes the snippet file at `location` once no template uses it. Locations outside the
+/// snippets directory are the user's own files and are left alone.
+pub(super) fn remove_snippet(config: &Config, location: &str) {
+    let path = Path::new(location);
+    let Ok(dir) = utilities::snippets_dir(config) else { return };
+    if path.parent() != Some(dir.as_path()) || !path.is_file() {
+        return;
+    }
+    if let Err(err) = std::fs::remove_file(path) {
+        utilities::warn(format_args!("could not remove snippet {}: {}", path.display(), err));
+    }
+}
+
+/// Classifies `git_ref` in `repo`, or `None` when no ref is pinned or it doesn't exist there.
+pub(super) fn classify_pinned_ref(repo: &Path, git_ref: Option<&str>) -> Option<RefKind> {
+    let git_ref = git_ref?;
+    git::ref_exists(repo, git_ref).then(|| git::classify_ref(repo, git_ref))
+}
+
+/// Errors unless `commit` names a commit in `repo` (a branch or tag name doesn't count).
+fn verify_commit(repo: &Path, commit: &str) -> Result<()> {
+    if !git::ref_exists(repo, commit) || git::classify_ref(repo, commit) != RefKind::Commit {
+        anyhow::bail!("{} is not a commit in {}", commit, repo.display());
+    }
     Ok(())
 }
+
+/// Tells the user whether `update` will move the pinned ref forward.
+fn describe_pin(kind: RefKind, git_ref: &str) -> String {
+    match kind {
+        RefKind::Branch => format!("pinned to branch '{}' (will update)", git_ref),
+        RefKind::Tag => format!("pinned to tag '{}' (immutable)", git_ref),
+        RefKind::Commit => format!("pinned to commit '{}' (immutable)", git_ref),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn pinned_refs_are_classified_and_described() {
+        let repo = tempdir().unwrap();
+        git(repo.path(), &["init", "-b", "main"]);
+        std::fs::write(repo.path().join("file.txt"), "v1").unwrap();
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-m", "initial"]);
+        git(repo.path(), &["tag", "v1.0"]);
+
+        assert_eq!(classify_pinned_ref(repo.path(), Some("main")), Some(RefKind::Branch));
+        assert_eq!(classify_pinned_ref(repo.path(), Some("v1.0")), Some(RefKind::Tag));
+        assert_eq!(classify_pinned_ref(repo.path(), Some("missing")), None);
+        assert_eq!(describe_pin(RefKind::Branch, "main"), "pinned to branch 'main' (will update)");
+        assert_eq!(describe_pin(RefKind::Tag, "v1.0"), "pinned to tag 'v1.0' (immutable)");
+        assert!(describe_pin(RefKind::Commit, "abc123").ends_with("(immutable)"));
+    }
+
+    #[test]
+    fn snippet_names_must_be_plain_file_names() {
+        assert!(snippet_path(&Config::new(), "../escape").is_err());
+        assert!(snippet_path(&Config::new(), "..").is_err());
+        assert!(snippet_path(&Config::new(), "").is_err());
+    }
+}
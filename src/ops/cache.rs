@@ -0,0 +1,18 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::git_cache;
+use crate::registry::Registry;
+
+/// Deletes cached clones and ref worktrees that no registered template needs anymore. With
+/// `dry_run` it only lists them.
+pub fn cmd_cache_prune(config: &Config, dry_run: bool) -> Result<()> {
+    let registry = Registry::load(config)?;
+    let pruned = git_cache::prune(config, &registry.templates, dry_run)?;
+    let verb = if dry_run { "would remove" } else { "removed" };
+    for path in pruned.caches.iter().chain(&pruned.worktrees) {
+        println!("{} {}", verb, path.display());
+    }
+    println!("{} {} cached clone(s) and {} ref checkout(s)", verb, pruned.caches.len(), pruned.worktrees.len());
+    Ok(())
+}
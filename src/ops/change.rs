@@ -1,21 +1,35 @@
-use std::path::PathBuf;
-
 use anyhow::{Context, Result};
 
-use crate::config::{GitMode, WriteMode};
+use crate::config::{Config, GitMode, WriteMode};
 use crate::errors::TemplativeError;
 use crate::registry::Registry;
+use crate::{fs_copy, git, utilities};
+
+use super::add;
 
 pub struct ChangeOptions {
     pub name: Option<String>,
     pub description: Option<Option<String>>,
-    pub location: Option<PathBuf>,
+    /// A git URL, stored as given, or a local path, which must exist.
+    pub location: Option<String>,
     pub git: Option<Option<GitMode>>,
     pub pre_init: Option<Option<String>>,
     pub post_init: Option<Option<String>>,
     pub git_ref: Option<Option<String>>,
     pub exclude: Option<Option<Vec<String>>>,
+    /// Patterns appended to the template's exclude list (created if absent).
+    pub add_exclude: Vec<String>,
+    /// Patterns removed from the template's exclude list.
+    pub remove_exclude: Vec<String>,
     pub write_mode: Option<Option<WriteMode>>,
+    pub target_subdir: Option<Option<String>>,
+    pub tags: Option<Option<Vec<String>>>,
+    pub subdir: Option<Option<String>>,
+    pub commit: Option<Option<String>>,
+    pub next_steps: Option<Option<String>>,
+    pub gitignore_template: Option<Option<String>>,
+    pub max_file_size: Option<Option<u64>>,
+    pub exclude_binaries: Option<Option<bool>>,
 }
 
 impl ChangeOptions {
@@ -28,51 +42,134 @@ impl ChangeOptions {
             && self.post_init.is_none()
             && self.git_ref.is_none()
             && self.exclude.is_none()
+            && self.add_exclude.is_empty()
+            && self.remove_exclude.is_empty()
             && self.write_mode.is_none()
+            && self.target_subdir.is_none()
+            && self.tags.is_none()
+            && self.subdir.is_none()
+            && self.commit.is_none()
+            && self.next_steps.is_none()
+            && self.gitignore_template.is_none()
+            && self.max_file_size.is_none()
+            && self.exclude_binaries.is_none()
     }
 }
 
-pub fn cmd_change(template_name: String, options: ChangeOptions) -> Result<()> {
+pub fn cmd_change(config: &Config, template_name: String, options: ChangeOptions) -> Result<()> {
     if options.is_empty() {
         anyhow::bail!("no changes specified");
     }
+    if let Some(Some(ref patterns)) = options.exclude {
+        fs_copy::validate_exclude_patterns(patterns)?;
+    }
+    fs_copy::validate_exclude_patterns(&options.add_exclude)?;
+    if let Some(Some(ref subdir)) = options.target_subdir
+        && !utilities::is_plain_relative(std::path::Path::new(subdir))
+    {
+        return Err(TemplativeError::InvalidTargetSubdir { subdir: subdir.clone() }.into());
+    }
 
-    let mut registry = Registry::load()?;
+    let mut registry = Registry::load_for_update(config)?;
+    let new_name = options.name.clone();
+    apply_change(&mut registry, &template_name, options)?;
+    // Checked again on the edited registry: the rename must still leave names unique when it
+    // is written, not just when it was first looked up.
+    if let Some(new_name) = new_name {
+        ensure_unique(&registry, &new_name)?;
+    }
+    registry.save()?;
+    println!("updated {}", template_name);
+    Ok(())
+}
 
-    if registry.get(&template_name).is_none() {
-        return Err(TemplativeError::TemplateNotFound { name: template_name.clone() }.into());
+fn ensure_unique(registry: &Registry, name: &str) -> Result<(), TemplativeError> {
+    if registry.templates.iter().filter(|template| template.name == name).count() > 1 {
+        return Err(TemplativeError::TemplateExists { name: name.to_string() });
     }
-    if let Some(ref new_name) = options.name {
-        if registry.get(new_name).is_some() {
-            return Err(TemplativeError::TemplateExists { name: new_name.clone() }.into());
-        }
+    Ok(())
+}
+
+/// Applies `options` to the template named `template_name` in `registry`, without saving.
+fn apply_change(registry: &mut Registry, template_name: &str, options: ChangeOptions) -> Result<()> {
+    if registry.get(template_name).is_none() {
+        return Err(TemplativeError::TemplateNotFound { name: template_name.to_string() }.into());
+    }
+    if let Some(ref new_name) = options.name
+        && registry.get(new_name).is_some()
+    {
+        return Err(TemplativeError::TemplateExists { name: new_name.clone() }.into());
     }
 
-    let template = registry.get_mut(&template_name).unwrap();
+    let template = registry.get_mut(template_name).unwrap();
 
     if let Some(new_name) = options.name { template.name = new_name; }
     if let Some(new_description) = options.description { template.description = new_description; }
     if let Some(new_git) = options.git { template.git = new_git; }
+    // The default branch and ref kind `add` recorded describe the old source.
+    let mut relocated = None;
     if let Some(new_location) = options.location {
-        let canonical = new_location
-            .canonicalize()
-            .with_context(|| format!("path not found: {}", new_location.display()))?;
-        template.location = canonical.to_string_lossy().into_owned();
+        template.location = if utilities::is_git_url(&new_location) {
+            new_location
+        } else {
+            let canonical = utilities::expand_location(&new_location)?
+                .canonicalize()
+                .with_context(|| format!("path not found: {}", new_location))?;
+            relocated = Some(canonical.clone());
+            if utilities::location_uses_variables(&new_location) {
+                new_location
+            } else {
+                canonical.to_string_lossy().into_owned()
+            }
+        };
+        template.default_branch = None;
+        template.git_ref_kind = None;
     }
     if let Some(new_pre_init) = options.pre_init { template.pre_init = new_pre_init; }
     if let Some(new_post_init) = options.post_init { template.post_init = new_post_init; }
-    if let Some(new_git_ref) = options.git_ref { template.git_ref = new_git_ref; }
+    if let Some(new_git_ref) = options.git_ref {
+        // The recorded kind belonged to the old ref; `list` stops comparing until it's re-added.
+        template.git_ref = new_git_ref;
+        template.git_ref_kind = None;
+    }
+    // A local repo can be re-classified on the spot; a URL would need fetching first.
+    if let Some(ref path) = relocated
+        && git::is_git_repo(path)
+    {
+        template.git_ref_kind = add::classify_pinned_ref(path, template.git_ref.as_deref());
+    }
     if let Some(new_exclude) = options.exclude { template.exclude = new_exclude; }
+    if !options.add_exclude.is_empty() || !options.remove_exclude.is_empty() {
+        let mut exclude = template.exclude.take().unwrap_or_default();
+        for pattern in options.add_exclude {
+            if !exclude.contains(&pattern) {
+                exclude.push(pattern);
+            }
+        }
+        for pattern in &options.remove_exclude {
+            if !exclude.contains(pattern) {
+                utilities::warn(format_args!("{} is not in the exclude list of {}", pattern, template_name));
+            }
+            exclude.retain(|existing| existing != pattern);
+        }
+        template.exclude = if exclude.is_empty() { None } else { Some(exclude) };
+    }
     if let Some(new_write_mode) = options.write_mode { template.write_mode = new_write_mode; }
-
-    registry.save()?;
-    println!("updated {}", template_name);
+    if let Some(new_target_subdir) = options.target_subdir { template.target_subdir = new_target_subdir; }
+    if let Some(new_tags) = options.tags { template.tags = new_tags; }
+    if let Some(new_subdir) = options.subdir { template.subdir = new_subdir; }
+    if let Some(new_commit) = options.commit { template.commit = new_commit; }
+    if let Some(new_next_steps) = options.next_steps { template.next_steps = new_next_steps; }
+    if let Some(new_gitignore) = options.gitignore_template { template.gitignore_template = new_gitignore; }
+    if let Some(new_max_file_size) = options.max_file_size { template.max_file_size = new_max_file_size; }
+    if let Some(new_exclude_binaries) = options.exclude_binaries { template.exclude_binaries = new_exclude_binaries; }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::Template;
 
     fn empty_options() -> ChangeOptions {
         ChangeOptions {
@@ -84,14 +181,36 @@ mod tests {
             post_init: None,
             git_ref: None,
             exclude: None,
+            add_exclude: Vec::new(),
+            remove_exclude: Vec::new(),
             write_mode: None,
+            target_subdir: None,
+            tags: None,
+            subdir: None,
+            commit: None,
+            next_steps: None,
+            gitignore_template: None,
+            max_file_size: None,
+            exclude_binaries: None,
         }
     }
 
     #[test]
     fn errors_when_no_changes_specified() {
-        let result = cmd_change("foo".into(), empty_options());
+        let result = cmd_change(&Config::new(), "foo".into(), empty_options());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("no changes specified"));
     }
+
+    #[test]
+    fn rename_collision_appearing_before_save_is_caught() {
+        let mut registry = Registry::default();
+        registry.templates.push(Template { name: "a".into(), location: "/tmp/a".into(), ..Template::default() });
+        apply_change(&mut registry, "a", ChangeOptions { name: Some("b".into()), ..empty_options() }).unwrap();
+        assert!(ensure_unique(&registry, "b").is_ok());
+
+        // Another writer adds "b" between the first lookup and the save.
+        registry.templates.push(Template { name: "b".into(), location: "/tmp/b".into(), ..Template::default() });
+        assert!(matches!(ensure_unique(&registry, "b"), Err(TemplativeError::TemplateExists { .. })));
+    }
 }
@@ -0,0 +1,32 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::errors::TemplativeError;
+use crate::fs_copy;
+use crate::registry::Registry;
+use crate::resolved::InitFlags;
+
+use super::init;
+
+/// Prints the checksum of the source `init` would copy for `template_name`, and with `save`
+/// pins the template to it.
+pub fn cmd_checksum(config: Config, template_name: String, save: bool) -> Result<()> {
+    let registry = Registry::load(&config)?;
+    let template = registry
+        .get(&template_name)
+        .ok_or_else(|| TemplativeError::TemplateNotFound { name: template_name.clone() })?;
+    let source = init::fetch_source(&config, template, &InitFlags::default())?;
+    init::print_notes(&source.notes);
+    let checksum = fs_copy::tree_checksum(&source.path, source.resolved.follow_symlinks)?;
+    println!("{}", checksum);
+    if save {
+        let mut registry = Registry::load_for_update(&config)?;
+        let template = registry
+            .get_mut(&template_name)
+            .ok_or_else(|| TemplativeError::TemplateNotFound { name: template_name.clone() })?;
+        template.checksum = Some(checksum);
+        registry.save()?;
+        eprintln!("pinned {} to this checksum", template_name);
+    }
+    Ok(())
+}
@@ -8,6 +8,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 
 #[derive(clap::ValueEnum, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Shell {
     Zsh,
     Bash,
@@ -16,7 +17,22 @@ pub enum Shell {
     PowerShell,
 }
 
-pub fn cmd_completions(shell: Shell, check: Option<PathBuf>) -> Result<()> {
+/// What `completions` does with the script.
+pub enum CompletionsAction {
+    /// Write it to stdout.
+    Print,
+    /// Compare its version against the script installed at this path.
+    Check(PathBuf),
+    /// Write it to the shell's conventional location (see `install_path`).
+    Install,
+    /// Show where `Install` would write it, without writing.
+    PrintPath,
+    /// Print a script generated by `clap_complete` from this parser instead of the hand-written
+    /// one. It always matches the CLI but can't complete template names.
+    Generate(Box<clap::Command>),
+}
+
+pub fn cmd_completions(shell: Shell, action: CompletionsAction) -> Result<()> {
     let (script, version) = match shell {
         Shell::Zsh        => (zsh::SCRIPT,        zsh::VERSION),
         Shell::Bash       => (bash::SCRIPT,        bash::VERSION),
@@ -24,9 +40,31 @@ pub fn cmd_completions(shell: Shell, check: Option<PathBuf>) -> Result<()> {
         Shell::PowerShell => (powershell::SCRIPT,  powershell::VERSION),
     };
 
-    match check {
-        None => print!("{}", script),
-        Some(path) => {
+    match action {
+        CompletionsAction::Print => print!("{}", script),
+        CompletionsAction::PrintPath => println!("{}", install_path(&shell)?.display()),
+        CompletionsAction::Generate(mut command) => {
+            let generator = match shell {
+                Shell::Zsh        => clap_complete::Shell::Zsh,
+                Shell::Bash       => clap_complete::Shell::Bash,
+                Shell::Fish       => clap_complete::Shell::Fish,
+                Shell::PowerShell => clap_complete::Shell::PowerShell,
+            };
+            clap_complete::generate(generator, &mut command, "templative", &mut std::io::stdout());
+        }
+        CompletionsAction::Install => {
+            let path = install_path(&shell)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            }
+            std::fs::write(&path, script).with_context(|| format!("failed to write {}", path.display()))?;
+            println!("installed {} completions to {}", shell_name(&shell), path.display());
+            if let Some(note) = install_note(&shell, &path) {
+                println!("{}", note);
+            }
+        }
+        CompletionsAction::Check(path) => {
             let contents = std::fs::read_to_string(&path)
                 .with_context(|| format!("failed to read {}", path.display()))?;
             match parse_version(&contents) {
@@ -54,6 +92,73 @@ pub fn cmd_completions(shell: Shell, check: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
+fn home_dir() -> Result<PathBuf> {
+    let base_dirs = directories::BaseDirs::new().context("could not determine home directory")?;
+    Ok(base_dirs.home_dir().to_path_buf())
+}
+
+/// `$var` if set, else `fallback` under the home directory.
+fn env_dir_or_home(var: &str, fallback: &str) -> Result<PathBuf> {
+    match std::env::var_os(var).filter(|dir| !dir.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(home_dir()?.join(fallback)),
+    }
+}
+
+/// Where `--install` puts the script: the per-user directory each shell loads completions from
+/// (the one the README suggests for zsh, which has no fixed default). PowerShell has none, so
+/// the script goes beside the default `$PROFILE` to be dot-sourced from it.
+fn install_path(shell: &Shell) -> Result<PathBuf> {
+    Ok(match shell {
+        Shell::Zsh => home_dir()?.join(".zsh/completions/_templative"),
+        Shell::Bash => env_dir_or_home("XDG_DATA_HOME", ".local/share")?
+            .join("bash-completion/completions/templative"),
+        Shell::Fish => env_dir_or_home("XDG_CONFIG_HOME", ".config")?.join("fish/completions/templative.fish"),
+        Shell::PowerShell => {
+            #[cfg(windows)]
+            let profile_dir = directories::UserDirs::new()
+                .and_then(|dirs| dirs.document_dir().map(|dir| dir.join("PowerShell")))
+                .context("could not determine documents directory")?;
+            #[cfg(not(windows))]
+            let profile_dir = env_dir_or_home("XDG_CONFIG_HOME", ".config")?.join("powershell");
+            profile_dir.join("templative-completions.ps1")
+        }
+    })
+}
+
+/// The one-time setup a shell still needs after `--install`, if any.
+fn install_note(shell: &Shell, path: &std::path::Path) -> Option<String> {
+    match shell {
+        Shell::Zsh => Some(format!(
+            "add this to ~/.zshrc before compinit if it isn't there already:\n  fpath=({} $fpath)",
+            path.parent()?.display()
+        )),
+        Shell::PowerShell => Some(format!("add this line to $PROFILE to load it:\n  . '{}'", path.display())),
+        Shell::Bash | Shell::Fish => None,
+    }
+}
+
+/// Every shell with the `VERSION` of its hand-written script, for checking all installed
+/// scripts at once.
+pub fn all_completion_versions() -> [(Shell, u32); 4] {
+    [
+        (Shell::Zsh,        zsh::VERSION),
+        (Shell::Bash,       bash::VERSION),
+        (Shell::Fish,       fish::VERSION),
+        (Shell::PowerShell, powershell::VERSION),
+    ]
+}
+
+/// The hand-written script for `shell`.
+pub fn script(shell: &Shell) -> &'static str {
+    match shell {
+        Shell::Zsh        => zsh::SCRIPT,
+        Shell::Bash       => bash::SCRIPT,
+        Shell::Fish       => fish::SCRIPT,
+        Shell::PowerShell => powershell::SCRIPT,
+    }
+}
+
 fn parse_version(contents: &str) -> Option<u32> {
     for line in contents.lines() {
         if let Some(rest) = line.strip_prefix("# templative-completions-version: ") {
@@ -71,3 +176,54 @@ fn shell_name(shell: &Shell) -> &'static str {
         Shell::PowerShell => "powershell",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zsh_script_version_comment_matches_constant() {
+        assert_eq!(parse_version(zsh::SCRIPT), Some(zsh::VERSION));
+    }
+
+    #[test]
+    fn bash_script_version_comment_matches_constant() {
+        assert_eq!(parse_version(bash::SCRIPT), Some(bash::VERSION));
+    }
+
+    #[test]
+    fn fish_script_version_comment_matches_constant() {
+        assert_eq!(parse_version(fish::SCRIPT), Some(fish::VERSION));
+    }
+
+    #[test]
+    fn powershell_script_version_comment_matches_constant() {
+        assert_eq!(parse_version(powershell::SCRIPT), Some(powershell::VERSION));
+    }
+
+    #[test]
+    fn all_completion_versions_match_each_script() {
+        for (shell, version) in all_completion_versions() {
+            assert_eq!(parse_version(script(&shell)), Some(version));
+        }
+    }
+
+    #[test]
+    fn install_paths_follow_xdg_dirs() {
+        let _lock = crate::test_env::ENV_LOCK.lock().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        unsafe {
+            std::env::set_var("XDG_CONFIG_HOME", temp.path().join("config"));
+            std::env::set_var("XDG_DATA_HOME", temp.path().join("data"));
+        }
+        let fish = install_path(&Shell::Fish).unwrap();
+        let bash = install_path(&Shell::Bash).unwrap();
+        unsafe {
+            std::env::remove_var("XDG_CONFIG_HOME");
+            std::env::remove_var("XDG_DATA_HOME");
+        }
+        assert_eq!(fish, temp.path().join("config/fish/completions/templative.fish"));
+        assert_eq!(bash, temp.path().join("data/bash-completion/completions/templative"));
+        assert!(install_path(&Shell::Zsh).unwrap().ends_with(".zsh/completions/_templative"));
+    }
+}
@@ -1,14 +1,23 @@
-pub const VERSION: u32 = 6;
+pub const VERSION: u32 = 57;
 
-pub const SCRIPT: &str = r#"# templative-completions-version: 6
+pub const SCRIPT: &str = r#"# templative-completions-version: 57
 
 _templative() {
   local cur="${COMP_WORDS[$COMP_CWORD]}"
   local prev="${COMP_WORDS[$COMP_CWORD-1]}"
-  local subcommands="init add change remove list completions update"
+  local subcommands="init add add-all change remove list info checksum completions export import update cache undo"
+
+  if [[ $prev == --config ]]; then
+    COMPREPLY=($(compgen -f -- "$cur"))
+    return
+  fi
+  if [[ $prev == --registry-dir ]]; then
+    COMPREPLY=($(compgen -d -- "$cur"))
+    return
+  fi
 
   if [[ $COMP_CWORD -eq 1 ]]; then
-    COMPREPLY=($(compgen -W "$subcommands --version -v --help -h" -- "$cur"))
+    COMPREPLY=($(compgen -W "$subcommands --version -v --progress --config --registry-dir --color --no-color --help -h" -- "$cur"))
     return
   fi
 
@@ -21,13 +30,19 @@ _templative() {
           COMPREPLY=($(compgen -W "fresh preserve no-git" -- "$cur")) ;;
         --write-mode)
           COMPREPLY=($(compgen -W "strict no-overwrite skip-overwrite overwrite ask" -- "$cur")) ;;
+        --format)
+          COMPREPLY=($(compgen -W "text json" -- "$cur")) ;;
+        --assume)
+          COMPREPLY=($(compgen -W "overwrite skip" -- "$cur")) ;;
+        --ref|--exclude|--commit-message|--output-name|--var)
+          ;;
         init)
           COMPREPLY=($(compgen -W "$(templative list --names-only 2>/dev/null)" -- "$cur")) ;;
         *)
           if [[ $COMP_CWORD -eq 3 ]]; then
             COMPREPLY=($(compgen -d -- "$cur"))
           else
-            COMPREPLY=($(compgen -W "--git --write-mode --help -h" -- "$cur"))
+            COMPREPLY=($(compgen -W "--git --write-mode --ref --yes -y --assume --exclude --exclude-only --no-hooks --trust-hooks --only-hooks --new-dir-only --output-name --var --follow-symlinks --commit-message --no-commit --amend --remember --format --verbose --progress --help -h" -- "$cur"))
           fi ;;
       esac
       ;;
@@ -37,12 +52,26 @@ _templative() {
           COMPREPLY=($(compgen -W "fresh preserve no-git" -- "$cur")) ;;
         --write-mode)
           COMPREPLY=($(compgen -W "strict no-overwrite skip-overwrite overwrite ask" -- "$cur")) ;;
-        --name|-n|--description|-d|--git-ref|--exclude)
+        --gitignore-template)
+          COMPREPLY=($(compgen -f -- "$cur")) ;;
+        --name|-n|--description|-d|--git-ref|--commit|--exclude|--tag|--subdir|--max-file-size)
           ;;
         add)
           COMPREPLY=($(compgen -d -- "$cur")) ;;
         *)
-          COMPREPLY=($(compgen -W "--name -n --description -d --git --git-ref --exclude --write-mode --help -h" -- "$cur")) ;;
+          COMPREPLY=($(compgen -W "--name -n --description -d --git --git-ref --commit --exclude --write-mode --tag --force -f --subdir --stdin --allow-empty --gitignore-template --max-file-size --exclude-binaries --progress --help -h" -- "$cur")) ;;
+      esac
+      ;;
+    add-all)
+      case "$prev" in
+        --git)
+          COMPREPLY=($(compgen -W "fresh preserve no-git" -- "$cur")) ;;
+        --write-mode)
+          COMPREPLY=($(compgen -W "strict no-overwrite skip-overwrite overwrite ask" -- "$cur")) ;;
+        add-all)
+          COMPREPLY=($(compgen -d -- "$cur")) ;;
+        *)
+          COMPREPLY=($(compgen -W "--git --write-mode --help -h" -- "$cur")) ;;
       esac
       ;;
     change)
@@ -53,21 +82,58 @@ _templative() {
           COMPREPLY=($(compgen -W "strict no-overwrite skip-overwrite overwrite ask unset" -- "$cur")) ;;
         --location)
           COMPREPLY=($(compgen -d -- "$cur")) ;;
-        --name|--description|--pre-init|--post-init|--git-ref|--exclude)
+        --gitignore-template)
+          COMPREPLY=($(compgen -f -- "$cur")) ;;
+        --name|--description|--pre-init|--post-init|--git-ref|--commit|--exclude|--add-exclude|--remove-exclude|--target-subdir|--tag|--subdir|--max-file-size)
           ;;
         change)
           COMPREPLY=($(compgen -W "$(templative list --names-only 2>/dev/null)" -- "$cur")) ;;
         *)
-          COMPREPLY=($(compgen -W "--name --description --unset-description --location --git --pre-init --unset-pre-init --post-init --unset-post-init --git-ref --unset-git-ref --exclude --clear-exclude --write-mode --help -h" -- "$cur")) ;;
+          COMPREPLY=($(compgen -W "--name --description --unset-description --location --git --pre-init --unset-pre-init --post-init --unset-post-init --git-ref --unset-git-ref --commit --unset-commit --next-steps --unset-next-steps --gitignore-template --unset-gitignore-template --max-file-size --unset-max-file-size --exclude-binaries --unset-exclude-binaries --exclude --clear-exclude --add-exclude --remove-exclude --write-mode --target-subdir --unset-target-subdir --tag --clear-tags --subdir --unset-subdir --help -h" -- "$cur")) ;;
       esac
       ;;
     remove)
-      if [[ $COMP_CWORD -ge 2 ]]; then
+      if [[ $cur == -* ]]; then
+        COMPREPLY=($(compgen -W "--all --missing --yes -y --help -h" -- "$cur"))
+      elif [[ $COMP_CWORD -ge 2 ]]; then
         COMPREPLY=($(compgen -W "$(templative list --names-only 2>/dev/null)" -- "$cur"))
       fi
       ;;
     list)
-      COMPREPLY=($(compgen -W "--names-only --color --no-color --help -h" -- "$cur"))
+      case "$prev" in
+        --tag)
+          ;;
+        --sort)
+          COMPREPLY=($(compgen -W "name location added" -- "$cur")) ;;
+        *)
+          COMPREPLY=($(compgen -W "--names-only --tag --sort --json --color --no-color --remote --show-mode --help -h" -- "$cur")) ;;
+      esac
+      ;;
+    info)
+      case "$prev" in
+        info)
+          COMPREPLY=($(compgen -W "$(templative list --names-only 2>/dev/null)" -- "$cur")) ;;
+        --var)
+          ;;
+        *)
+          COMPREPLY=($(compgen -W "--json --size --var --help -h" -- "$cur")) ;;
+      esac
+      ;;
+    checksum)
+      case "$prev" in
+        checksum)
+          COMPREPLY=($(compgen -W "$(templative list --names-only 2>/dev/null)" -- "$cur")) ;;
+        *)
+          COMPREPLY=($(compgen -W "--save --help -h" -- "$cur")) ;;
+      esac
+      ;;
+    cache)
+      case "$prev" in
+        cache)
+          COMPREPLY=($(compgen -W "prune" -- "$cur")) ;;
+        *)
+          COMPREPLY=($(compgen -W "--dry-run --help -h" -- "$cur")) ;;
+      esac
       ;;
     completions)
       case "$prev" in
@@ -76,15 +142,33 @@ _templative() {
         completions)
           COMPREPLY=($(compgen -W "zsh bash fish powershell" -- "$cur")) ;;
         *)
-          COMPREPLY=($(compgen -W "zsh bash fish powershell --check --help -h" -- "$cur")) ;;
+          COMPREPLY=($(compgen -W "zsh bash fish powershell --check --install --print-path --generate --help -h" -- "$cur")) ;;
+      esac
+      ;;
+    export)
+      case "$prev" in
+        --output|-o)
+          COMPREPLY=($(compgen -f -- "$cur")) ;;
+        *)
+          COMPREPLY=($(compgen -W "--output -o --help -h" -- "$cur")) ;;
+      esac
+      ;;
+    import)
+      case "$prev" in
+        import)
+          COMPREPLY=($(compgen -f -- "$cur")) ;;
+        *)
+          COMPREPLY=($(compgen -W "--merge --replace --strict --help -h" -- "$cur")) ;;
       esac
       ;;
     update)
       case "$prev" in
         update)
           COMPREPLY=($(compgen -W "$(templative list --names-only 2>/dev/null)" -- "$cur")) ;;
+        --tag)
+          ;;
         *)
-          COMPREPLY=($(compgen -W "--check --help -h" -- "$cur")) ;;
+          COMPREPLY=($(compgen -W "--tag --check --log --dry-run --fail-fast --keep-going --progress --help -h" -- "$cur")) ;;
       esac
       ;;
   esac
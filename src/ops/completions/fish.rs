@@ -1,21 +1,33 @@
-pub const VERSION: u32 = 4;
+pub const VERSION: u32 = 55;
 
-pub const SCRIPT: &str = r#"# templative-completions-version: 4
+pub const SCRIPT: &str = r#"# templative-completions-version: 55
 
 # Disable file completion globally
 complete -c templative -f
 
 # Global flags
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -s v -l version -d 'Print version'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -s v -l version -d 'Print version'
+complete -c templative -n 'not __fish_seen_subcommand_from remove list info checksum completions export import change' -l progress -d 'Show git progress while cloning'
+complete -c templative -l config -d 'Use this config file' -r -F
+complete -c templative -l registry-dir -d 'Keep templates.json in this directory' -r -a '(__fish_complete_directories)'
+complete -c templative -n 'not __fish_seen_subcommand_from list' -l color -d 'Force coloured output'
+complete -c templative -n 'not __fish_seen_subcommand_from list' -l no-color -d 'Disable coloured output'
 
 # Subcommands
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a init -d 'Copy a template into a directory'
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a add -d 'Register a directory or git URL as a template'
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a change -d 'Update fields on a registered template'
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a remove -d 'Remove a template from the registry'
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a list -d 'List registered templates'
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a completions -d 'Generate shell completion scripts'
-complete -c templative -n 'not __fish_seen_subcommand_from init add change remove list completions update' -a update -d 'Update cached git templates'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a init -d 'Copy a template into a directory'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a add -d 'Register a directory or git URL as a template'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a add-all -d 'Register every subdirectory of a directory as a template'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a change -d 'Update fields on a registered template'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a remove -d 'Remove a template from the registry'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a list -d 'List registered templates'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a info -d 'Show the merged settings of a template'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a checksum -d 'Print the checksum of a template source'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a completions -d 'Generate shell completion scripts'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a export -d 'Write the template registry as JSON'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a import -d 'Load templates from an exported registry file'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a update -d 'Update cached git templates'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a cache -d 'Manage the clones of URL templates'
+complete -c templative -n 'not __fish_seen_subcommand_from init add add-all change remove list info checksum completions export import update cache undo' -a undo -d 'Restore the registry from before the last change'
 
 # Returns true when 'init' has been given and at least one non-flag argument follows it
 function __templative_init_has_template
@@ -38,6 +50,29 @@ complete -c templative -n '__fish_seen_subcommand_from init' -a '(templative lis
 complete -c templative -n '__fish_seen_subcommand_from init; and __templative_init_has_template' -F -d 'Target directory'
 complete -c templative -n '__fish_seen_subcommand_from init' -l git -d 'Git mode' -a 'fresh preserve no-git' -r
 complete -c templative -n '__fish_seen_subcommand_from init' -l write-mode -d 'Write mode' -a 'strict no-overwrite skip-overwrite overwrite ask' -r
+complete -c templative -n '__fish_seen_subcommand_from init' -l ref -d 'Git ref to use' -r
+complete -c templative -n '__fish_seen_subcommand_from init' -s y -l yes -d 'Skip overwrite confirmation'
+complete -c templative -n '__fish_seen_subcommand_from init' -l exclude -d 'Extra exclude patterns' -r
+complete -c templative -n '__fish_seen_subcommand_from init' -l exclude-only -d 'Use only --exclude patterns'
+complete -c templative -n '__fish_seen_subcommand_from init' -l no-hooks -d 'Skip pre/post-init hooks'
+complete -c templative -n '__fish_seen_subcommand_from init' -l trust-hooks -d 'Run hooks from the template manifest without asking'
+complete -c templative -n '__fish_seen_subcommand_from init' -l only-hooks -d 'Only run hooks against an existing target'
+complete -c templative -n '__fish_seen_subcommand_from init' -l new-dir-only -d 'Error if the target path already exists'
+complete -c templative -n '__fish_seen_subcommand_from init' -l output-name -d 'Create and init this directory inside PATH' -r
+complete -c templative -n '__fish_seen_subcommand_from init' -l var -d 'Set a variable (NAME or NAME=VALUE)' -r
+complete -c templative -n '__fish_seen_subcommand_from init' -l follow-symlinks -d 'Copy symlink targets instead of links'
+complete -c templative -n '__fish_seen_subcommand_from init' -l commit-message -d 'Template commit message' -r
+complete -c templative -n '__fish_seen_subcommand_from init' -l no-commit -d 'Stage files without committing'
+complete -c templative -n '__fish_seen_subcommand_from init' -l amend -d 'Add the files to the existing HEAD commit'
+complete -c templative -n '__fish_seen_subcommand_from init' -l remember -d 'Save an overwrite-all/skip-all answer as the write mode'
+complete -c templative -n '__fish_seen_subcommand_from init' -l assume -d 'Answer every ask prompt' -r -f -a 'overwrite skip'
+complete -c templative -n '__fish_seen_subcommand_from init' -l format -d 'Output format' -r -f -a 'text json'
+complete -c templative -n '__fish_seen_subcommand_from init' -l verbose -d 'Show hook output as the hooks run'
+
+# add-all
+complete -c templative -n '__fish_seen_subcommand_from add-all' -F -d 'Directory of templates'
+complete -c templative -n '__fish_seen_subcommand_from add-all' -l git -d 'Git mode' -a 'fresh preserve no-git' -r
+complete -c templative -n '__fish_seen_subcommand_from add-all' -l write-mode -d 'Write mode' -a 'strict no-overwrite skip-overwrite overwrite ask' -r
 
 # add
 complete -c templative -n '__fish_seen_subcommand_from add' -F -d 'Template directory'
@@ -45,8 +80,17 @@ complete -c templative -n '__fish_seen_subcommand_from add' -s n -l name -d 'Tem
 complete -c templative -n '__fish_seen_subcommand_from add' -s d -l description -d 'Description' -r
 complete -c templative -n '__fish_seen_subcommand_from add' -l git -d 'Git mode' -a 'fresh preserve no-git' -r
 complete -c templative -n '__fish_seen_subcommand_from add' -l git-ref -d 'Pin to git ref' -r
+complete -c templative -n '__fish_seen_subcommand_from add' -l commit -d 'Pin to a commit' -r
 complete -c templative -n '__fish_seen_subcommand_from add' -l exclude -d 'Exclude patterns' -r
 complete -c templative -n '__fish_seen_subcommand_from add' -l write-mode -d 'Write mode' -a 'strict no-overwrite skip-overwrite overwrite ask' -r
+complete -c templative -n '__fish_seen_subcommand_from add' -l tag -d 'Tag' -r
+complete -c templative -n '__fish_seen_subcommand_from add' -s f -l force -d 'Replace existing template'
+complete -c templative -n '__fish_seen_subcommand_from add' -l subdir -d 'Folder inside the source to use as the template' -r
+complete -c templative -n '__fish_seen_subcommand_from add' -l stdin -d 'Read a single-file template from stdin'
+complete -c templative -n '__fish_seen_subcommand_from add' -l allow-empty -d 'Register a path that does not exist yet'
+complete -c templative -n '__fish_seen_subcommand_from add' -l gitignore-template -d '.gitignore to add on init' -r -F
+complete -c templative -n '__fish_seen_subcommand_from add' -l max-file-size -d 'Skip files larger than this many bytes' -r
+complete -c templative -n '__fish_seen_subcommand_from add' -l exclude-binaries -d 'Skip files that look binary'
 
 # change
 complete -c templative -n '__fish_seen_subcommand_from change' -a '(templative list --names-only 2>/dev/null)'
@@ -61,23 +105,80 @@ complete -c templative -n '__fish_seen_subcommand_from change' -l post-init -d '
 complete -c templative -n '__fish_seen_subcommand_from change' -l unset-post-init -d 'Clear post-init hook'
 complete -c templative -n '__fish_seen_subcommand_from change' -l git-ref -d 'Pin to git ref' -r
 complete -c templative -n '__fish_seen_subcommand_from change' -l unset-git-ref -d 'Clear git ref'
+complete -c templative -n '__fish_seen_subcommand_from change' -l commit -d 'Pin to a commit' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-commit -d 'Clear pinned commit'
+complete -c templative -n '__fish_seen_subcommand_from change' -l next-steps -d 'Text printed after init' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-next-steps -d 'Clear next-steps text'
+complete -c templative -n '__fish_seen_subcommand_from change' -l gitignore-template -d '.gitignore to add on init' -r -F
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-gitignore-template -d 'Use the config gitignore_template'
+complete -c templative -n '__fish_seen_subcommand_from change' -l max-file-size -d 'Skip files larger than this many bytes' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-max-file-size -d 'Use the config max_file_size'
+complete -c templative -n '__fish_seen_subcommand_from change' -l exclude-binaries -d 'Skip files that look binary'
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-exclude-binaries -d 'Use the config exclude_binaries'
 complete -c templative -n '__fish_seen_subcommand_from change' -l exclude -d 'Exclude patterns' -r
 complete -c templative -n '__fish_seen_subcommand_from change' -l clear-exclude -d 'Clear all exclude patterns'
+complete -c templative -n '__fish_seen_subcommand_from change' -l add-exclude -d 'Append an exclude pattern' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l remove-exclude -d 'Remove an exclude pattern' -r
 complete -c templative -n '__fish_seen_subcommand_from change' -l write-mode -d 'Write mode' -a 'strict no-overwrite skip-overwrite overwrite ask unset' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l target-subdir -d 'Target subdirectory' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-target-subdir -d 'Clear target subdirectory'
+complete -c templative -n '__fish_seen_subcommand_from change' -l tag -d 'Replace tags' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l clear-tags -d 'Clear all tags'
+complete -c templative -n '__fish_seen_subcommand_from change' -l subdir -d 'Folder inside the source to use as the template' -r
+complete -c templative -n '__fish_seen_subcommand_from change' -l unset-subdir -d 'Use the whole source again'
 
 # remove
 complete -c templative -n '__fish_seen_subcommand_from remove' -a '(templative list --names-only 2>/dev/null)'
+complete -c templative -n '__fish_seen_subcommand_from remove' -l all -d 'Remove every template'
+complete -c templative -n '__fish_seen_subcommand_from remove' -l missing -d 'Remove templates whose location is missing'
+complete -c templative -n '__fish_seen_subcommand_from remove' -s y -l yes -d 'Skip confirmation'
 
 # list
 complete -c templative -n '__fish_seen_subcommand_from list' -l names-only -d 'Print only template names'
+complete -c templative -n '__fish_seen_subcommand_from list' -l tag -d 'Filter by tag' -r
+complete -c templative -n '__fish_seen_subcommand_from list' -l sort -d 'Row order' -r -f -a 'name location added'
+complete -c templative -n '__fish_seen_subcommand_from list' -l json -d 'Print templates as JSON'
 complete -c templative -n '__fish_seen_subcommand_from list' -l color -d 'Force coloured output'
 complete -c templative -n '__fish_seen_subcommand_from list' -l no-color -d 'Disable coloured output'
+complete -c templative -n '__fish_seen_subcommand_from list' -l remote -d 'Check pinned refs of uncached templates on the remote'
+complete -c templative -n '__fish_seen_subcommand_from list' -l show-mode -d 'Show the git mode each template would use'
+
+# info
+complete -c templative -n '__fish_seen_subcommand_from info' -a '(templative list --names-only 2>/dev/null)'
+complete -c templative -n '__fish_seen_subcommand_from info' -l json -d 'Print the details as JSON'
+complete -c templative -n '__fish_seen_subcommand_from info' -l size -d 'Report the size init would copy'
+complete -c templative -n '__fish_seen_subcommand_from info' -l var -d 'Measure with this variable set (NAME or NAME=VALUE)' -r
+
+# checksum
+complete -c templative -n '__fish_seen_subcommand_from checksum' -a '(templative list --names-only 2>/dev/null)'
+complete -c templative -n '__fish_seen_subcommand_from checksum' -l save -d 'Pin the template to this checksum'
+
+# cache
+complete -c templative -n '__fish_seen_subcommand_from cache; and not __fish_seen_subcommand_from prune' -a prune -d 'Delete clones no template uses'
+complete -c templative -n '__fish_seen_subcommand_from prune' -l dry-run -d 'List what would be deleted'
 
 # completions
 complete -c templative -n '__fish_seen_subcommand_from completions' -a 'zsh bash fish powershell'
 complete -c templative -n '__fish_seen_subcommand_from completions' -l check -d 'Check if installed script is up to date' -r -F
+complete -c templative -n '__fish_seen_subcommand_from completions' -l install -d 'Write the script to the usual completions directory'
+complete -c templative -n '__fish_seen_subcommand_from completions' -l print-path -d 'Print where --install would write the script'
+complete -c templative -n '__fish_seen_subcommand_from completions' -l generate -d 'Print a script generated from the CLI definition'
+
+# export
+complete -c templative -n '__fish_seen_subcommand_from export' -s o -l output -d 'Output file' -r -F
+
+# import
+complete -c templative -n '__fish_seen_subcommand_from import' -F -d 'Registry file'
+complete -c templative -n '__fish_seen_subcommand_from import' -l merge -d 'Merge with existing templates'
+complete -c templative -n '__fish_seen_subcommand_from import' -l replace -d 'Replace the whole registry'
+complete -c templative -n '__fish_seen_subcommand_from import' -l strict -d 'Error on name collisions'
 
 # update
 complete -c templative -n '__fish_seen_subcommand_from update' -a '(templative list --names-only 2>/dev/null)'
+complete -c templative -n '__fish_seen_subcommand_from update' -l tag -d 'Only update templates with this tag' -r
 complete -c templative -n '__fish_seen_subcommand_from update' -l check -d 'Check for updates without applying'
+complete -c templative -n '__fish_seen_subcommand_from update' -l log -d 'With --check, list the incoming commits'
+complete -c templative -n '__fish_seen_subcommand_from update' -l dry-run -d 'Print the git commands an update would run'
+complete -c templative -n '__fish_seen_subcommand_from update' -l fail-fast -d 'Stop at the first failing template'
+complete -c templative -n '__fish_seen_subcommand_from update' -l keep-going -d 'Attempt every template (default)'
 "#;
@@ -1,12 +1,12 @@
-pub const VERSION: u32 = 5;
+pub const VERSION: u32 = 56;
 
-pub const SCRIPT: &str = r#"# templative-completions-version: 5
+pub const SCRIPT: &str = r#"# templative-completions-version: 56
 
 Register-ArgumentCompleter -Native -CommandName templative -ScriptBlock {
     param($wordToComplete, $commandAst, $cursorPosition)
 
     $words = $commandAst.CommandElements
-    $subcommands = @('init', 'add', 'change', 'remove', 'list', 'completions', 'update')
+    $subcommands = @('init', 'add', 'add-all', 'change', 'remove', 'list', 'info', 'checksum', 'completions', 'export', 'import', 'update', 'cache', 'undo')
 
     $subcommand = $null
     foreach ($word in $words[1..($words.Count - 1)]) {
@@ -18,23 +18,34 @@ Register-ArgumentCompleter -Native -CommandName templative -ScriptBlock {
 
     $prev = if ($words.Count -ge 2) { $words[$words.Count - 2].ToString() } else { '' }
 
-    $completions = if ($null -eq $subcommand) {
-        $subcommands + @('--version', '-v', '--help', '-h')
+    $completions = if ($prev -eq '--config' -or $prev -eq '--registry-dir') {
+        @()
+    } elseif ($null -eq $subcommand) {
+        $subcommands + @('--version', '-v', '--progress', '--config', '--registry-dir', '--color', '--no-color', '--help', '-h')
     } else {
         switch ($subcommand) {
             'init' {
                 switch ($prev) {
                     '--git'        { @('fresh', 'preserve', 'no-git') }
                     '--write-mode' { @('strict', 'no-overwrite', 'skip-overwrite', 'overwrite', 'ask') }
+                    '--format'     { @('text', 'json') }
+                    '--assume'     { @('overwrite', 'skip') }
                     'init'         { templative list --names-only 2>$null }
-                    default        { @('--git', '--write-mode', '--help', '-h') }
+                    default        { @('--git', '--write-mode', '--ref', '--yes', '-y', '--assume', '--exclude', '--exclude-only', '--no-hooks', '--trust-hooks', '--only-hooks', '--new-dir-only', '--output-name', '--var', '--follow-symlinks', '--commit-message', '--no-commit', '--amend', '--remember', '--format', '--verbose', '--progress', '--help', '-h') }
                 }
             }
             'add' {
                 switch ($prev) {
                     '--git'        { @('fresh', 'preserve', 'no-git') }
                     '--write-mode' { @('strict', 'no-overwrite', 'skip-overwrite', 'overwrite', 'ask') }
-                    default        { @('--name', '-n', '--description', '-d', '--git', '--git-ref', '--exclude', '--write-mode', '--help', '-h') }
+                    default        { @('--name', '-n', '--description', '-d', '--git', '--git-ref', '--commit', '--exclude', '--write-mode', '--tag', '--force', '-f', '--subdir', '--stdin', '--allow-empty', '--gitignore-template', '--max-file-size', '--exclude-binaries', '--progress', '--help', '-h') }
+                }
+            }
+            'add-all' {
+                switch ($prev) {
+                    '--git'        { @('fresh', 'preserve', 'no-git') }
+                    '--write-mode' { @('strict', 'no-overwrite', 'skip-overwrite', 'overwrite', 'ask') }
+                    default        { @('--git', '--write-mode', '--help', '-h') }
                 }
             }
             'change' {
@@ -42,26 +53,62 @@ Register-ArgumentCompleter -Native -CommandName templative -ScriptBlock {
                     '--git'        { @('fresh', 'preserve', 'no-git', 'unset') }
                     '--write-mode' { @('strict', 'no-overwrite', 'skip-overwrite', 'overwrite', 'ask', 'unset') }
                     'change'       { templative list --names-only 2>$null }
-                    default        { @('--name', '--description', '--unset-description', '--location', '--git', '--pre-init', '--unset-pre-init', '--post-init', '--unset-post-init', '--git-ref', '--unset-git-ref', '--exclude', '--clear-exclude', '--write-mode', '--help', '-h') }
+                    default        { @('--name', '--description', '--unset-description', '--location', '--git', '--pre-init', '--unset-pre-init', '--post-init', '--unset-post-init', '--git-ref', '--unset-git-ref', '--commit', '--unset-commit', '--next-steps', '--unset-next-steps', '--gitignore-template', '--unset-gitignore-template', '--max-file-size', '--unset-max-file-size', '--exclude-binaries', '--unset-exclude-binaries', '--exclude', '--clear-exclude', '--add-exclude', '--remove-exclude', '--write-mode', '--target-subdir', '--unset-target-subdir', '--tag', '--clear-tags', '--subdir', '--unset-subdir', '--help', '-h') }
                 }
             }
             'remove' {
-                templative list --names-only 2>$null
+                if ($wordToComplete -like '-*') {
+                    @('--all', '--missing', '--yes', '-y', '--help', '-h')
+                } else {
+                    templative list --names-only 2>$null
+                }
             }
             'list' {
-                @('--names-only', '--color', '--no-color', '--help', '-h')
+                switch ($prev) {
+                    '--sort'       { @('name', 'location', 'added') }
+                    default        { @('--names-only', '--tag', '--sort', '--json', '--color', '--no-color', '--remote', '--show-mode', '--help', '-h') }
+                }
+            }
+            'info' {
+                switch ($prev) {
+                    'info'    { templative list --names-only 2>$null }
+                    default   { @('--json', '--size', '--var', '--help', '-h') }
+                }
+            }
+            'checksum' {
+                switch ($prev) {
+                    'checksum' { templative list --names-only 2>$null }
+                    default    { @('--save', '--help', '-h') }
+                }
+            }
+            'cache' {
+                switch ($prev) {
+                    'cache'  { @('prune') }
+                    default  { @('--dry-run', '--help', '-h') }
+                }
             }
             'completions' {
                 switch ($prev) {
                     '--check'      { @() }
                     'completions'  { @('zsh', 'bash', 'fish', 'powershell') }
-                    default        { @('zsh', 'bash', 'fish', 'powershell', '--check', '--help', '-h') }
+                    default        { @('zsh', 'bash', 'fish', 'powershell', '--check', '--install', '--print-path', '--generate', '--help', '-h') }
                 }
             }
+            'export' {
+                switch ($prev) {
+                    '--output' { @() }
+                    '-o'       { @() }
+                    default    { @('--output', '-o', '--help', '-h') }
+                }
+            }
+            'import' {
+                @('--merge', '--replace', '--strict', '--help', '-h')
+            }
             'update' {
                 switch ($prev) {
                     'update'  { templative list --names-only 2>$null }
-                    default   { @('--check', '--help', '-h') }
+                    '--tag'   { @() }
+                    default   { @('--tag', '--check', '--log', '--dry-run', '--fail-fast', '--keep-going', '--progress', '--help', '-h') }
                 }
             }
         }
@@ -1,7 +1,7 @@
-pub const VERSION: u32 = 5;
+pub const VERSION: u32 = 56;
 
 pub const SCRIPT: &str = r#"#compdef templative
-# templative-completions-version: 5
+# templative-completions-version: 56
 
 _templative_template_names() {
   local -a names
@@ -14,6 +14,11 @@ _templative() {
 
   _arguments -C \
     '(-v --version)'{-v,--version}'[Print version]' \
+    '--progress[Show git progress while cloning]' \
+    '--config[Use this config file]:file:_files' \
+    '--registry-dir[Keep templates.json in this directory]:directory:_files -/' \
+    '(--no-color)--color[Force coloured output]' \
+    '(--color)--no-color[Disable coloured output]' \
     '1:command:->command' \
     '*::args:->args'
 
@@ -23,11 +28,18 @@ _templative() {
       commands=(
         'init:Copy a template into a directory'
         'add:Register a directory or git URL as a template'
+        'add-all:Register every subdirectory of a directory as a template'
         'change:Update fields on a registered template'
         'remove:Remove a template from the registry'
         'list:List registered templates'
+        'info:Show the merged settings of a template'
+        'checksum:Print the checksum of a template source'
         'completions:Generate shell completion scripts'
+        'export:Write the template registry as JSON'
+        'import:Load templates from an exported registry file'
         'update:Update cached git templates'
+        'cache:Manage the clones of URL templates'
+        'undo:Restore the registry from before the last change'
       )
       _describe 'command' commands
       ;;
@@ -37,6 +49,25 @@ _templative() {
           _arguments \
             '--git[Git mode]:mode:(fresh preserve no-git)' \
             '--write-mode[Write mode]:mode:(strict no-overwrite skip-overwrite overwrite ask)' \
+            '--ref[Git ref to use]:ref:' \
+            '(-y --yes)'{-y,--yes}'[Skip overwrite confirmation]' \
+            '*--exclude[Extra exclude patterns]:pattern:' \
+            '--exclude-only[Use only --exclude patterns]' \
+            '(--only-hooks --trust-hooks)--no-hooks[Skip pre/post-init hooks]' \
+            '(--no-hooks)--trust-hooks[Run hooks from the template manifest without asking]' \
+            '(--no-hooks --new-dir-only)--only-hooks[Only run hooks against an existing target]' \
+            '(--only-hooks)--new-dir-only[Error if the target path already exists]' \
+            '--output-name[Create and init this directory inside PATH]:name:' \
+            '*--var[Set a variable (NAME or NAME=VALUE)]:variable:' \
+            '--follow-symlinks[Copy symlink targets instead of links]' \
+            '(--no-commit --amend)--commit-message[Template commit message]:message:' \
+            '(--commit-message --amend)--no-commit[Stage files without committing]' \
+            '(--commit-message --no-commit)--amend[Add the files to the existing HEAD commit]' \
+            '--remember[Save an overwrite-all/skip-all answer as the write mode]' \
+            '--assume[Answer every ask prompt]:answer:(overwrite skip)' \
+            '--format[Output format]:format:(text json)' \
+            '--verbose[Show hook output as the hooks run]' \
+            '--progress[Show git progress while cloning]' \
             '1:template:_templative_template_names' \
             '2:path:_files -/'
           ;;
@@ -46,10 +77,26 @@ _templative() {
             '(-d --description)'{-d,--description}'[Description]:desc:' \
             '--git[Git mode]:mode:(fresh preserve no-git)' \
             '--git-ref[Pin to git ref]:ref:' \
+            '--commit[Pin to a commit]:commit:' \
             '--exclude[Exclude patterns]:pattern:' \
             '--write-mode[Write mode]:mode:(strict no-overwrite skip-overwrite overwrite ask)' \
+            '*--tag[Tag]:tag:' \
+            '(-f --force)'{-f,--force}'[Replace existing template]' \
+            '--subdir[Folder inside the source to use as the template]:dir:' \
+            '--stdin[Read a single-file template from stdin]' \
+            '--allow-empty[Register a path that does not exist yet]' \
+            '--gitignore-template[.gitignore to add on init]:file:_files' \
+            '--max-file-size[Skip files larger than this many bytes]:bytes:' \
+            '--exclude-binaries[Skip files that look binary]' \
+            '--progress[Show git progress while cloning]' \
             '1:path:_files -/'
           ;;
+        add-all)
+          _arguments \
+            '--git[Git mode]:mode:(fresh preserve no-git)' \
+            '--write-mode[Write mode]:mode:(strict no-overwrite skip-overwrite overwrite ask)' \
+            '1:dir:_files -/'
+          ;;
         change)
           _arguments \
             '--name[New name]:name:' \
@@ -63,29 +110,92 @@ _templative() {
             '--unset-post-init[Clear post-init hook]' \
             '--git-ref[Pin to git ref]:ref:' \
             '--unset-git-ref[Clear git ref]' \
+            '(--unset-commit)--commit[Pin to a commit]:commit:' \
+            '(--commit)--unset-commit[Clear pinned commit]' \
+            '(--unset-next-steps)--next-steps[Text printed after init]:text:' \
+            '(--next-steps)--unset-next-steps[Clear next-steps text]' \
+            '(--unset-gitignore-template)--gitignore-template[.gitignore to add on init]:file:_files' \
+            '(--gitignore-template)--unset-gitignore-template[Use the config gitignore_template]' \
+            '(--unset-max-file-size)--max-file-size[Skip files larger than this many bytes]:bytes:' \
+            '(--max-file-size)--unset-max-file-size[Use the config max_file_size]' \
+            '(--unset-exclude-binaries)--exclude-binaries[Skip files that look binary]' \
+            '(--exclude-binaries)--unset-exclude-binaries[Use the config exclude_binaries]' \
             '--exclude[Exclude patterns]:pattern:' \
             '--clear-exclude[Clear all exclude patterns]' \
+            '*--add-exclude[Append an exclude pattern]:pattern:' \
+            '*--remove-exclude[Remove an exclude pattern]:pattern:' \
             '--write-mode[Write mode]:mode:(strict no-overwrite skip-overwrite overwrite ask unset)' \
+            '--target-subdir[Target subdirectory]:dir:' \
+            '--unset-target-subdir[Clear target subdirectory]' \
+            '*--tag[Replace tags]:tag:' \
+            '--clear-tags[Clear all tags]' \
+            '(--unset-subdir)--subdir[Folder inside the source to use as the template]:dir:' \
+            '(--subdir)--unset-subdir[Use the whole source again]' \
             '1:template:_templative_template_names'
           ;;
         remove)
           _arguments \
+            '(* --missing)--all[Remove every template]' \
+            '(* --all)--missing[Remove templates whose location is missing]' \
+            '(-y --yes)'{-y,--yes}'[Skip confirmation]' \
             '*:template:_templative_template_names'
           ;;
         list)
           _arguments \
             '--names-only[Print only template names]' \
+            '*--tag[Filter by tag]:tag:' \
+            '--sort[Row order]:order:(name location added)' \
+            '--json[Print templates as JSON]' \
             '--color[Force coloured output]' \
-            '--no-color[Disable coloured output]'
+            '--no-color[Disable coloured output]' \
+            '--remote[Check pinned refs of uncached templates on the remote]' \
+            '--show-mode[Show the git mode each template would use]'
+          ;;
+        info)
+          _arguments \
+            '--json[Print the details as JSON]' \
+            '--size[Report the size init would copy]' \
+            '*--var[Measure with this variable set (NAME or NAME=VALUE)]:variable:' \
+            '1:template:_templative_template_names'
+          ;;
+        checksum)
+          _arguments \
+            '--save[Pin the template to this checksum]' \
+            '1:template:_templative_template_names'
+          ;;
+        cache)
+          _arguments \
+            '--dry-run[List what would be deleted]' \
+            '1:action:((prune\:"Delete clones no template uses"))'
           ;;
         completions)
           _arguments \
-            '--check[Check if installed script is up to date]:path:_files' \
+            '(--install --print-path)--check[Check if installed script is up to date]:path:_files' \
+            '(--check --print-path)--install[Write the script to the usual completions directory]' \
+            '(--check --install)--print-path[Print where --install would write the script]' \
+            '(--check --install --print-path)--generate[Print a script generated from the CLI definition]' \
             '1:shell:(zsh bash fish powershell)'
           ;;
+        export)
+          _arguments \
+            '(-o --output)'{-o,--output}'[Output file]:file:_files'
+          ;;
+        import)
+          _arguments \
+            '(--replace)--merge[Merge with existing templates]' \
+            '(--merge --strict)--replace[Replace the whole registry]' \
+            '(--replace)--strict[Error on name collisions]' \
+            '1:file:_files'
+          ;;
         update)
           _arguments \
-            '--check[Check for updates without applying]' \
+            '*--tag[Only update templates with this tag]:tag:' \
+            '(--dry-run)--check[Check for updates without applying]' \
+            '(--dry-run)--log[With --check, list the incoming commits]' \
+            '(--check --log)--dry-run[Print the git commands an update would run]' \
+            '(--keep-going)--fail-fast[Stop at the first failing template]' \
+            '(--fail-fast)--keep-going[Attempt every template (default)]' \
+            '--progress[Show git progress while cloning]' \
             '1:template:_templative_template_names'
           ;;
       esac
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+use crate::registry::Registry;
+
+pub fn cmd_export(config: &Config, output: Option<PathBuf>) -> Result<()> {
+    let registry = Registry::load(config)?;
+    match output {
+        Some(path) => {
+            registry.save_to_path(&path)?;
+            println!("exported {} templates to {}", registry.templates.len(), path.display());
+        }
+        None => {
+            let contents =
+                serde_json::to_string_pretty(&registry).context("failed to serialize registry")?;
+            println!("{}", contents);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::errors::TemplativeError;
+use crate::registry::Registry;
+use crate::utilities;
+
+pub fn cmd_import(config: &Config, file: PathBuf, replace: bool, strict: bool) -> Result<()> {
+    if !file.exists() {
+        anyhow::bail!("file not found: {}", file.display());
+    }
+    let imported = Registry::read_from_path(&file)?;
+    let mut registry = Registry::load_for_update(config)?;
+
+    if replace {
+        let count = imported.templates.len();
+        registry.templates = imported.templates;
+        registry.save()?;
+        println!("replaced registry with {} templates from {}", count, file.display());
+        return Ok(());
+    }
+
+    // Collisions are resolved before any mutation so `--strict` leaves the registry untouched.
+    let (colliding, incoming): (Vec<_>, Vec<_>) = imported
+        .templates
+        .into_iter()
+        .partition(|tmpl| registry.get(&tmpl.name).is_some());
+    if strict && let Some(first) = colliding.first() {
+        return Err(TemplativeError::TemplateExists { name: first.name.clone() }.into());
+    }
+    for tmpl in &colliding {
+        utilities::warn(format_args!("skipping {}: template name already exists", tmpl.name));
+    }
+    let count = incoming.len();
+    for tmpl in incoming {
+        registry.add(tmpl)?;
+    }
+    registry.save()?;
+    println!("imported {} templates from {}", count, file.display());
+    Ok(())
+}
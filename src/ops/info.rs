@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::errors::TemplativeError;
+use crate::manifest::Manifest;
+use crate::registry::{Registry, Template};
+use crate::resolved::{InitFlags, ResolvedOptions};
+use crate::{fs_copy, utilities};
+
+use super::{init, list};
+
+/// Where a merged setting's value came from. `info` never has flags, so only the template,
+/// its manifest and the config (or the built-in default) can supply one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Source {
+    Template,
+    Manifest,
+    Config,
+    Default,
+}
+
+impl Source {
+    fn of<T>(template_value: &Option<T>) -> Self {
+        if template_value.is_some() { Source::Template } else { Source::Config }
+    }
+
+    /// Like `of`, for settings a `.templative.toml` can also supply.
+    fn of_layered<T, M>(template_value: &Option<T>, manifest_value: Option<&M>) -> Self {
+        match (template_value, manifest_value) {
+            (Some(_), _) => Source::Template,
+            (None, Some(_)) => Source::Manifest,
+            (None, None) => Source::Config,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Source::Template => "template",
+            Source::Manifest => "manifest",
+            Source::Config => "config",
+            Source::Default => "default",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Setting {
+    value: String,
+    source: Source,
+}
+
+/// The `info` report: the template's own fields merged with the config the way `init` would.
+#[derive(Debug, Serialize)]
+struct InfoReport {
+    name: String,
+    location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    tags: Vec<String>,
+    /// The `list` status, empty when there's nothing to flag.
+    status: String,
+    git: Setting,
+    write_mode: Setting,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    exclude_config: Vec<String>,
+    exclude_template: Vec<String>,
+    /// Patterns from the template's `.templative.toml`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude_manifest: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pre_init: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_init: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subdir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_subdir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gitignore_template: Option<Setting>,
+    commit_message: Setting,
+    /// The cached clone of a URL template, whether or not it exists yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_used: Option<String>,
+    /// With `--size`, what `init` would copy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<SourceSize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SourceSize {
+    bytes: u64,
+    files: usize,
+}
+
+/// `bytes` in binary units, e.g. `1.5 MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Fetches the source `init` would copy (cloning a URL template's cache if needed) and
+/// measures it with the settings `init` would use, `vars` deciding the conditional paths.
+fn measure_source(config: &Config, template: &Template, vars: BTreeMap<String, String>) -> Result<SourceSize> {
+    let flags = InitFlags { vars, ..InitFlags::default() };
+    let source = init::fetch_source(config, template, &flags)?;
+    init::print_notes(&source.notes);
+    let (bytes, files) =
+        fs_copy::measure_template(&source.path, &source.resolved.exclude, &source.resolved.copy_options())?;
+    Ok(SourceSize { bytes, files })
+}
+
+/// The kebab-case name a config enum is written as in JSON, for display.
+fn serialized_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn build_report(config: &Config, template: &Template) -> Result<InfoReport> {
+    let manifest = Manifest::load_for(config, template)?;
+    let resolved = ResolvedOptions::build(config, template, manifest.as_ref(), &InitFlags::default())?;
+    let cache_path = if utilities::is_git_url(&template.location) {
+        Some(utilities::cache_path_for_url(config, &template.location)?)
+    } else {
+        None
+    };
+    let gitignore_source = Source::of(&template.gitignore_template);
+    let commit_message_source = if config.commit_message.is_some() { Source::Config } else { Source::Default };
+    Ok(InfoReport {
+        name: template.name.clone(),
+        location: template.location.clone(),
+        description: template.description.clone(),
+        tags: template.tags.clone().unwrap_or_default(),
+        status: list::status_text(config, template),
+        git: Setting {
+            value: serialized_name(&resolved.git),
+            source: Source::of_layered(&template.git, manifest.as_ref().and_then(|manifest| manifest.git.as_ref())),
+        },
+        write_mode: Setting {
+            value: serialized_name(&resolved.write_mode),
+            source: Source::of(&template.write_mode),
+        },
+        git_ref: resolved.git_ref,
+        commit: resolved.commit,
+        checksum: template.checksum.clone(),
+        exclude_config: config.exclude.clone(),
+        exclude_template: template.exclude.clone().unwrap_or_default(),
+        exclude_manifest: manifest.as_ref().and_then(|manifest| manifest.exclude.clone()).unwrap_or_default(),
+        pre_init: resolved.pre_init,
+        post_init: resolved.post_init,
+        subdir: resolved.subdir,
+        target_subdir: resolved.target_subdir,
+        gitignore_template: resolved
+            .gitignore_template
+            .map(|value| Setting { value, source: gitignore_source }),
+        commit_message: Setting { value: resolved.commit_message_template, source: commit_message_source },
+        cache_path,
+        created_at: template.created_at.clone(),
+        last_used: template.last_used.clone(),
+        size: None,
+    })
+}
+
+fn print_report(report: &InfoReport) {
+    let line = |label: &str, value: &str| println!("{:<20}{}", format!("{}:", label), value);
+    let setting = |label: &str, setting: &Setting| {
+        line(label, &format!("{} ({})", setting.value, setting.source.as_str()));
+    };
+    line("name", &report.name);
+    line("location", &report.location);
+    if let Some(ref description) = report.description {
+        line("description", description);
+    }
+    if !report.tags.is_empty() {
+        line("tags", &report.tags.join(", "));
+    }
+    if !report.status.is_empty() {
+        line("status", &report.status);
+    }
+    setting("git", &report.git);
+    setting("write mode", &report.write_mode);
+    if let Some(ref git_ref) = report.git_ref {
+        line("git ref", git_ref);
+    }
+    if let Some(ref commit) = report.commit {
+        line("commit", commit);
+    }
+    if let Some(ref checksum) = report.checksum {
+        line("checksum", checksum);
+    }
+    if !report.exclude_config.is_empty() {
+        line("exclude (config)", &report.exclude_config.join(", "));
+    }
+    if !report.exclude_template.is_empty() {
+        line("exclude (template)", &report.exclude_template.join(", "));
+    }
+    if !report.exclude_manifest.is_empty() {
+        line("exclude (manifest)", &report.exclude_manifest.join(", "));
+    }
+    if let Some(ref pre_init) = report.pre_init {
+        line("pre-init", pre_init);
+    }
+    if let Some(ref post_init) = report.post_init {
+        line("post-init", post_init);
+    }
+    if let Some(ref subdir) = report.subdir {
+        line("subdir", subdir);
+    }
+    if let Some(ref target_subdir) = report.target_subdir {
+        line("target subdir", target_subdir);
+    }
+    if let Some(ref gitignore) = report.gitignore_template {
+        setting("gitignore template", gitignore);
+    }
+    setting("commit message", &report.commit_message);
+    if let Some(ref cache_path) = report.cache_path {
+        let state = if cache_path.join(".git").exists() { "" } else { " (not cloned yet)" };
+        line("cache", &format!("{}{}", cache_path.display(), state));
+    }
+    if let Some(ref created_at) = report.created_at {
+        line("added", created_at);
+    }
+    if let Some(ref last_used) = report.last_used {
+        line("last used", last_used);
+    }
+    if let Some(ref size) = report.size {
+        let plural = if size.files == 1 { "" } else { "s" };
+        line("size", &format!("{} in {} file{}", format_size(size.bytes), size.files, plural));
+    }
+}
+
+/// Prints `template_name`'s merged settings. With `size` the source is fetched and measured
+/// too, which clones a URL template that isn't cached yet; `vars` are the `init --var` values
+/// to measure with.
+pub fn cmd_info(
+    config: Config,
+    template_name: String,
+    json: bool,
+    size: bool,
+    vars: BTreeMap<String, String>,
+) -> Result<()> {
+    let registry = Registry::load(&config)?;
+    let template = registry
+        .get(&template_name)
+        .ok_or_else(|| TemplativeError::TemplateNotFound { name: template_name.clone() })?;
+    let mut report = build_report(&config, template)?;
+    if size {
+        report.size = Some(measure_source(&config, template, vars)?);
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).context("failed to serialize template info")?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WriteMode;
+
+    fn make_template() -> Template {
+        let mut template: Template =
+            serde_json::from_str(r#"{"name": "tmpl", "location": "/nonexistent/tmpl"}"#).unwrap();
+        template.write_mode = Some(WriteMode::Ask);
+        template.exclude = Some(vec!["target".into()]);
+        template
+    }
+
+    #[test]
+    fn report_attributes_settings_to_template_or_config() {
+        let config = Config::new();
+        let report = build_report(&config, &make_template()).unwrap();
+        assert_eq!(report.git.value, "fresh");
+        assert_eq!(report.git.source, Source::Config);
+        assert_eq!(report.write_mode.value, "ask");
+        assert_eq!(report.write_mode.source, Source::Template);
+        assert_eq!(report.exclude_template, vec!["target".to_string()]);
+        assert_eq!(report.commit_message.source, Source::Default);
+        assert!(report.cache_path.is_none());
+    }
+
+    #[test]
+    fn measure_source_leaves_out_excluded_files() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("main.rs"), "fn main() {}").unwrap();
+        std::fs::create_dir(source.path().join("target")).unwrap();
+        std::fs::write(source.path().join("target/app"), "binary").unwrap();
+        let mut template = make_template();
+        template.location = source.path().to_string_lossy().into_owned();
+        let size = measure_source(&Config::new(), &template, BTreeMap::new()).unwrap();
+        assert_eq!((size.bytes, size.files), (12, 1));
+    }
+
+    #[test]
+    fn measure_source_counts_conditional_paths_only_when_their_var_is_set() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("main.rs"), "fn main() {}").unwrap();
+        std::fs::create_dir(source.path().join("docs")).unwrap();
+        std::fs::write(source.path().join("docs/guide.md"), "guide").unwrap();
+        std::fs::write(
+            source.path().join(crate::manifest::MANIFEST_FILE),
+            "[[conditional]]\npath = \"docs\"\nwhen = \"docs\"\n",
+        )
+        .unwrap();
+        let mut template = make_template();
+        template.location = source.path().to_string_lossy().into_owned();
+
+        let size = measure_source(&Config::new(), &template, BTreeMap::new()).unwrap();
+        assert_eq!((size.bytes, size.files), (12, 1));
+        let vars = BTreeMap::from([("docs".to_string(), "true".to_string())]);
+        let size = measure_source(&Config::new(), &template, vars).unwrap();
+        assert_eq!((size.bytes, size.files), (17, 2));
+    }
+
+    #[test]
+    fn format_size_uses_binary_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn url_template_reports_its_cache_path() {
+        let _lock = crate::test_env::ENV_LOCK.lock().unwrap();
+        let mut template = make_template();
+        template.location = "https://example.com/repo.git".into();
+        let report = build_report(&Config::new(), &template).unwrap();
+        assert!(report.cache_path.is_some());
+    }
+}
@@ -1,141 +1,915 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
+use dialoguer::Confirm;
+use serde::Serialize;
 
-use crate::config::{Config, GitMode, WriteMode};
+use crate::config::{Config, GitMode, HookCwd, PreserveStrategy, WriteMode};
 use crate::errors::TemplativeError;
 use crate::fs_copy;
 use crate::git;
 use crate::git_cache;
-use crate::registry::Registry;
-use crate::resolved::ResolvedOptions;
+use crate::manifest::Manifest;
+use crate::registry::{Registry, Template};
+use crate::resolved::{InitFlags, ResolvedOptions};
 use crate::utilities;
 
+use super::update;
+
 /// Resolves the template source path.
-/// For URL templates, uses the cache (always updated; silently falls back to cached if offline).
-/// For local templates, uses the path as-is.
-/// When `git_ref` is set, a temp clone is used to avoid mutating the source repo.
-/// Returns the path and an optional TempDir that must stay alive for the duration of the copy.
+/// For URL templates, uses the cache (fetched per `update_on_init` unless fetched within
+/// `cache_ttl_secs`; silently falls back to cached if offline), or a throwaway clone when
+/// `no_cache` is set.
+/// For local templates, uses the canonical path (pulled first only under `update_on_init: always`).
+/// When a ref or commit is pinned, a temp clone is used to avoid mutating the source repo.
+/// Returns the path and whatever must stay alive for the duration of the copy.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(location = %template.location)))]
 fn resolve_template_path(
-    location: &str,
+    config: &Config,
+    template: &Template,
     location_is_url: bool,
     resolved: &ResolvedOptions,
-) -> Result<(PathBuf, Option<tempfile::TempDir>)> {
-    if location_is_url {
-        let cache_path = git_cache::ensure_cached(location)?;
-        git_cache::update_cache(&cache_path);
-        if let Some(ref git_ref) = resolved.git_ref {
-            let tempdir = tempfile::tempdir().context("failed to create temp dir")?;
-            git::clone_local(&cache_path, tempdir.path())?;
+) -> Result<(PathBuf, SourceGuard)> {
+    let location = template.location.as_str();
+    if location_is_url && resolved.no_cache {
+        let tempdir = tempfile::tempdir().context("failed to create temp dir")?;
+        git::clone_repo(location, tempdir.path(), config.network())?;
+        if let Some(git_ref) = resolved.checkout_ref() {
             git::checkout_ref(tempdir.path(), git_ref)?;
-            let path = tempdir.path().to_path_buf();
-            Ok((path, Some(tempdir)))
+        }
+        let path = tempdir.path().to_path_buf();
+        Ok((path, SourceGuard::TempDir { _dir: tempdir }))
+    } else if location_is_url {
+        let cache_path = git_cache::ensure_cached(config, location)?;
+        let fresh = resolved
+            .cache_ttl_secs
+            .is_some_and(|ttl| git_cache::fetched_within(&cache_path, Duration::from_secs(ttl)));
+        if resolved.update_on_init.updates_url_cache() && !fresh {
+            git_cache::update_cache(&cache_path, template.default_branch.as_deref(), config.network());
+        }
+        if let Some(git_ref) = resolved.checkout_ref() {
+            let worktree = git_cache::worktree_for_ref(&cache_path, git_ref)?;
+            Ok((worktree.path.clone(), SourceGuard::Worktree { _worktree: worktree }))
         } else {
-            Ok((cache_path, None))
+            Ok((cache_path, SourceGuard::None))
         }
     } else {
-        let path = PathBuf::from(location);
-        if let Some(ref git_ref) = resolved.git_ref {
+        // Follow a symlinked location so the copy always walks the real directory. A broken
+        // link fails to canonicalize and is reported the same as a missing path.
+        let path = utilities::expand_location(location)?
+            .canonicalize()
+            .map_err(|_| TemplativeError::TemplatePathMissing { path: PathBuf::from(location) })?;
+        if resolved.update_on_init.updates_local_repo() && git::is_git_repo(&path) {
+            // Non-fatal, like the URL cache refresh: an offline or diverged repo is used as-is.
+            let _ = update::update_template(config, template, update::UpdateMode::Apply);
+        }
+        if let Some(git_ref) = resolved.checkout_ref() {
             let tempdir = tempfile::tempdir().context("failed to create temp dir")?;
             git::clone_local(&path, tempdir.path())?;
             git::checkout_ref(tempdir.path(), git_ref)?;
             let path = tempdir.path().to_path_buf();
-            Ok((path, Some(tempdir)))
+            Ok((path, SourceGuard::TempDir { _dir: tempdir }))
         } else {
-            Ok((path, None))
+            Ok((path, SourceGuard::None))
         }
     }
 }
 
-pub fn cmd_init(
-    config: Config,
-    template_name: String,
-    target_path: PathBuf,
-    git_flag: Option<GitMode>,
-    write_mode_flag: Option<WriteMode>,
+/// Keeps a template source usable until the copy is done.
+#[derive(Debug)]
+pub(super) enum SourceGuard {
+    None,
+    /// Backs a temporary checkout; dropping it deletes the files.
+    TempDir { _dir: tempfile::TempDir },
+    /// Holds the lock on a shared cache worktree.
+    Worktree { _worktree: git_cache::RefWorktree },
+}
+
+/// Clones the template for `GitMode::Preserve` using `strategy`. A temporary source (a
+/// `--ref` checkout, a `no_cache` clone or a cache worktree, which the next init of that ref
+/// moves and `cache prune` deletes) can't be borrowed from, so a full clone is made instead.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(source = %template_path.display())))]
+fn clone_preserved(
+    strategy: &PreserveStrategy,
+    template_path: &Path,
+    location: &str,
+    source_is_temporary: bool,
+    target: &Path,
+    notes: &mut Vec<InitNote>,
 ) -> Result<()> {
-    let registry = Registry::load()?;
-    let template = registry
-        .get(&template_name)
-        .ok_or_else(|| TemplativeError::TemplateNotFound {
-            name: template_name.clone(),
+    if source_is_temporary && *strategy != PreserveStrategy::Full {
+        notes.push(InitNote::Note("making a full clone; the template source is a temporary checkout".into()));
+        return git::clone_local(template_path, target);
+    }
+    match strategy {
+        PreserveStrategy::Full => git::clone_local(template_path, target),
+        PreserveStrategy::Shared => {
+            notes.push(InitNote::Warning(format!(
+                "{} shares objects with {}; pruning or deleting it will break the project's history",
+                target.display(),
+                template_path.display()
+            )));
+            git::clone_shared(template_path, target)
+        }
+        PreserveStrategy::Reference => {
+            let source = if utilities::is_git_url(location) {
+                location.to_string()
+            } else {
+                template_path.to_string_lossy().into_owned()
+            };
+            git::clone_reference(&source, template_path, target)
+        }
+    }
+}
+
+/// Stamps the template's `last_used`. The project is already created by now, so a registry
+/// that can't be updated only warrants a warning.
+fn record_last_used(config: &Config, template_name: &str) {
+    let result = Registry::load_for_update(config).and_then(|mut registry| {
+        if let Some(template) = registry.get_mut(template_name) {
+            template.last_used = Some(utilities::now_rfc3339());
+            registry.save_keeping_undo()?;
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        utilities::warn(format_args!("could not record last use of {}: {:#}", template_name, err));
+    }
+}
+
+/// Saves `mode` as the template's write mode after `init --remember`. Like `last_used`, a
+/// failure here only warrants a warning.
+pub(super) fn remember_write_mode(config: &Config, template_name: &str, mode: &WriteMode) {
+    let result = Registry::load_for_update(config).and_then(|mut registry| {
+        if let Some(template) = registry.get_mut(template_name) {
+            template.write_mode = Some(mode.clone());
+            registry.save()?;
+        }
+        Ok(())
+    });
+    // Only the two modes an `Ask` copy can escalate to reach here.
+    let mode_name = if *mode == WriteMode::Overwrite { "overwrite" } else { "skip-overwrite" };
+    match result {
+        Ok(()) => eprintln!("note: {} will now use write mode {}", template_name, mode_name),
+        Err(err) => utilities::warn(format_args!("could not remember write mode for {}: {:#}", template_name, err)),
+    }
+}
+
+/// Joins `subdir` onto the template source, requiring it to exist and to stay inside the
+/// source (so `../elsewhere` can't reach outside the repo).
+fn resolve_subdir(template_path: &Path, subdir: &str) -> Result<PathBuf> {
+    let missing = || TemplativeError::SubdirMissing {
+        subdir: subdir.to_string(),
+        source_path: template_path.to_path_buf(),
+    };
+    let root = template_path.canonicalize().map_err(|_| missing())?;
+    let joined = root.join(subdir).canonicalize().map_err(|_| missing())?;
+    if !joined.starts_with(&root) {
+        return Err(missing().into());
+    }
+    Ok(joined)
+}
+
+/// The answer `--assume` gives to every `ask` prompt, so `ask` can run without a terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssumeAnswer {
+    Overwrite,
+    Skip,
+}
+
+/// The write mode to copy with. `Ask` becomes the non-interactive mode matching `assume`
+/// when one is given, and is refused without a terminal otherwise, so the copy never reaches
+/// a prompt it can't show.
+pub(super) fn non_interactive_write_mode(
+    write_mode: &WriteMode,
+    assume: Option<AssumeAnswer>,
+    stdin_is_terminal: bool,
+) -> Result<WriteMode, TemplativeError> {
+    match (write_mode, assume) {
+        (WriteMode::Ask, Some(AssumeAnswer::Overwrite)) => Ok(WriteMode::Overwrite),
+        (WriteMode::Ask, Some(AssumeAnswer::Skip)) => Ok(WriteMode::SkipOverwrite),
+        (WriteMode::Ask, None) if !stdin_is_terminal => Err(TemplativeError::AskNeedsTerminal),
+        (write_mode, _) => Ok(write_mode.clone()),
+    }
+}
+
+/// Shown the manifest's hook commands; returns whether to run them.
+pub type ApproveHooks = fn(&[String]) -> Result<bool>;
+
+/// Shown a non-empty target an `overwrite`/`ask` copy is about to write into; returns whether
+/// to go ahead.
+pub type ConfirmOverwrite = fn(&Path) -> Result<bool>;
+
+/// Per-invocation `init` options.
+#[derive(Default)]
+pub struct InitOptions {
+    /// Overrides for template and config settings.
+    pub flags: InitFlags,
+    /// Skip the confirmation prompt before overwriting into a non-empty target.
+    pub yes: bool,
+    /// Asked before an `overwrite`/`ask` copy into a non-empty target; without it the copy goes
+    /// ahead. `cmd_init` prompts on a terminal unless `yes` is set.
+    pub confirm_overwrite: Option<ConfirmOverwrite>,
+    /// Let the `ask` write mode prompt for each existing file. Without it `ask` needs `assume`.
+    /// `cmd_init` sets it when stdin is a terminal.
+    pub interactive: bool,
+    /// Skip the template's pre/post-init hooks.
+    pub no_hooks: bool,
+    /// Run hooks declared by the template's `.templative.toml` without asking. Hooks the user
+    /// set on the template with `change` are always trusted.
+    pub trust_hooks: bool,
+    /// Asked to approve the manifest's hooks when `trust_hooks` is off; without it they're
+    /// refused. `cmd_init` prompts on a terminal.
+    pub approve_hooks: Option<ApproveHooks>,
+    /// Run only the hooks against an existing target, skipping the copy and git steps.
+    pub only_hooks: bool,
+    /// Refuse a target path that already exists, where write modes would accept an empty one.
+    pub new_dir_only: bool,
+    /// A directory name joined onto the target path; `target_subdir` nests inside it.
+    pub output_name: Option<String>,
+    /// Print a JSON report instead of the human "created …" line.
+    pub json: bool,
+    /// Save an "overwrite all"/"skip all" answer to an `ask` prompt as the template's write mode.
+    pub remember: bool,
+    /// Fold a Fresh copy into an existing repo's HEAD commit instead of committing it separately.
+    pub amend: bool,
+    /// Answer every `ask` prompt this way instead of prompting.
+    pub assume: Option<AssumeAnswer>,
+    /// Stream hook output to the terminal as the hooks run instead of capturing it.
+    pub verbose: bool,
+}
+
+/// The `init --format json` report.
+#[derive(Serialize)]
+struct InitReport<'a> {
+    target: &'a Path,
+    template: &'a str,
+    git_mode: &'a GitMode,
+    files_created: Vec<PathBuf>,
+    files_overwritten: Vec<PathBuf>,
+    files_skipped: Vec<PathBuf>,
+    /// Whether a template commit was made (always false outside `GitMode::Fresh`).
+    committed: bool,
+}
+
+/// Copies the template into `target`: a directory template is walked, a single-file template
+/// is copied in by name. With a `path_token_style` set, file and folder names can use the
+/// `project` (the target folder's name) and `template` tokens and any `--var`. A configured
+/// `gitignore_template` is then added when there's no `.gitignore` yet.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(target = %target.display())))]
+fn copy_source(
+    template_path: &Path,
+    target: &Path,
+    template_name: &str,
+    resolved: &ResolvedOptions,
+) -> Result<fs_copy::CopySummary> {
+    // Read up front so a bad path fails before anything is copied.
+    let gitignore = resolved.gitignore_contents()?;
+    let mut summary = if template_path.is_file() {
+        fs_copy::copy_single_file(template_path, target, &resolved.write_mode, &resolved.file_filter)?
+    } else {
+        fs_copy::copy_template(
+            template_path,
+            target,
+            &resolved.exclude,
+            &resolved.write_mode,
+            &fs_copy::CopyOptions {
+                path_tokens: path_tokens(template_name, target, &resolved.vars),
+                ..resolved.copy_options()
+            },
+        )?
+    };
+    if let Some(contents) = gitignore {
+        fs_copy::write_generated_file(target, Path::new(".gitignore"), &contents, &mut summary)?;
+    }
+    Ok(summary)
+}
+
+/// `--var` values take precedence over the built-in `project` and `template`.
+fn path_tokens(template_name: &str, target: &Path, vars: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    let project = target.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut tokens =
+        BTreeMap::from([("project".to_string(), project), ("template".to_string(), template_name.to_string())]);
+    tokens.extend(vars.iter().map(|(name, value)| (name.clone(), value.clone())));
+    tokens
+}
+
+/// Records a Fresh copy in git. Into an existing repo only the written files are staged, so
+/// unrelated work stays out of the template commit; otherwise a new repo is initialized.
+/// With no `message` (`--no-commit`) the files are staged but not committed, and a note
+/// for the success line is returned.
+fn commit_fresh(
+    target: &Path,
+    summary: &fs_copy::CopySummary,
+    message: Option<&str>,
+    amend: bool,
+    notes: &mut Vec<InitNote>,
+) -> Result<Option<&'static str>> {
+    if target.join(".git").exists() {
+        let written = summary.written();
+        if written.is_empty() {
+            notes.push(InitNote::Note("no files were written; skipping the template commit".into()));
+            return Ok(None);
+        }
+        match message {
+            Some(_) if amend && git::has_commits(target) => git::add_paths_and_amend(target, &written)?,
+            Some(message) => {
+                if amend {
+                    notes.push(InitNote::Note(format!(
+                        "{} has no commit to amend; making a new one",
+                        target.display()
+                    )));
+                }
+                git::add_paths_and_commit(target, &written, message)?
+            }
+            None => {
+                git::add_paths(target, &written)?;
+                return Ok(Some("staged but not committed"));
+            }
+        }
+    } else {
+        match message {
+            Some(message) => git::init_and_commit(target, message)?,
+            None => {
+                git::init_and_stage(target)?;
+                return Ok(Some("repository initialized, staged but not committed"));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The highest directory on the way to `path` that doesn't exist yet, i.e. the one
+/// `create_dir_all` would create first. `None` when `path` already exists.
+fn first_missing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .last()
+        .map(Path::to_path_buf)
+}
+
+/// What the target looked like before init touched it.
+struct Cleanup<'a> {
+    /// The directory init created, if the target didn't exist.
+    created_root: Option<&'a Path>,
+    target_was_empty: bool,
+    had_git: bool,
+}
+
+/// Undoes a failed init so a retry doesn't trip over a half-populated target. A target init
+/// created is removed outright and one that was empty is emptied again. Otherwise only the
+/// files the copy created (and a `.git` it initialized) are removed: pre-existing files are
+/// never touched, overwritten ones can't be restored, and a copy that failed midway has no
+/// record of what it wrote. Best effort; problems are warnings so the original error shows.
+fn clean_up_failed_init(target: &Path, cleanup: &Cleanup, summary: &fs_copy::CopySummary) {
+    let result = if let Some(root) = cleanup.created_root {
+        std::fs::remove_dir_all(root)
+    } else if cleanup.target_was_empty {
+        std::fs::read_dir(target).and_then(|entries| {
+            entries.flatten().try_for_each(|entry| match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => std::fs::remove_dir_all(entry.path()),
+                _ => std::fs::remove_file(entry.path()),
+            })
         })
-        .with_context(|| "run 'templative list' to see available templates")?;
+    } else {
+        if !cleanup.had_git && target.join(".git").is_dir() {
+            let _ = std::fs::remove_dir_all(target.join(".git"));
+        }
+        for relative in &summary.created {
+            let _ = std::fs::remove_file(target.join(relative));
+            // Drop directories the copy created, stopping at the first one that isn't empty.
+            for parent in relative.ancestors().skip(1) {
+                if parent.as_os_str().is_empty() || std::fs::remove_dir(target.join(parent)).is_err() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    };
+    if let Err(err) = result {
+        utilities::warn(format_args!("could not clean up {} after the failed init: {}", target.display(), err));
+    }
+}
 
-    let resolved = ResolvedOptions::build(&config, template, git_flag, write_mode_flag);
-    let location = template.location.clone();
-    let location_is_url = utilities::is_git_url(&location);
+/// A scratch copy of `source` for a `pre_init_cwd: source` hook to run in, so the hook can't
+/// change the registered template, the shared URL cache or a ref worktree (or break a pinned
+/// checksum). Preserve gets a clone, everything else a plain copy. Returns the copy's path (a
+/// single-file template keeps its name) and the TempDir backing it.
+fn scratch_source(source: &Path, git_mode: &GitMode) -> Result<(PathBuf, tempfile::TempDir)> {
+    let scratch = tempfile::tempdir().context("failed to create temp dir")?;
+    let path = if source.is_file() {
+        let name = source.file_name().with_context(|| format!("template has no file name: {}", source.display()))?;
+        let path = scratch.path().join(name);
+        std::fs::copy(source, &path).with_context(|| format!("failed to copy {}", source.display()))?;
+        path
+    } else {
+        let path = scratch.path().join("source");
+        if *git_mode == GitMode::Preserve {
+            git::clone_local(source, &path)?;
+        } else {
+            fs_copy::copy_template(source, &path, &[], &WriteMode::Strict, &fs_copy::CopyOptions::default())?;
+        }
+        path
+    };
+    Ok((path, scratch))
+}
+
+/// The directory the pre-init hook runs in, per `pre_init_cwd`. A single-file template's
+/// source is the folder holding it.
+fn pre_init_dir<'a>(resolved: &ResolvedOptions, source: &'a Path, target: &'a Path) -> &'a Path {
+    match resolved.pre_init_cwd {
+        HookCwd::Target => target,
+        HookCwd::Source if source.is_file() => source.parent().unwrap_or(source),
+        HookCwd::Source => source,
+    }
+}
+
+/// Deletes the executable hooks in `repo`'s `.git/hooks`, leaving git's `.sample` files and
+/// anything else that isn't an executable file. `git clone` never copies the source repo's
+/// hooks: what a preserved clone has there comes from the user's own `init.templateDir`.
+pub(super) fn clear_git_hooks(repo: &Path) -> Result<()> {
+    let hooks_dir = repo.join(".git").join("hooks");
+    let Ok(entries) = std::fs::read_dir(&hooks_dir) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|extension| extension == "sample") || !is_executable_file(&path) {
+            continue;
+        }
+        std::fs::remove_file(&path).with_context(|| format!("failed to remove git hook {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Whether git would run `path` as a hook. Without unix modes every file counts.
+fn is_executable_file(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else { return false };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.is_file()
+    }
+}
+
+/// Re-runs a template's hooks against an already-initialized target. A `pre_init_cwd: source`
+/// pre-init hook runs in a scratch copy of `source`, which is only fetched when that's needed;
+/// whatever it writes there is thrown away.
+fn run_hooks_only(
+    resolved: &ResolvedOptions,
+    source: impl FnOnce() -> Result<FetchedSource>,
+    target_path: &Path,
+    template_name: &str,
+    stream: bool,
+) -> Result<()> {
+    let target_canonical = target_path
+        .canonicalize()
+        .with_context(|| format!("target does not exist: {}", target_path.display()))?;
+    if resolved.pre_init.is_none() && resolved.post_init.is_none() {
+        println!("{} has no hooks", template_name);
+        return Ok(());
+    }
+    if let Some(ref cmd) = resolved.pre_init {
+        let scratch = if resolved.pre_init_cwd == HookCwd::Source {
+            Some(scratch_source(&source()?.path, &resolved.git)?)
+        } else {
+            None
+        };
+        let source_path = scratch.as_ref().map_or(target_canonical.as_path(), |(path, _)| path.as_path());
+        let dir = pre_init_dir(resolved, source_path, &target_canonical);
+        utilities::run_hook(cmd, dir, resolved.hook_timeout_secs, stream)?;
+    }
+    if let Some(ref cmd) = resolved.post_init {
+        utilities::run_hook(cmd, &target_canonical, resolved.hook_timeout_secs, stream)?;
+    }
+    println!("ran {} hooks in {}", template_name, target_canonical.display());
+    Ok(())
+}
+
+/// Errors unless the manifest's hooks are trusted, approved, skipped, or there are none, so a
+/// template fetched from someone else can't run commands unseen.
+fn check_hooks_trusted(resolved: &ResolvedOptions, options: &InitOptions) -> Result<()> {
+    if options.no_hooks || options.trust_hooks || resolved.manifest_hooks.is_empty() {
+        return Ok(());
+    }
+    let Some(approve) = options.approve_hooks else {
+        return Err(TemplativeError::UntrustedHooks { hooks: resolved.manifest_hooks.clone() }.into());
+    };
+    if !approve(&resolved.manifest_hooks)? {
+        anyhow::bail!("aborted by user");
+    }
+    Ok(())
+}
 
-    let (template_path, _tempdir) = resolve_template_path(&location, location_is_url, &resolved)?;
+/// Shows the manifest's hooks and asks whether to run them.
+fn confirm_manifest_hooks(hooks: &[String]) -> Result<bool> {
+    eprintln!("the template's .templative.toml wants to run:");
+    for hook in hooks {
+        eprintln!("  {}", hook);
+    }
+    Confirm::new()
+        .with_prompt("Run these hooks?")
+        .default(false)
+        .interact()
+        .context("prompt failed (pass --trust-hooks or --no-hooks)")
+}
+
+/// Checks with `options.confirm_overwrite` before `Overwrite`/`Ask` writes into a non-empty
+/// target; `Strict` and `NoOverwrite` already refuse to clobber files.
+fn check_overwrite_confirmed(write_mode: &WriteMode, target: &Path, options: &InitOptions) -> Result<()> {
+    let Some(confirm) = options.confirm_overwrite else {
+        return Ok(());
+    };
+    if !matches!(write_mode, WriteMode::Overwrite | WriteMode::Ask) || utilities::is_dir_empty(target)? {
+        return Ok(());
+    }
+    if !confirm(target)? {
+        anyhow::bail!("aborted by user");
+    }
+    Ok(())
+}
+
+/// Asks whether to write into the non-empty `target`.
+fn prompt_overwrite(target: &Path) -> Result<bool> {
+    Confirm::new()
+        .with_prompt(format!("This will overwrite files in {}. Continue?", target.display()))
+        .default(false)
+        .interact()
+        .context("prompt failed")
+}
 
-    if !template_path.exists() || !template_path.is_dir() {
-        return Err(TemplativeError::TemplatePathMissing {
-            path: template_path.clone(),
+/// Something `init_template` wants the user told; `cmd_init` prints these to stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitNote {
+    Note(String),
+    Warning(String),
+}
+
+pub(super) fn print_notes(notes: &[InitNote]) {
+    for note in notes {
+        match note {
+            InitNote::Note(message) => eprintln!("note: {}", message),
+            InitNote::Warning(message) => utilities::warn(format_args!("{}", message)),
+        }
+    }
+}
+
+/// What a successful `init_template` did.
+#[derive(Debug)]
+pub struct InitOutcome {
+    /// The canonical directory written to, `target_subdir` included.
+    pub target: PathBuf,
+    pub git_mode: GitMode,
+    pub summary: fs_copy::CopySummary,
+    /// Set when a Fresh copy was staged but not committed, describing what was left to do.
+    pub uncommitted_note: Option<&'static str>,
+    pub notes: Vec<InitNote>,
+}
+
+impl InitOutcome {
+    /// Whether a template commit was made (always false outside `GitMode::Fresh`).
+    pub fn committed(&self) -> bool {
+        self.git_mode == GitMode::Fresh && self.uncommitted_note.is_none()
+    }
+}
+
+/// The target with the template's `target_subdir` joined on. Joined before creation so the
+/// empty/dangerous checks apply to the directory actually being written, not its parent.
+fn effective_target(target_path: &Path, resolved: &ResolvedOptions) -> Result<PathBuf, TemplativeError> {
+    match resolved.target_subdir {
+        Some(ref subdir) if !utilities::is_plain_relative(Path::new(subdir)) => {
+            Err(TemplativeError::InvalidTargetSubdir { subdir: subdir.clone() })
+        }
+        Some(ref subdir) => Ok(target_path.join(subdir)),
+        None => Ok(target_path.to_path_buf()),
+    }
+}
+
+/// A template source on disk and ready to copy.
+pub(super) struct FetchedSource {
+    /// The directory or single file to copy from, `subdir` applied (except under Preserve,
+    /// which clones the whole repo).
+    pub path: PathBuf,
+    /// The settings merged with the source's manifest.
+    pub resolved: ResolvedOptions,
+    /// Keeps `path` usable until dropped.
+    pub guard: SourceGuard,
+    pub notes: Vec<InitNote>,
+}
+
+/// Fetches or locates `template`'s source the way `init` would and merges in its manifest.
+pub(super) fn fetch_source(config: &Config, template: &Template, flags: &InitFlags) -> Result<FetchedSource> {
+    let resolved = ResolvedOptions::build(config, template, None, flags)?;
+    let location_is_url = utilities::is_git_url(&template.location);
+    if location_is_url || resolved.checkout_ref().is_some() {
+        git::ensure_available()?;
+    }
+
+    let (template_path, guard) = resolve_template_path(config, template, location_is_url, &resolved)?;
+    // The manifest can only be read once the source is on disk, so the settings are merged
+    // again with it. Nothing it covers is needed to fetch the source.
+    let manifest_dir = match resolved.subdir {
+        Some(ref subdir) => template_path.join(subdir),
+        None => template_path.clone(),
+    };
+    let manifest = if manifest_dir.is_dir() { Manifest::load(&manifest_dir)? } else { None };
+    let resolved = ResolvedOptions::build(config, template, manifest.as_ref(), flags)?;
+
+    let mut notes = Vec::new();
+    let path = match resolved.subdir {
+        Some(ref subdir) if resolved.git == GitMode::Preserve => {
+            notes.push(InitNote::Note(format!(
+                "git mode preserve clones the whole repo; ignoring subdir {}",
+                subdir
+            )));
+            template_path
+        }
+        Some(ref subdir) => resolve_subdir(&template_path, subdir)?,
+        None => template_path,
+    };
+    if !path.is_dir() && !path.is_file() {
+        return Err(TemplativeError::TemplatePathMissing { path }.into());
+    }
+    Ok(FetchedSource { path, resolved, guard, notes })
+}
+
+/// Errors unless `template` has no pinned checksum or `source` still matches it.
+fn verify_checksum(template: &Template, source: &Path, follow_symlinks: bool) -> Result<()> {
+    let Some(ref expected) = template.checksum else {
+        return Ok(());
+    };
+    let actual = fs_copy::tree_checksum(source, follow_symlinks)?;
+    if actual != *expected {
+        return Err(TemplativeError::ChecksumMismatch {
+            name: template.name.clone(),
+            expected: expected.clone(),
+            actual,
         }
         .into());
     }
+    Ok(())
+}
 
-    if !target_path.exists() {
+/// Creates a project from `template` in `target_path`: copy or clone, git setup and hooks,
+/// undoing a partial target on failure. Results and notes go to the returned outcome rather
+/// than stdout, and the registry is not touched. It only interacts with the user through
+/// `options`: the `confirm_overwrite`/`approve_hooks` callbacks, `ask` prompts when
+/// `interactive` is set, and hook output when `verbose` is (hooks otherwise run captured). A
+/// failed cleanup after an error is warned about. `options.only_hooks` and `options.json` are
+/// the caller's business and ignored here.
+pub fn init_template(
+    config: &Config,
+    template: &Template,
+    target_path: &Path,
+    options: &InitOptions,
+) -> Result<InitOutcome> {
+    if options.new_dir_only && target_path.exists() {
+        return Err(TemplativeError::TargetExists { path: target_path.to_path_buf() }.into());
+    }
+    let template_name = template.name.clone();
+    let location = template.location.clone();
+    let location_is_url = utilities::is_git_url(&location);
+    let FetchedSource { path: template_path, mut resolved, guard, mut notes } =
+        fetch_source(config, template, &options.flags)?;
+    verify_checksum(template, &template_path, resolved.follow_symlinks)?;
+    check_hooks_trusted(&resolved, options)?;
+    resolved.write_mode = non_interactive_write_mode(&resolved.write_mode, options.assume, options.interactive)?;
+    if resolved.git != GitMode::NoGit {
+        git::ensure_available()?;
+    }
+    let target_path = effective_target(target_path, &resolved)?;
+
+    if template_path.is_file() && resolved.git == GitMode::Preserve {
+        anyhow::bail!(
+            "git mode preserve needs a directory template; {} is a single file",
+            template_path.display()
+        );
+    }
+    // URL templates and `--ref` checkouts are always clones; a plain local folder would fail
+    // inside `git clone` with a much less helpful message.
+    if resolved.git == GitMode::Preserve && !git::is_git_repo(&template_path) {
+        return Err(TemplativeError::PreserveNeedsGitRepo { path: template_path.clone() }.into());
+    }
+
+    let created_root = first_missing_ancestor(&target_path);
+    if created_root.is_some() {
         std::fs::create_dir_all(&target_path)
             .with_context(|| format!("failed to create target: {}", target_path.display()))?;
     }
     let target_canonical = target_path
         .canonicalize()
         .with_context(|| format!("failed to canonicalize target: {}", target_path.display()))?;
+    let target_was_empty = utilities::is_dir_empty(&target_canonical)?;
+    let had_git = target_canonical.join(".git").exists();
 
-    if utilities::is_dangerous_path(&target_canonical) {
-        return Err(TemplativeError::DangerousPath {
-            path: target_canonical,
+    // Preserve clones rather than copies, so its summary stays empty.
+    let mut summary = fs_copy::CopySummary::default();
+    let result = (|| -> Result<Option<&'static str>> {
+        if utilities::is_dangerous_path(&target_canonical, &config.dangerous_paths) {
+            return Err(TemplativeError::DangerousPath {
+                path: target_canonical.clone(),
+            }
+            .into());
         }
-        .into());
-    }
 
-    if resolved.write_mode == WriteMode::Strict && !utilities::is_dir_empty(&target_canonical)? {
-        return Err(TemplativeError::TargetNotEmpty.into());
-    }
+        if resolved.write_mode == WriteMode::Strict && !target_was_empty {
+            return Err(TemplativeError::TargetNotEmpty.into());
+        }
 
-    if let Some(ref cmd) = resolved.pre_init {
-        utilities::run_hook(cmd, &target_canonical)?;
-    }
-
-    match resolved.git {
-        GitMode::Fresh => {
-            fs_copy::copy_template(
-                &template_path,
-                &target_canonical,
-                &resolved.exclude,
-                &resolved.write_mode,
-            )?;
-            if target_canonical.join(".git").exists() {
-                git::add_and_commit(&target_canonical, &template_name)?;
-            } else {
-                git::init_and_commit(&target_canonical, &template_name)?;
+        check_overwrite_confirmed(&resolved.write_mode, &target_canonical, options)?;
+
+        // A hook run in the source gets a scratch copy, which the project is then copied from
+        // (Preserve still clones the real source: uncommitted changes wouldn't carry anyway).
+        let mut scratch = None;
+        if !options.no_hooks
+            && let Some(ref cmd) = resolved.pre_init
+        {
+            if resolved.pre_init_cwd == HookCwd::Source {
+                scratch = Some(scratch_source(&template_path, &resolved.git)?);
             }
+            let source = scratch.as_ref().map_or(template_path.as_path(), |(path, _)| path.as_path());
+            let dir = pre_init_dir(&resolved, source, &target_canonical);
+            utilities::run_hook(cmd, dir, resolved.hook_timeout_secs, options.verbose)?;
         }
-        GitMode::Preserve => {
-            git::clone_local(&template_path, &target_canonical)?;
-            if location_is_url {
-                git::set_remote_url(&target_canonical, &location)?;
+        let copy_from = scratch.as_ref().map_or(template_path.as_path(), |(path, _)| path.as_path());
+
+        let mut uncommitted_note = None;
+        match resolved.git {
+            GitMode::Fresh => {
+                // Rendered before copying so an empty message fails before anything is written.
+                let commit_message = if resolved.no_commit {
+                    None
+                } else {
+                    Some(resolved.commit_message(&template_name)?)
+                };
+                summary = copy_source(copy_from, &target_canonical, &template_name, &resolved)?;
+                uncommitted_note =
+                    commit_fresh(&target_canonical, &summary, commit_message.as_deref(), options.amend, &mut notes)?;
+            }
+            GitMode::Preserve => {
+                clone_preserved(
+                    &resolved.preserve_strategy,
+                    &template_path,
+                    &location,
+                    !matches!(guard, SourceGuard::None),
+                    &target_canonical,
+                    &mut notes,
+                )?;
+                if location_is_url {
+                    git::set_remote_url(&target_canonical, &location)?;
+                }
+                if resolved.clear_git_hooks {
+                    clear_git_hooks(&target_canonical)?;
+                }
             }
+            GitMode::NoGit => {
+                summary = copy_source(copy_from, &target_canonical, &template_name, &resolved)?;
+            }
+        }
+
+        if !options.no_hooks
+            && let Some(ref cmd) = resolved.post_init
+        {
+            utilities::run_hook(cmd, &target_canonical, resolved.hook_timeout_secs, options.verbose)?;
         }
-        GitMode::NoGit => {
-            fs_copy::copy_template(
-                &template_path,
-                &target_canonical,
-                &resolved.exclude,
-                &resolved.write_mode,
-            )?;
+        Ok(uncommitted_note)
+    })();
+    match result {
+        Ok(uncommitted_note) => Ok(InitOutcome {
+            target: target_canonical,
+            git_mode: resolved.git,
+            summary,
+            uncommitted_note,
+            notes,
+        }),
+        Err(err) => {
+            let cleanup = Cleanup { created_root: created_root.as_deref(), target_was_empty, had_git };
+            clean_up_failed_init(&target_canonical, &cleanup, &summary);
+            Err(err)
         }
     }
+}
 
-    if let Some(ref cmd) = resolved.post_init {
-        utilities::run_hook(cmd, &target_canonical)?;
+/// A throwaway template for `init <url-or-path>` when the argument isn't a registered name:
+/// a git URL, or an existing file or directory. It uses config defaults for everything.
+fn unregistered_template(spec: &str) -> Option<Template> {
+    let location = if utilities::is_git_url(spec) {
+        spec.to_string()
+    } else {
+        Path::new(spec).canonicalize().ok()?.to_string_lossy().into_owned()
+    };
+    Some(Template { name: utilities::name_from_location(&location), location, ..Template::default() })
+}
+
+/// Checks that `--output-name` names a single directory, so it can't climb out of the target.
+fn output_dir_name(name: &str) -> Result<&Path> {
+    let path = Path::new(name);
+    if !utilities::is_plain_relative(path) || path.components().count() != 1 {
+        anyhow::bail!("--output-name must be a plain directory name, not {:?}", name);
+    }
+    Ok(path)
+}
+
+/// Runs `init`. `template_name` is looked up in the registry first; only when no template has
+/// that name is it tried as a git URL and then as a path, so a registered name always wins
+/// over a same-named directory.
+pub fn cmd_init(
+    mut config: Config,
+    template_name: String,
+    target_path: PathBuf,
+    mut options: InitOptions,
+) -> Result<()> {
+    let stdin_is_terminal = std::io::stdin().is_terminal();
+    if options.approve_hooks.is_none() && !options.json && stdin_is_terminal {
+        options.approve_hooks = Some(confirm_manifest_hooks);
+    }
+    if options.confirm_overwrite.is_none() && !options.yes && stdin_is_terminal {
+        options.confirm_overwrite = Some(prompt_overwrite);
     }
+    options.interactive |= stdin_is_terminal;
+    let registry = Registry::load(&config)?;
+    let (template, registered) = match registry.get(&template_name) {
+        Some(template) => (template.clone(), true),
+        None => {
+            let template = unregistered_template(&template_name)
+                .ok_or_else(|| TemplativeError::TemplateNotFound { name: template_name.clone() })
+                .with_context(|| "run 'templative list' to see available templates")?;
+            // A one-off URL gets a throwaway clone rather than a cache nothing would own.
+            config.no_cache = true;
+            (template, false)
+        }
+    };
+    let template = &template;
+    let template_name = template.name.clone();
+    let target_path = match options.output_name {
+        Some(ref name) => target_path.join(output_dir_name(name)?),
+        None => target_path,
+    };
 
-    println!(
-        "created {} from {}",
-        target_canonical.display(),
-        template_name
-    );
+    if options.only_hooks {
+        let manifest = Manifest::load_for(&config, template)?;
+        let resolved = ResolvedOptions::build(&config, template, manifest.as_ref(), &options.flags)?;
+        check_hooks_trusted(&resolved, &options)?;
+        return run_hooks_only(
+            &resolved,
+            || fetch_source(&config, template, &options.flags),
+            &effective_target(&target_path, &resolved)?,
+            &template_name,
+            options.verbose,
+        );
+    }
+
+    let outcome = init_template(&config, template, &target_path, &options)?;
+    print_notes(&outcome.notes);
+
+    if registered {
+        record_last_used(&config, &template_name);
+        if options.remember
+            && let Some(ref mode) = outcome.summary.escalated_mode
+        {
+            remember_write_mode(&config, &template_name, mode);
+        }
+    }
+
+    if options.json {
+        let committed = outcome.committed();
+        let report = InitReport {
+            target: &outcome.target,
+            template: &template_name,
+            git_mode: &outcome.git_mode,
+            files_created: outcome.summary.created,
+            files_overwritten: outcome.summary.overwritten,
+            files_skipped: outcome.summary.skipped,
+            committed,
+        };
+        println!("{}", serde_json::to_string_pretty(&report).context("failed to serialize report")?);
+    } else {
+        println!(
+            "created {} from {}{}",
+            outcome.target.display(),
+            template_name,
+            outcome.uncommitted_note.map(|note| format!(" ({})", note)).unwrap_or_default()
+        );
+        if let Some(ref next_steps) = template.next_steps {
+            println!();
+            println!("{}", render_next_steps(next_steps, &template_name, &outcome.target).trim_end());
+        }
+    }
     Ok(())
 }
+
+/// Fills `{{project}}` (the target folder's name), `{{template}}` and `{{target}}` (the full
+/// path) in a template's next-steps text. Unknown placeholders are left as written.
+pub(super) fn render_next_steps(text: &str, template_name: &str, target: &Path) -> String {
+    let project = target.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    text.replace("{{project}}", &project)
+        .replace("{{template}}", template_name)
+        .replace("{{target}}", &target.to_string_lossy())
+}
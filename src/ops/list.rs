@@ -1,40 +1,87 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use owo_colors::OwoColorize;
 use unicode_width::UnicodeWidthStr;
 
+use crate::config::Config;
 use crate::git;
-use crate::registry::{Registry, Template};
-use crate::utilities;
+use crate::manifest::Manifest;
+use crate::registry::{ListSort, Registry, Template};
+use crate::resolved::{InitFlags, ResolvedOptions};
+use crate::utilities::{self, ColorChoice};
 
 enum Style { Normal, Yellow, Blue, Red, RedThrough }
 
 struct Row {
     name: String,
+    mode: String,
     description: String,
+    tags: String,
+    last_used: String,
     location: String,
     status: String,
     style: Style,
 }
 
-fn git_ref_status(tmpl: &Template, path: &PathBuf, is_url: bool) -> Option<(String, Style)> {
-    let ref_val = tmpl.git_ref.as_deref()?;
+/// Looks like an abbreviated or full commit SHA, which `ls-remote` can't check.
+fn looks_like_sha(git_ref: &str) -> bool {
+    (7..=40).contains(&git_ref.len()) && git_ref.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+/// Status for a URL template's pinned ref when there's no cached clone to inspect.
+fn remote_ref_status(url: &str, ref_val: &str) -> (String, Style) {
+    if looks_like_sha(ref_val) {
+        return (format!("(git ref {})", ref_val), Style::Blue);
+    }
+    match git::ls_remote_has_ref(url, ref_val) {
+        Ok(true) => (format!("(git ref {} on remote)", ref_val), Style::Blue),
+        Ok(false) => (format!("(git {} missing on remote)", ref_val), Style::Red),
+        Err(_) => (format!("(git ref {}, remote unreachable)", ref_val), Style::Yellow),
+    }
+}
+
+fn git_ref_status(
+    config: &Config,
+    tmpl: &Template,
+    path: &Path,
+    is_url: bool,
+    remote: bool,
+) -> Option<(String, Style)> {
+    let ref_val = tmpl.commit.as_deref().or(tmpl.git_ref.as_deref())?;
     let repo = if is_url {
-        utilities::cache_path_for_url(&tmpl.location).ok()
+        utilities::cache_path_for_url(config, &tmpl.location).ok()
             .filter(|cache_path| cache_path.join(".git").exists())
     } else if path.join(".git").exists() {
-        Some(path.clone())
+        Some(path.to_path_buf())
     } else {
         None
     };
     Some(match repo {
+        None if is_url && remote => remote_ref_status(&tmpl.location, ref_val),
         None => (format!("(git ref {})", ref_val), Style::Blue),
         Some(repo_path) if !git::ref_exists(&repo_path, ref_val) => {
             (format!("(git {} missing)", ref_val), Style::Red)
         }
         Some(repo_path) => {
-            let status_str = match git::classify_ref(&repo_path, ref_val) {
+            let kind = git::classify_ref(&repo_path, ref_val);
+            // The recorded kind describes `git_ref`, which a pinned commit overrides.
+            if let Some(recorded) = tmpl.git_ref_kind
+                && tmpl.commit.is_none()
+                && recorded != kind
+            {
+                return Some((
+                    format!(
+                        "(ref kind changed: {} was a {}, now a {})",
+                        ref_val, recorded.as_str(), kind.as_str()
+                    ),
+                    Style::Red,
+                ));
+            }
+            let status_str = match kind {
+                git::RefKind::Branch if is_diverged(&repo_path, ref_val) => {
+                    return Some((format!("(git branch {} diverged)", ref_val), Style::Yellow));
+                }
                 git::RefKind::Branch => format!("(in git branch {})", ref_val),
                 git::RefKind::Tag    => format!("(at git tag {})", ref_val),
                 git::RefKind::Commit => format!("(at git commit {})", ref_val),
@@ -44,6 +91,11 @@ fn git_ref_status(tmpl: &Template, path: &PathBuf, is_url: bool) -> Option<(Stri
     })
 }
 
+/// A pinned branch with commits both locally and on origin can't be fast-forwarded by update.
+fn is_diverged(repo: &Path, branch: &str) -> bool {
+    matches!(git::branch_ahead_behind(repo, branch), Ok((ahead, behind)) if ahead > 0 && behind > 0)
+}
+
 fn worse_style(a: Style, b: Style) -> Style {
     match (a, b) {
         (Style::RedThrough, _) | (_, Style::RedThrough) => Style::RedThrough,
@@ -54,10 +106,15 @@ fn worse_style(a: Style, b: Style) -> Style {
     }
 }
 
-fn template_status(tmpl: &Template) -> (String, Style) {
-    let path = PathBuf::from(&tmpl.location);
+/// With `remote`, pinned refs of uncached URL templates are checked with `git ls-remote`.
+fn template_status(config: &Config, tmpl: &Template, remote: bool) -> (String, Style) {
+    // A location using a variable that isn't set here may well exist elsewhere.
+    let Ok(is_missing) = tmpl.is_location_missing() else {
+        return ("(variable unset)".into(), Style::Yellow);
+    };
+    let path = utilities::expand_location(&tmpl.location)
+        .unwrap_or_else(|_| PathBuf::from(&tmpl.location));
     let is_url = utilities::is_git_url(&tmpl.location);
-    let is_missing = !is_url && !path.exists();
     let is_file = !is_url && !is_missing && path.is_file();
     let is_empty = !is_url && !is_missing && !is_file
         && utilities::is_dir_empty(&path).unwrap_or(false);
@@ -71,13 +128,13 @@ fn template_status(tmpl: &Template) -> (String, Style) {
         return ("(folder empty)".into(), Style::Red);
     }
     if is_file {
-        if let Some((git_str, git_style)) = git_ref_status(tmpl, &path, is_url) {
+        if let Some((git_str, git_style)) = git_ref_status(config, tmpl, &path, is_url, remote) {
             let combined_style = worse_style(Style::Blue, git_style);
             return (format!("(single file) {}", git_str), combined_style);
         }
         return ("(single file)".into(), Style::Blue);
     }
-    if let Some(git_annotation) = git_ref_status(tmpl, &path, is_url) {
+    if let Some(git_annotation) = git_ref_status(config, tmpl, &path, is_url, remote) {
         return git_annotation;
     }
     if has_no_git {
@@ -85,43 +142,85 @@ fn template_status(tmpl: &Template) -> (String, Style) {
     }
     // Check if update available (no network call; uses cached remote tracking refs)
     if utilities::is_git_url(&tmpl.location) {
-        if let Ok(cache_path) = utilities::cache_path_for_url(&tmpl.location) {
-            if git::is_behind_remote(&cache_path) {
-                return ("(update available)".into(), Style::Yellow);
-            }
-        }
-    } else if git::is_git_repo(&path) {
-        if git::is_behind_remote(&path) {
+        if let Ok(cache_path) = utilities::cache_path_for_url(config, &tmpl.location)
+            && git::is_behind_remote(&cache_path)
+        {
             return ("(update available)".into(), Style::Yellow);
         }
+    } else if git::is_git_repo(&path) && git::is_behind_remote(&path) {
+        return ("(update available)".into(), Style::Yellow);
     }
     (String::new(), Style::Normal)
 }
 
+/// The status `list` shows for `tmpl`, without styling or network checks. Empty when there's
+/// nothing to flag.
+pub(super) fn status_text(config: &Config, tmpl: &Template) -> String {
+    template_status(config, tmpl, false).0
+}
+
 fn col_width(header: &str, values: impl Iterator<Item = usize>) -> usize {
     values.max().unwrap_or(0).max(header.width())
 }
 
-pub fn cmd_list(color: bool, names_only: bool) -> Result<()> {
-    let registry = Registry::load()?;
+/// The git mode `init` would use for `tmpl` without flags, or `?` if its settings don't merge.
+fn resolved_mode(config: &Config, tmpl: &Template) -> String {
+    let manifest = Manifest::load_for(config, tmpl).ok().flatten();
+    ResolvedOptions::build(config, tmpl, manifest.as_ref(), &InitFlags::default())
+        .map(|resolved| resolved.git.as_str().to_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
+
+#[derive(Default)]
+pub struct ListOptions {
+    /// Print only template names, one per line.
+    pub names_only: bool,
+    /// Only templates carrying all of these tags.
+    pub tags: Vec<String>,
+    pub sort: ListSort,
+    pub json: bool,
+    /// Check pinned refs of uncached URL templates with `git ls-remote`.
+    pub remote: bool,
+    /// Add a MODE column with the git mode each template would init with.
+    pub show_mode: bool,
+}
+
+pub fn cmd_list(config: &Config, color: ColorChoice, options: ListOptions) -> Result<()> {
+    let ListOptions { names_only, tags, sort, json, remote, show_mode } = options;
+    let color = color.enabled();
+    let registry = Registry::load(config)?;
+    if json {
+        let templates = registry.templates_with_tags(&tags, sort);
+        println!("{}", serde_json::to_string_pretty(&templates).context("failed to serialize templates")?);
+        return Ok(());
+    }
     if registry.templates.is_empty() {
         if !names_only {
             println!("no templates available: use `templative add <FOLDER>` to add a template");
         }
         return Ok(());
     }
+    let templates = registry.templates_with_tags(&tags, sort);
     if names_only {
-        for tmpl in registry.templates_sorted() {
+        for tmpl in templates {
             println!("{}", tmpl.name);
         }
         return Ok(());
     }
+    if templates.is_empty() {
+        println!("no templates tagged {}", tags.join(", "));
+        return Ok(());
+    }
 
-    let rows: Vec<Row> = registry.templates_sorted().iter().map(|tmpl| {
-        let (status, style) = template_status(tmpl);
+    let rows: Vec<Row> = templates.iter().map(|tmpl| {
+        let (status, style) = template_status(config, tmpl, remote);
         Row {
             name: tmpl.name.clone(),
+            mode: if show_mode { resolved_mode(config, tmpl) } else { String::new() },
             description: tmpl.description.as_deref().unwrap_or("").to_string(),
+            tags: tmpl.tags.as_deref().map(|tags| tags.join(", ")).unwrap_or_default(),
+            // Date only; the full timestamp is in `list --json`.
+            last_used: tmpl.last_used.as_deref().map(|time| time.chars().take(10).collect()).unwrap_or_default(),
             location: tmpl.location.clone(),
             status,
             style,
@@ -145,10 +244,15 @@ pub fn cmd_list(color: bool, names_only: bool) -> Result<()> {
 
     let show_status = rows.iter().any(|row| !row.status.is_empty());
     let show_desc   = rows.iter().any(|row| !row.description.is_empty());
+    let show_tags   = rows.iter().any(|row| !row.tags.is_empty());
+    let show_used   = rows.iter().any(|row| !row.last_used.is_empty());
 
     let name_w   = col_width("NAME",        rows.iter().map(|row| row.name.width()));
+    let mode_w   = if show_mode   { col_width("MODE",        rows.iter().map(|row| row.mode.width())) } else { 0 };
     let status_w = if show_status { col_width("STATUS",      rows.iter().map(|row| row.status.width())) } else { 0 };
     let desc_w   = if show_desc   { col_width("DESCRIPTION", rows.iter().map(|row| row.description.width())) } else { 0 };
+    let tags_w   = if show_tags   { col_width("TAGS",        rows.iter().map(|row| row.tags.width())) } else { 0 };
+    let used_w   = if show_used   { col_width("LAST USED",   rows.iter().map(|row| row.last_used.width())) } else { 0 };
 
     let apply_style = |text: String, style: &Style| -> String {
         if !color { return text; }
@@ -162,17 +266,97 @@ pub fn cmd_list(color: bool, names_only: bool) -> Result<()> {
     };
 
     let mut header = pad_underlined("NAME", name_w);
+    if show_mode   { header = format!("{}  {}", header, pad_underlined("MODE", mode_w)); }
     if show_status { header = format!("{}  {}", header, pad_underlined("STATUS", status_w)); }
     if show_desc   { header = format!("{}  {}", header, pad_underlined("DESCRIPTION", desc_w)); }
+    if show_tags   { header = format!("{}  {}", header, pad_underlined("TAGS", tags_w)); }
+    if show_used   { header = format!("{}  {}", header, pad_underlined("LAST USED", used_w)); }
     let location_header = if color { format!("{}", "LOCATION".underline()) } else { "LOCATION".to_string() };
     println!("{}  {}", header, location_header);
 
     for row in &rows {
         let mut line = pad(&row.name, name_w);
+        if show_mode   { line = format!("{}  {}", line, pad(&row.mode, mode_w)); }
         if show_status { line = format!("{}  {}", line, pad(&row.status, status_w)); }
         if show_desc   { line = format!("{}  {}", line, pad(&row.description, desc_w)); }
+        if show_tags   { line = format!("{}  {}", line, pad(&row.tags, tags_w)); }
+        if show_used   { line = format!("{}  {}", line, pad(&row.last_used, used_w)); }
         let line = format!("{}  {}", line, row.location);
         println!("{}", apply_style(line, &row.style));
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::RefKind;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn pinned_template(location: &Path, git_ref: &str, kind: Option<RefKind>) -> Template {
+        Template {
+            name: "pinned".into(),
+            location: location.to_string_lossy().into_owned(),
+            git: None,
+            description: None,
+            pre_init: None,
+            post_init: None,
+            git_ref: Some(git_ref.into()),
+            exclude: None,
+            write_mode: None,
+            target_subdir: None,
+            tags: None,
+            default_branch: None,
+            created_at: None,
+            last_used: None,
+            subdir: None,
+            git_ref_kind: kind,
+            gitignore_template: None,
+            commit: None,
+            next_steps: None,
+            max_file_size: None,
+            exclude_binaries: None,
+            checksum: None,
+        }
+    }
+
+    #[test]
+    fn ref_kind_change_is_flagged() {
+        let repo = tempdir().unwrap();
+        git(repo.path(), &["init", "-b", "main"]);
+        std::fs::write(repo.path().join("file.txt"), "v1").unwrap();
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-m", "initial"]);
+        git(repo.path(), &["tag", "release"]);
+
+        let unchanged = pinned_template(repo.path(), "release", Some(RefKind::Tag));
+        assert_eq!(template_status(&Config::new(), &unchanged, false).0, "(at git tag release)");
+
+        let changed = pinned_template(repo.path(), "release", Some(RefKind::Branch));
+        assert_eq!(template_status(&Config::new(), &changed, false).0, "(ref kind changed: release was a branch, now a tag)");
+
+        let unrecorded = pinned_template(repo.path(), "release", None);
+        assert_eq!(template_status(&Config::new(), &unrecorded, false).0, "(at git tag release)");
+    }
+
+    #[test]
+    fn resolved_mode_merges_template_and_config() {
+        let mut config = Config::new();
+        config.git = crate::config::GitMode::NoGit;
+        let mut tmpl = Template { name: "t".into(), location: "/nonexistent/t".into(), ..Template::default() };
+        assert_eq!(resolved_mode(&config, &tmpl), "no-git");
+        tmpl.git = Some(crate::config::GitMode::Preserve);
+        assert_eq!(resolved_mode(&config, &tmpl), "preserve");
+    }
+}
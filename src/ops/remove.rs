@@ -1,13 +1,57 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use dialoguer::Confirm;
 
+use crate::config::Config;
 use crate::registry::Registry;
+use crate::utilities;
 
-pub fn cmd_remove(template_names: Vec<String>) -> Result<()> {
-    let mut registry = Registry::load()?;
+use super::add;
+
+pub fn cmd_remove(config: &Config, template_names: Vec<String>, all: bool, missing: bool, yes: bool) -> Result<()> {
+    let mut registry = Registry::load_for_update(config)?;
+    let template_names: Vec<String> = if all {
+        registry.templates_sorted().iter().map(|tmpl| tmpl.name.clone()).collect()
+    } else if missing {
+        registry
+            .templates_sorted()
+            .iter()
+            .filter(|tmpl| match tmpl.is_location_missing() {
+                Ok(missing) => missing,
+                Err(err) => {
+                    utilities::warn(format_args!("keeping {}: {:#}", tmpl.name, err));
+                    false
+                }
+            })
+            .map(|tmpl| tmpl.name.clone())
+            .collect()
+    } else {
+        template_names
+    };
+    if template_names.is_empty() {
+        println!("no templates to remove");
+        return Ok(());
+    }
+    if all && !yes {
+        let confirmed = Confirm::new()
+            .with_prompt(format!("Remove all {} templates?", template_names.len()))
+            .default(false)
+            .interact()
+            .context("prompt failed (pass --yes to skip confirmation)")?;
+        if !confirmed {
+            anyhow::bail!("aborted by user");
+        }
+    }
+    let mut locations = Vec::new();
     for name in &template_names {
+        if let Some(template) = registry.get(name) {
+            locations.push(template.location.clone());
+        }
         registry.remove(name)?;
     }
     registry.save()?;
+    for location in &locations {
+        add::remove_snippet(config, location);
+    }
     for name in &template_names {
         println!("removed {}", name);
     }
@@ -2,9 +2,12 @@ use std::path::PathBuf;
 
 use tempfile::tempdir;
 
+use crate::config::Config;
 use crate::errors::TemplativeError;
 use crate::registry::{Registry, Template};
+use crate::resolved::InitFlags;
 use crate::test_env::ENV_LOCK;
+use crate::utilities::ColorChoice;
 
 use super::*;
 
@@ -32,35 +35,131 @@ impl Drop for IsolatedConfig {
     }
 }
 
+fn add_options(name: &str) -> AddOptions {
+    AddOptions {
+        name: Some(name.into()),
+        description: None,
+        git: None,
+        git_ref: None,
+        exclude: vec![],
+        write_mode: None,
+        tags: vec![],
+        force: false,
+        subdir: None,
+        commit: None,
+        stdin: false,
+        allow_empty: false,
+        gitignore_template: None,
+        max_file_size: None,
+        exclude_binaries: None,
+    }
+}
+
 #[test]
 fn cmd_add_registers_local_template() {
     let _config = IsolatedConfig::new();
     let template_dir = tempdir().unwrap();
 
-    cmd_add(
-        template_dir.path().to_str().unwrap().to_string(),
-        Some("my-template".into()),
-        None,
-        None,
-        None,
-        vec![],
-        None,
+    cmd_add(&Config::new(), template_dir.path().to_str().unwrap().to_string(),
+        add_options("my-template"),
     )
     .unwrap();
 
-    let registry = crate::registry::Registry::load().unwrap();
+    let registry = crate::registry::Registry::load(&Config::new()).unwrap();
     assert!(registry.get("my-template").is_some());
 }
 
+#[test]
+fn cmd_add_all_registers_subdirs_and_skips_existing() {
+    let _config = IsolatedConfig::new();
+    let parent = tempdir().unwrap();
+    for name in ["alpha", "beta", ".hidden"] {
+        std::fs::create_dir(parent.path().join(name)).unwrap();
+    }
+    std::fs::write(parent.path().join("notes.txt"), "not a template").unwrap();
+    let parent_path = parent.path().to_str().unwrap().to_string();
+
+    cmd_add_all(&Config::new(), parent_path.clone(), Some(crate::config::GitMode::NoGit), None).unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.templates.len(), 2);
+    assert_eq!(registry.get("alpha").unwrap().git, Some(crate::config::GitMode::NoGit));
+    assert!(registry.get("beta").is_some());
+    assert!(registry.get(".hidden").is_none());
+
+    // A second run finds both registered and leaves them alone.
+    cmd_add_all(&Config::new(), parent_path, None, None).unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.templates.len(), 2);
+    assert_eq!(registry.get("alpha").unwrap().git, Some(crate::config::GitMode::NoGit));
+}
+
+#[test]
+fn cmd_add_allow_empty_registers_missing_path() {
+    let _config = IsolatedConfig::new();
+    let parent = tempdir().unwrap();
+    let missing = parent.path().join("later");
+    let missing_str = missing.to_str().unwrap().to_string();
+
+    assert!(cmd_add(&Config::new(), missing_str.clone(), add_options("later")).is_err());
+    cmd_add(&Config::new(), missing_str, AddOptions { allow_empty: true, ..add_options("later") }).unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    let template = registry.get("later").unwrap();
+    assert_eq!(std::path::Path::new(&template.location), missing);
+    assert_eq!(list::status_text(&Config::new(), template), "(template missing)");
+}
+
+#[test]
+fn cmd_add_keeps_a_variable_location_as_written() {
+    let _config = IsolatedConfig::new();
+    let root = tempdir().unwrap();
+    std::fs::create_dir(root.path().join("site")).unwrap();
+    unsafe { std::env::set_var("TEMPLATIVE_TEST_ADD_ROOT", root.path()); }
+
+    let added = cmd_add(&Config::new(), "$TEMPLATIVE_TEST_ADD_ROOT/site".into(), AddOptions { name: None, ..add_options("") });
+    let missing = cmd_add(&Config::new(), "$TEMPLATIVE_TEST_ADD_ROOT/nope".into(), add_options("nope"));
+    unsafe { std::env::remove_var("TEMPLATIVE_TEST_ADD_ROOT"); }
+    added.unwrap();
+    assert!(missing.is_err());
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.get("site").unwrap().location, "$TEMPLATIVE_TEST_ADD_ROOT/site");
+}
+
 #[test]
 fn cmd_add_duplicate_name_errors() {
     let _config = IsolatedConfig::new();
     let template_dir = tempdir().unwrap();
     let path = template_dir.path().to_str().unwrap().to_string();
 
-    cmd_add(path.clone(), Some("dup".into()), None, None, None, vec![], None).unwrap();
-    let result = cmd_add(path, Some("dup".into()), None, None, None, vec![], None);
-    assert!(result.is_err());
+    cmd_add(&Config::new(), path.clone(), add_options("dup")).unwrap();
+    let result = cmd_add(&Config::new(), path, add_options("dup"));
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::TemplateExists { .. })
+    ));
+}
+
+#[test]
+fn cmd_add_force_replaces_existing_template() {
+    let _config = IsolatedConfig::new();
+    let first_dir = tempdir().unwrap();
+    let second_dir = tempdir().unwrap();
+
+    cmd_add(&Config::new(), first_dir.path().to_str().unwrap().to_string(),
+        AddOptions { description: Some("old".into()), ..add_options("dup") },
+    )
+    .unwrap();
+    cmd_add(&Config::new(), second_dir.path().to_str().unwrap().to_string(),
+        AddOptions { force: true, ..add_options("dup") },
+    )
+    .unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    let template = registry.get("dup").unwrap();
+    let expected = second_dir.path().canonicalize().unwrap().to_string_lossy().into_owned();
+    assert_eq!(template.location, expected);
+    assert!(template.description.is_none());
+    assert_eq!(registry.templates.len(), 1);
 }
 
 #[test]
@@ -68,27 +167,21 @@ fn cmd_remove_deregisters_template() {
     let _config = IsolatedConfig::new();
     let template_dir = tempdir().unwrap();
 
-    cmd_add(
-        template_dir.path().to_str().unwrap().to_string(),
-        Some("to-remove".into()),
-        None,
-        None,
-        None,
-        vec![],
-        None,
+    cmd_add(&Config::new(), template_dir.path().to_str().unwrap().to_string(),
+        add_options("to-remove"),
     )
     .unwrap();
 
-    cmd_remove(vec!["to-remove".into()]).unwrap();
+    cmd_remove(&Config::new(), vec!["to-remove".into()], false, false, false).unwrap();
 
-    let registry = crate::registry::Registry::load().unwrap();
+    let registry = crate::registry::Registry::load(&Config::new()).unwrap();
     assert!(registry.get("to-remove").is_none());
 }
 
 #[test]
 fn cmd_remove_nonexistent_errors() {
     let _config = IsolatedConfig::new();
-    let result = cmd_remove(vec!["ghost".into()]);
+    let result = cmd_remove(&Config::new(), vec!["ghost".into()], false, false, false);
     assert!(result.is_err());
 }
 
@@ -97,29 +190,122 @@ fn cmd_remove_multiple_all_or_nothing() {
     let _config = IsolatedConfig::new();
     let template_dir = tempdir().unwrap();
 
-    cmd_add(
-        template_dir.path().to_str().unwrap().to_string(),
-        Some("real".into()),
-        None,
-        None,
-        None,
-        vec![],
-        None,
+    cmd_add(&Config::new(), template_dir.path().to_str().unwrap().to_string(),
+        add_options("real"),
     )
     .unwrap();
 
     // "ghost" doesn't exist — neither should be removed
-    let result = cmd_remove(vec!["real".into(), "ghost".into()]);
+    let result = cmd_remove(&Config::new(), vec!["real".into(), "ghost".into()], false, false, false);
     assert!(result.is_err());
 
-    let registry = crate::registry::Registry::load().unwrap();
+    let registry = crate::registry::Registry::load(&Config::new()).unwrap();
     assert!(registry.get("real").is_some());
 }
 
+#[test]
+fn cmd_remove_missing_removes_only_missing_templates() {
+    let config = IsolatedConfig::new();
+    let present = tempdir().unwrap();
+    setup_registry(&config, vec![
+        make_template("present", present.path().to_str().unwrap()),
+        make_template("gone", "/this/path/does/not/exist/ever"),
+        make_template("remote", "https://github.com/user/repo"),
+    ]);
+
+    cmd_remove(&Config::new(), vec![], false, true, false).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("present").is_some());
+    assert!(registry.get("remote").is_some());
+    assert!(registry.get("gone").is_none());
+}
+
+#[test]
+fn cmd_remove_missing_keeps_templates_with_unset_variables() {
+    let config = IsolatedConfig::new();
+    let unset = make_template("elsewhere", "$TEMPLATIVE_TEST_SURELY_UNSET/site");
+    assert_eq!(list::status_text(&Config::new(), &unset), "(variable unset)");
+    setup_registry(&config, vec![unset, make_template("gone", "/this/path/does/not/exist/ever")]);
+
+    cmd_remove(&Config::new(), vec![], false, true, false).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("elsewhere").is_some());
+    assert!(registry.get("gone").is_none());
+}
+
+#[test]
+fn cmd_remove_all_with_yes_clears_registry() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("foo", "/tmp"), make_template("bar", "/tmp")]);
+
+    cmd_remove(&Config::new(), vec![], true, false, true).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.templates.is_empty());
+}
+
+#[test]
+fn cmd_undo_restores_registry_before_remove_once() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("foo", "/tmp"), make_template("bar", "/tmp")]);
+
+    cmd_remove(&Config::new(), vec!["foo".into()], false, false, false).unwrap();
+    assert!(Registry::load(&Config::new()).unwrap().get("foo").is_none());
+
+    cmd_undo(&Config::new()).unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("foo").is_some());
+    assert!(registry.get("bar").is_some());
+
+    // Only one level is kept, and restoring uses it up.
+    assert!(cmd_undo(&Config::new()).is_err());
+}
+
+#[test]
+fn cmd_undo_restores_registry_before_change_and_import() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("foo", "/tmp")]);
+
+    cmd_change(&Config::new(), "foo".into(), ChangeOptions { name: Some("renamed".into()), ..empty_change_options() })
+        .unwrap();
+    cmd_undo(&Config::new()).unwrap();
+    assert!(Registry::load(&Config::new()).unwrap().get("foo").is_some());
+
+    let import_dir = tempdir().unwrap();
+    let import_path = import_dir.path().join("import.json");
+    let mut imported = Registry::new();
+    imported.templates.push(make_template("new", "/imported"));
+    imported.save_to_path(&import_path).unwrap();
+    cmd_import(&Config::new(), import_path, false, false).unwrap();
+    assert!(Registry::load(&Config::new()).unwrap().get("new").is_some());
+
+    cmd_undo(&Config::new()).unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("new").is_none());
+    assert!(registry.get("foo").is_some());
+}
+
+#[test]
+fn cmd_undo_keeps_the_last_change_through_a_save_that_changes_nothing() {
+    let _config = IsolatedConfig::new();
+    let parent = tempdir().unwrap();
+    std::fs::create_dir(parent.path().join("alpha")).unwrap();
+    let parent_path = parent.path().to_str().unwrap().to_string();
+
+    cmd_add_all(&Config::new(), parent_path.clone(), None, None).unwrap();
+    // Finds nothing new, so the snapshot from before the first run must survive.
+    cmd_add_all(&Config::new(), parent_path, None, None).unwrap();
+
+    cmd_undo(&Config::new()).unwrap();
+    assert!(Registry::load(&Config::new()).unwrap().get("alpha").is_none());
+}
+
 #[test]
 fn cmd_list_succeeds_with_empty_registry() {
     let _config = IsolatedConfig::new();
-    cmd_list(false, false).unwrap();
+    cmd_list(&crate::config::Config::new(), ColorChoice::Never, ListOptions::default()).unwrap();
 }
 
 #[test]
@@ -127,18 +313,31 @@ fn cmd_list_succeeds_with_templates() {
     let _config = IsolatedConfig::new();
     let template_dir = tempdir().unwrap();
 
-    cmd_add(
-        template_dir.path().to_str().unwrap().to_string(),
-        Some("listed".into()),
-        Some("a template".into()),
-        None,
-        None,
-        vec![],
-        None,
+    cmd_add(&Config::new(), template_dir.path().to_str().unwrap().to_string(),
+        AddOptions { description: Some("a template".into()), ..add_options("listed") },
     )
     .unwrap();
 
-    cmd_list(false, false).unwrap();
+    cmd_list(&crate::config::Config::new(), ColorChoice::Never, ListOptions::default()).unwrap();
+}
+
+#[test]
+fn cmd_list_filters_by_tag() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![
+        Template { tags: Some(vec!["web".into()]), ..make_template("tagged", "/tmp") },
+        make_template("untagged", "/tmp"),
+    ]);
+    cmd_list(
+        &crate::config::Config::new(),
+        ColorChoice::Never,
+        ListOptions { tags: vec!["web".into()], ..Default::default() },
+    ).unwrap();
+    cmd_list(
+        &crate::config::Config::new(),
+        ColorChoice::Never,
+        ListOptions { names_only: true, tags: vec!["missing".into()], ..Default::default() },
+    ).unwrap();
 }
 
 fn make_template(name: &str, location: &str) -> Template {
@@ -152,6 +351,19 @@ fn make_template(name: &str, location: &str) -> Template {
         git_ref: None,
         exclude: None,
         write_mode: None,
+        target_subdir: None,
+        tags: None,
+        default_branch: None,
+        created_at: None,
+        last_used: None,
+        subdir: None,
+        git_ref_kind: None,
+        gitignore_template: None,
+        commit: None,
+        next_steps: None,
+        max_file_size: None,
+        exclude_binaries: None,
+        checksum: None,
     }
 }
 
@@ -173,7 +385,17 @@ fn empty_change_options() -> ChangeOptions {
         post_init: None,
         git_ref: None,
         exclude: None,
+        add_exclude: Vec::new(),
+        remove_exclude: Vec::new(),
         write_mode: None,
+        target_subdir: None,
+        tags: None,
+        subdir: None,
+        commit: None,
+        next_steps: None,
+        gitignore_template: None,
+        max_file_size: None,
+        exclude_binaries: None,
     }
 }
 
@@ -181,18 +403,144 @@ fn empty_change_options() -> ChangeOptions {
 fn cmd_change_errors_when_template_not_found() {
     let config = IsolatedConfig::new();
     setup_registry(&config, vec![]);
-    let result = cmd_change("nonexistent".into(), ChangeOptions { name: Some("x".into()), ..empty_change_options() });
+    let result = cmd_change(&Config::new(), "nonexistent".into(), ChangeOptions { name: Some("x".into()), ..empty_change_options() });
     assert!(matches!(
         result.unwrap_err().downcast_ref::<TemplativeError>(),
         Some(TemplativeError::TemplateNotFound { .. })
     ));
 }
 
+#[test]
+fn cmd_change_keeps_a_variable_location_as_written() {
+    let config = IsolatedConfig::new();
+    let root = tempdir().unwrap();
+    setup_registry(&config, vec![make_template("a", root.path().to_str().unwrap())]);
+    unsafe { std::env::set_var("TEMPLATIVE_TEST_CHANGE_ROOT", root.path()); }
+
+    let changed = cmd_change(&Config::new(), "a".into(),
+        ChangeOptions { location: Some("${TEMPLATIVE_TEST_CHANGE_ROOT}".into()), ..empty_change_options() },
+    );
+    unsafe { std::env::remove_var("TEMPLATIVE_TEST_CHANGE_ROOT"); }
+    changed.unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.get("a").unwrap().location, "${TEMPLATIVE_TEST_CHANGE_ROOT}");
+}
+
+#[test]
+fn add_and_change_set_the_gitignore_template() {
+    let _config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    cmd_add(&Config::new(), template_dir.path().to_str().unwrap().to_string(),
+        AddOptions { gitignore_template: Some("~/gitignore".into()), ..add_options("g") },
+    ).unwrap();
+    assert_eq!(Registry::load(&Config::new()).unwrap().get("g").unwrap().gitignore_template.as_deref(), Some("~/gitignore"));
+
+    cmd_change(&Config::new(), "g".into(), ChangeOptions { gitignore_template: Some(None), ..empty_change_options() }).unwrap();
+    assert!(Registry::load(&Config::new()).unwrap().get("g").unwrap().gitignore_template.is_none());
+}
+
+#[test]
+fn add_and_change_set_the_file_filters() {
+    let _config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    cmd_add(&Config::new(), template_dir.path().to_str().unwrap().to_string(),
+        AddOptions { max_file_size: Some(1024), exclude_binaries: Some(true), ..add_options("f") },
+    ).unwrap();
+    let template = Registry::load(&Config::new()).unwrap().get("f").unwrap().clone();
+    assert_eq!((template.max_file_size, template.exclude_binaries), (Some(1024), Some(true)));
+
+    cmd_change(&Config::new(), "f".into(),
+        ChangeOptions { max_file_size: Some(None), exclude_binaries: Some(Some(false)), ..empty_change_options() },
+    ).unwrap();
+    let template = Registry::load(&Config::new()).unwrap().get("f").unwrap().clone();
+    assert_eq!((template.max_file_size, template.exclude_binaries), (None, Some(false)));
+}
+
+#[test]
+fn cmd_change_rejects_invalid_exclude_pattern() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("a", "/tmp/a")]);
+    let result = cmd_change(&Config::new(), "a".into(),
+        ChangeOptions { exclude: Some(Some(vec!["[oops".into()])), ..empty_change_options() },
+    );
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::InvalidExcludePattern { .. })
+    ));
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("a").unwrap().exclude.is_none());
+}
+
+#[test]
+fn target_subdir_outside_the_target_is_rejected() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    let location = template_dir.path().to_str().unwrap();
+    setup_registry(&config, vec![
+        make_template("a", location),
+        Template { target_subdir: Some("../escaped".into()), ..make_template("imported", location) },
+    ]);
+    for subdir in ["../up", "/abs", "", "."] {
+        let result = cmd_change(&Config::new(), "a".into(),
+            ChangeOptions { target_subdir: Some(Some(subdir.into())), ..empty_change_options() },
+        );
+        assert!(
+            matches!(result.unwrap_err().downcast_ref(), Some(TemplativeError::InvalidTargetSubdir { .. })),
+            "{:?}",
+            subdir
+        );
+    }
+    cmd_change(&Config::new(), "a".into(), ChangeOptions { target_subdir: Some(Some("docs/api".into())), ..empty_change_options() })
+        .unwrap();
+
+    // A registry entry that slipped past `change` (say, by import) is still refused by init.
+    let parent = tempdir().unwrap();
+    let target = parent.path().join("project");
+    let result = cmd_init(crate::config::Config::new(), "imported".into(), target, no_git_options());
+    assert!(matches!(result.unwrap_err().downcast_ref(), Some(TemplativeError::InvalidTargetSubdir { .. })));
+    assert!(!parent.path().join("escaped").exists());
+}
+
+#[test]
+fn cmd_change_add_and_remove_exclude_edit_the_list() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("a", "/tmp/a")]);
+    cmd_change(&Config::new(), "a".into(),
+        ChangeOptions { add_exclude: vec!["dist".into(), "*.log".into()], ..empty_change_options() },
+    )
+    .unwrap();
+    assert_eq!(
+        Registry::load(&Config::new()).unwrap().get("a").unwrap().exclude,
+        Some(vec!["dist".to_string(), "*.log".to_string()])
+    );
+
+    cmd_change(&Config::new(), "a".into(),
+        ChangeOptions {
+            add_exclude: vec!["dist".into(), "tmp".into()],
+            remove_exclude: vec!["*.log".into()],
+            ..empty_change_options()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        Registry::load(&Config::new()).unwrap().get("a").unwrap().exclude,
+        Some(vec!["dist".to_string(), "tmp".to_string()])
+    );
+
+    cmd_change(&Config::new(), "a".into(),
+        ChangeOptions { remove_exclude: vec!["dist".into(), "tmp".into()], ..empty_change_options() },
+    )
+    .unwrap();
+    assert!(Registry::load(&Config::new()).unwrap().get("a").unwrap().exclude.is_none());
+}
+
 #[test]
 fn cmd_change_errors_when_new_name_already_exists() {
     let config = IsolatedConfig::new();
     setup_registry(&config, vec![make_template("foo", "/tmp"), make_template("bar", "/tmp")]);
-    let result = cmd_change("foo".into(), ChangeOptions { name: Some("bar".into()), ..empty_change_options() });
+    let result = cmd_change(&Config::new(), "foo".into(), ChangeOptions { name: Some("bar".into()), ..empty_change_options() });
     assert!(matches!(
         result.unwrap_err().downcast_ref::<TemplativeError>(),
         Some(TemplativeError::TemplateExists { .. })
@@ -203,7 +551,7 @@ fn cmd_change_errors_when_new_name_already_exists() {
 fn cmd_change_updates_name_successfully() {
     let config = IsolatedConfig::new();
     setup_registry(&config, vec![make_template("foo", "/tmp")]);
-    let result = cmd_change("foo".into(), ChangeOptions { name: Some("bar".into()), ..empty_change_options() });
+    let result = cmd_change(&Config::new(), "foo".into(), ChangeOptions { name: Some("bar".into()), ..empty_change_options() });
     let registry = Registry::load_from_path(&config.path().join("templates.json")).unwrap();
     assert!(result.is_ok());
     assert!(registry.get("bar").is_some());
@@ -214,8 +562,8 @@ fn cmd_change_updates_name_successfully() {
 fn cmd_change_rejects_nonexistent_location() {
     let config = IsolatedConfig::new();
     setup_registry(&config, vec![make_template("foo", "/tmp")]);
-    let result = cmd_change("foo".into(), ChangeOptions {
-        location: Some(PathBuf::from("/this/path/does/not/exist/ever")),
+    let result = cmd_change(&Config::new(), "foo".into(), ChangeOptions {
+        location: Some("/this/path/does/not/exist/ever".into()),
         ..empty_change_options()
     });
     assert!(result.is_err());
@@ -227,8 +575,8 @@ fn cmd_change_updates_location_to_existing_path() {
     let config = IsolatedConfig::new();
     let new_location = tempdir().unwrap();
     setup_registry(&config, vec![make_template("foo", "/tmp")]);
-    let result = cmd_change("foo".into(), ChangeOptions {
-        location: Some(new_location.path().to_path_buf()),
+    let result = cmd_change(&Config::new(), "foo".into(), ChangeOptions {
+        location: Some(new_location.path().to_string_lossy().into_owned()),
         ..empty_change_options()
     });
     let registry = Registry::load_from_path(&config.path().join("templates.json")).unwrap();
@@ -236,3 +584,1214 @@ fn cmd_change_updates_location_to_existing_path() {
     let expected = new_location.path().canonicalize().unwrap().to_string_lossy().into_owned();
     assert_eq!(registry.get("foo").unwrap().location, expected);
 }
+
+#[test]
+fn registry_dir_env_moves_the_registry_but_not_the_config() {
+    let config = IsolatedConfig::new();
+    let registry_dir = tempdir().unwrap();
+    unsafe { std::env::set_var("TEMPLATIVE_REGISTRY_DIR", registry_dir.path()); }
+    let template_dir = tempdir().unwrap();
+    let added = cmd_add(&Config::new(), template_dir.path().to_str().unwrap().into(), add_options("elsewhere"));
+    let listed = Registry::load(&Config::new()).map(|registry| registry.get("elsewhere").is_some());
+    let loaded = crate::config::Config::load();
+    unsafe { std::env::remove_var("TEMPLATIVE_REGISTRY_DIR"); }
+
+    added.unwrap();
+    assert!(listed.unwrap());
+    assert!(registry_dir.path().join("templates.json").exists());
+    assert!(!config.path().join("templates.json").exists());
+    loaded.unwrap();
+    assert!(config.path().join("config.json").exists());
+    assert!(!registry_dir.path().join("config.json").exists());
+}
+
+#[test]
+fn cmd_change_switches_location_to_git_url() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("foo", "/tmp")]);
+    cmd_change(&Config::new(), "foo".into(), ChangeOptions {
+        location: Some("https://example.com/user/foo.git".into()),
+        ..empty_change_options()
+    })
+    .unwrap();
+    let registry = Registry::load_from_path(&config.path().join("templates.json")).unwrap();
+    assert_eq!(registry.get("foo").unwrap().location, "https://example.com/user/foo.git");
+}
+
+#[test]
+fn cmd_change_location_drops_or_reclassifies_what_add_recorded() {
+    let config = IsolatedConfig::new();
+    let (remote, _local) = setup_stale_clone();
+    let branch = String::from_utf8(
+        std::process::Command::new("git")
+            .args(["branch", "--show-current"])
+            .current_dir(remote.path())
+            .output()
+            .unwrap()
+            .stdout,
+    )
+    .unwrap();
+    let mut template = make_template("foo", "https://example.com/user/foo.git");
+    template.default_branch = Some("trunk".into());
+    template.git_ref = Some(branch.trim().to_string());
+    template.git_ref_kind = Some(crate::git::RefKind::Tag);
+    setup_registry(&config, vec![template]);
+
+    cmd_change(&Config::new(), "foo".into(), ChangeOptions {
+        location: Some(remote.path().to_string_lossy().into_owned()),
+        ..empty_change_options()
+    })
+    .unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    let changed = registry.get("foo").unwrap();
+    assert!(changed.default_branch.is_none());
+    assert_eq!(changed.git_ref_kind, Some(crate::git::RefKind::Branch));
+
+    cmd_change(&Config::new(), "foo".into(), ChangeOptions {
+        location: Some("https://example.com/user/other.git".into()),
+        ..empty_change_options()
+    })
+    .unwrap();
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("foo").unwrap().git_ref_kind.is_none());
+}
+
+#[test]
+fn cmd_export_then_import_merge_adds_templates() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("foo", "/tmp")]);
+    let export_dir = tempdir().unwrap();
+    let export_path = export_dir.path().join("export.json");
+    cmd_export(&Config::new(), Some(export_path.clone())).unwrap();
+
+    setup_registry(&config, vec![make_template("bar", "/tmp")]);
+    cmd_import(&Config::new(), export_path, false, false).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("foo").is_some());
+    assert!(registry.get("bar").is_some());
+}
+
+#[test]
+fn cmd_import_merge_skips_collisions() {
+    let config = IsolatedConfig::new();
+    let import_dir = tempdir().unwrap();
+    let import_path = import_dir.path().join("import.json");
+    let mut imported = Registry::new();
+    imported.templates.push(make_template("foo", "/imported"));
+    imported.templates.push(make_template("new", "/imported"));
+    imported.save_to_path(&import_path).unwrap();
+    setup_registry(&config, vec![make_template("foo", "/original")]);
+
+    cmd_import(&Config::new(), import_path, false, false).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.get("foo").unwrap().location, "/original");
+    assert!(registry.get("new").is_some());
+}
+
+#[test]
+fn cmd_import_leaves_an_old_export_file_as_it_was() {
+    let _config = IsolatedConfig::new();
+    let import_dir = tempdir().unwrap();
+    let import_path = import_dir.path().join("import.json");
+    let original = r#"{"version": 1, "templates": [{"name": "old", "location": "/imported"}]}"#;
+    std::fs::write(&import_path, original).unwrap();
+
+    cmd_import(&Config::new(), import_path.clone(), false, false).unwrap();
+
+    assert!(Registry::load(&Config::new()).unwrap().get("old").is_some());
+    assert_eq!(std::fs::read_to_string(&import_path).unwrap(), original);
+    assert_eq!(std::fs::read_dir(import_dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn cmd_import_strict_errors_on_collision_without_saving() {
+    let config = IsolatedConfig::new();
+    let import_dir = tempdir().unwrap();
+    let import_path = import_dir.path().join("import.json");
+    let mut imported = Registry::new();
+    imported.templates.push(make_template("foo", "/imported"));
+    imported.templates.push(make_template("new", "/imported"));
+    imported.save_to_path(&import_path).unwrap();
+    setup_registry(&config, vec![make_template("foo", "/original")]);
+
+    let result = cmd_import(&Config::new(), import_path, false, true);
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::TemplateExists { .. })
+    ));
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("new").is_none());
+}
+
+#[test]
+fn cmd_import_replace_discards_existing_templates() {
+    let config = IsolatedConfig::new();
+    let import_dir = tempdir().unwrap();
+    let import_path = import_dir.path().join("import.json");
+    let mut imported = Registry::new();
+    imported.templates.push(make_template("new", "/imported"));
+    imported.save_to_path(&import_path).unwrap();
+    setup_registry(&config, vec![make_template("old", "/original")]);
+
+    cmd_import(&Config::new(), import_path, true, false).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("old").is_none());
+    assert!(registry.get("new").is_some());
+}
+
+#[test]
+fn cmd_import_rejects_future_registry_version() {
+    let _config = IsolatedConfig::new();
+    let import_dir = tempdir().unwrap();
+    let import_path = import_dir.path().join("import.json");
+    std::fs::write(&import_path, r#"{"version": 99, "templates": []}"#).unwrap();
+
+    let result = cmd_import(&Config::new(), import_path, false, false);
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::UnsupportedRegistryVersion { .. })
+    ));
+}
+
+fn no_git_options() -> InitOptions {
+    InitOptions {
+        flags: InitFlags { git: Some(crate::config::GitMode::NoGit), ..Default::default() },
+        ..Default::default()
+    }
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@test.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@test.com")
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// Creates a remote repo and a local clone of it, then advances the remote so the
+/// clone is one commit behind. Returns (remote, local).
+fn setup_stale_clone() -> (tempfile::TempDir, tempfile::TempDir) {
+    let remote = tempdir().unwrap();
+    git(remote.path(), &["init"]);
+    std::fs::write(remote.path().join("file.txt"), "v1").unwrap();
+    git(remote.path(), &["add", "-A"]);
+    git(remote.path(), &["commit", "-m", "initial"]);
+    let local = tempdir().unwrap();
+    git(
+        local.path().parent().unwrap(),
+        &["clone", remote.path().to_str().unwrap(), local.path().to_str().unwrap()],
+    );
+    std::fs::write(remote.path().join("file.txt"), "v2").unwrap();
+    git(remote.path(), &["add", "-A"]);
+    git(remote.path(), &["commit", "-m", "update"]);
+    (remote, local)
+}
+
+#[test]
+fn cmd_init_preserve_rejects_template_without_git() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![make_template("plain", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    let options = InitOptions {
+        flags: InitFlags { git: Some(crate::config::GitMode::Preserve), ..Default::default() },
+        ..Default::default()
+    };
+
+    let result = cmd_init(crate::config::Config::new(), "plain".into(), target.path().to_path_buf(), options);
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::PreserveNeedsGitRepo { .. })
+    ));
+    assert!(!target.path().join("file.txt").exists());
+}
+
+#[test]
+fn cmd_init_checks_out_pinned_commit() {
+    let config = IsolatedConfig::new();
+    let (remote, _local) = setup_stale_clone();
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD~1"])
+        .current_dir(remote.path())
+        .output()
+        .unwrap();
+    let first_commit = String::from_utf8(output.stdout).unwrap().trim().to_string();
+    let mut template = make_template("pinned", remote.path().to_str().unwrap());
+    template.git_ref = Some("HEAD".into());
+    template.commit = Some(first_commit);
+    setup_registry(&config, vec![template]);
+    let target = tempdir().unwrap();
+
+    cmd_init(crate::config::Config::new(), "pinned".into(), target.path().to_path_buf(), no_git_options())
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(target.path().join("file.txt")).unwrap(), "v1");
+    assert_eq!(std::fs::read_to_string(remote.path().join("file.txt")).unwrap(), "v2");
+}
+
+#[test]
+fn cmd_add_uses_an_existing_cache_without_checking_the_remote() {
+    let _config = IsolatedConfig::new();
+    let (remote, _local) = setup_stale_clone();
+    // Nothing answers at this URL, so the add only works if ls-remote is skipped.
+    let url = "https://templative.invalid/cached.git";
+    let cache_path = crate::utilities::cache_path_for_url(&Config::new(), url).unwrap();
+    std::fs::create_dir_all(cache_path.parent().unwrap()).unwrap();
+    git(remote.path(), &["clone", remote.path().to_str().unwrap(), cache_path.to_str().unwrap()]);
+
+    cmd_add(&Config::new(), url.to_string(), add_options("cached")).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.get("cached").unwrap().location, url);
+}
+
+#[test]
+fn cmd_add_rejects_commit_that_is_a_branch() {
+    let _config = IsolatedConfig::new();
+    let (remote, _local) = setup_stale_clone();
+    let mut options = add_options("pinned");
+    let branch = String::from_utf8(
+        std::process::Command::new("git")
+            .args(["branch", "--show-current"])
+            .current_dir(remote.path())
+            .output()
+            .unwrap()
+            .stdout,
+    )
+    .unwrap();
+    options.commit = Some(branch.trim().to_string());
+
+    let result = cmd_add(&Config::new(), remote.path().to_str().unwrap().to_string(), options);
+    assert!(result.unwrap_err().to_string().contains("is not a commit"));
+}
+
+#[test]
+fn cmd_init_only_url_leaves_local_repo_untouched() {
+    let config = IsolatedConfig::new();
+    let (_remote, local) = setup_stale_clone();
+    setup_registry(&config, vec![make_template("local", local.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    let mut settings = crate::config::Config::new();
+    settings.update_on_init = crate::config::UpdateOnInit::OnlyUrl;
+
+    cmd_init(
+        settings,
+        "local".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read_to_string(local.path().join("file.txt")).unwrap(), "v1");
+    assert_eq!(std::fs::read_to_string(target.path().join("file.txt")).unwrap(), "v1");
+}
+
+#[test]
+fn cmd_init_always_pulls_local_repo() {
+    let config = IsolatedConfig::new();
+    let (_remote, local) = setup_stale_clone();
+    setup_registry(&config, vec![make_template("local", local.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    let mut settings = crate::config::Config::new();
+    settings.update_on_init = crate::config::UpdateOnInit::Always;
+
+    cmd_init(
+        settings,
+        "local".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read_to_string(local.path().join("file.txt")).unwrap(), "v2");
+    assert_eq!(std::fs::read_to_string(target.path().join("file.txt")).unwrap(), "v2");
+}
+
+#[test]
+fn cmd_init_overwrite_into_non_empty_target_runs_without_prompt_when_non_interactive() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "template").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    std::fs::write(target.path().join("file.txt"), "existing").unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "tmpl".into(),
+        target.path().to_path_buf(),
+        InitOptions {
+            flags: InitFlags {
+                git: Some(crate::config::GitMode::NoGit),
+                write_mode: Some(crate::config::WriteMode::Overwrite),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read_to_string(target.path().join("file.txt")).unwrap(), "template");
+}
+
+#[test]
+#[cfg(unix)]
+fn cmd_init_follows_symlinked_template_location() {
+    let config = IsolatedConfig::new();
+    let real = tempdir().unwrap();
+    std::fs::write(real.path().join("file.txt"), "content").unwrap();
+    let links = tempdir().unwrap();
+    let link = links.path().join("template-link");
+    std::os::unix::fs::symlink(real.path(), &link).unwrap();
+    setup_registry(&config, vec![make_template("linked", link.to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    // The source init copies from is the real directory, not the link.
+    let registry = Registry::load(&Config::new()).unwrap();
+    let source = init::fetch_source(&Config::new(), registry.get("linked").unwrap(), &InitFlags::default()).unwrap();
+    assert_eq!(source.path, real.path().canonicalize().unwrap());
+    drop(source);
+
+    cmd_init(
+        crate::config::Config::new(),
+        "linked".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+
+    let copied = target.path().join("file.txt");
+    assert!(!copied.is_symlink());
+    assert_eq!(std::fs::read_to_string(copied).unwrap(), "content");
+}
+
+#[test]
+#[cfg(unix)]
+fn cmd_init_broken_symlinked_template_reports_missing_path() {
+    let config = IsolatedConfig::new();
+    let links = tempdir().unwrap();
+    let link = links.path().join("template-link");
+    std::os::unix::fs::symlink(links.path().join("gone"), &link).unwrap();
+    setup_registry(&config, vec![make_template("broken", link.to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    let result = cmd_init(
+        crate::config::Config::new(),
+        "broken".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    );
+
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::TemplatePathMissing { .. })
+    ));
+}
+
+#[test]
+#[cfg(unix)]
+fn cmd_init_no_hooks_skips_pre_and_post_init() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![Template {
+        pre_init: Some("touch pre.txt".into()),
+        post_init: Some("touch post.txt".into()),
+        ..make_template("hooked", template_dir.path().to_str().unwrap())
+    }]);
+    let target = tempdir().unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "hooked".into(),
+        target.path().to_path_buf(),
+        InitOptions { no_hooks: true, ..no_git_options() },
+    )
+    .unwrap();
+
+    assert!(target.path().join("file.txt").exists());
+    assert!(!target.path().join("pre.txt").exists());
+    assert!(!target.path().join("post.txt").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn cmd_init_runs_pre_init_in_source_when_configured() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("setup.sh"), "touch prepared.txt\n").unwrap();
+    setup_registry(&config, vec![Template {
+        pre_init: Some("sh ./setup.sh".into()),
+        post_init: Some("touch post.txt".into()),
+        ..make_template("hooked", template_dir.path().to_str().unwrap())
+    }]);
+    let target = tempdir().unwrap();
+    let mut settings = crate::config::Config::new();
+    settings.pre_init_cwd = crate::config::HookCwd::Source;
+
+    cmd_init(settings, "hooked".into(), target.path().to_path_buf(), no_git_options()).unwrap();
+
+    // The hook ran against a scratch copy: what it wrote reaches the project, not the template.
+    assert!(target.path().join("prepared.txt").exists());
+    assert!(target.path().join("setup.sh").exists());
+    assert!(target.path().join("post.txt").exists());
+    let template_files: Vec<_> = std::fs::read_dir(template_dir.path()).unwrap().flatten().map(|entry| entry.file_name()).collect();
+    assert_eq!(template_files, vec![std::ffi::OsString::from("setup.sh")]);
+}
+
+#[test]
+#[cfg(unix)]
+fn cmd_init_only_hooks_runs_hooks_without_copying() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![Template {
+        pre_init: Some("touch pre.txt".into()),
+        post_init: Some("touch post.txt".into()),
+        ..make_template("hooked", template_dir.path().to_str().unwrap())
+    }]);
+    let target = tempdir().unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "hooked".into(),
+        target.path().to_path_buf(),
+        InitOptions { only_hooks: true, ..Default::default() },
+    )
+    .unwrap();
+
+    assert!(!target.path().join("file.txt").exists());
+    assert!(!target.path().join(".git").exists());
+    assert!(target.path().join("pre.txt").exists());
+    assert!(target.path().join("post.txt").exists());
+}
+
+#[test]
+fn cmd_init_only_hooks_errors_when_target_missing() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    let result = cmd_init(
+        crate::config::Config::new(),
+        "tmpl".into(),
+        target.path().join("missing"),
+        InitOptions { only_hooks: true, ..Default::default() },
+    );
+
+    assert!(result.is_err());
+    assert!(!target.path().join("missing").exists());
+}
+
+#[test]
+fn cmd_init_output_name_creates_a_named_directory_holding_target_subdir() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![Template {
+        target_subdir: Some("app".into()),
+        ..make_template("tmpl", template_dir.path().to_str().unwrap())
+    }]);
+    let target = tempdir().unwrap();
+    let options = |name: &str| InitOptions { output_name: Some(name.into()), ..no_git_options() };
+
+    cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().to_path_buf(), options("my-app")).unwrap();
+    assert!(target.path().join("my-app/app/file.txt").exists());
+
+    let result = cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().to_path_buf(), options("../up"));
+    assert!(result.unwrap_err().to_string().contains("plain directory name"));
+}
+
+#[test]
+fn cmd_init_copies_conditional_paths_only_when_their_var_is_true() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("main.rs"), "fn main() {}").unwrap();
+    std::fs::write(template_dir.path().join("ci.yml"), "on: push").unwrap();
+    std::fs::write(
+        template_dir.path().join(".templative.toml"),
+        "[[conditional]]\npath = \"ci.yml\"\nwhen = \"use_ci\"\n",
+    )
+    .unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    let init = |dir: &str, value: Option<&str>| {
+        let mut options = no_git_options();
+        options.flags.vars = value.map(|value| ("use_ci".to_string(), value.to_string())).into_iter().collect();
+        cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().join(dir), options).unwrap();
+    };
+
+    init("absent", None);
+    init("off", Some("false"));
+    init("on", Some("true"));
+
+    assert!(target.path().join("absent/main.rs").exists());
+    assert!(!target.path().join("absent/ci.yml").exists());
+    assert!(!target.path().join("off/ci.yml").exists());
+    assert!(target.path().join("on/ci.yml").exists());
+}
+
+#[test]
+fn cmd_init_new_dir_only_refuses_an_existing_empty_target() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    let options = || InitOptions { new_dir_only: true, ..no_git_options() };
+
+    let result = cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().to_path_buf(), options());
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::TargetExists { .. })
+    ));
+    assert!(!target.path().join("file.txt").exists());
+
+    let fresh = target.path().join("fresh");
+    cmd_init(crate::config::Config::new(), "tmpl".into(), fresh.clone(), options()).unwrap();
+    assert!(fresh.join("file.txt").exists());
+}
+
+#[test]
+fn cmd_init_records_last_used() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "tmpl".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    let last_used = registry.get("tmpl").unwrap().last_used.clone().unwrap();
+    assert!(last_used.ends_with('Z'));
+}
+
+#[test]
+fn init_template_returns_what_it_wrote_without_touching_the_registry() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    let template = make_template("tmpl", template_dir.path().to_str().unwrap());
+    setup_registry(&config, vec![template.clone()]);
+    let target = tempdir().unwrap();
+
+    let outcome =
+        init_template(&crate::config::Config::new(), &template, target.path(), &no_git_options()).unwrap();
+
+    assert_eq!(outcome.summary.created, vec![PathBuf::from("file.txt")]);
+    assert_eq!(outcome.target, target.path().canonicalize().unwrap());
+    assert!(!outcome.committed());
+    assert!(Registry::load(&Config::new()).unwrap().get("tmpl").unwrap().last_used.is_none());
+}
+
+#[test]
+fn init_template_asks_through_its_options_before_overwriting() {
+    let _config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "new").unwrap();
+    let template = make_template("tmpl", template_dir.path().to_str().unwrap());
+    let target = tempdir().unwrap();
+    std::fs::write(target.path().join("file.txt"), "old").unwrap();
+    let overwrite = |confirm_overwrite: Option<crate::ops::ConfirmOverwrite>| InitOptions {
+        flags: InitFlags { write_mode: Some(crate::config::WriteMode::Overwrite), ..no_git_options().flags },
+        confirm_overwrite,
+        ..no_git_options()
+    };
+
+    let declined = init_template(&crate::config::Config::new(), &template, target.path(), &overwrite(Some(|_| Ok(false))));
+    assert!(declined.is_err());
+    assert_eq!(std::fs::read_to_string(target.path().join("file.txt")).unwrap(), "old");
+
+    init_template(&crate::config::Config::new(), &template, target.path(), &overwrite(None)).unwrap();
+    assert_eq!(std::fs::read_to_string(target.path().join("file.txt")).unwrap(), "new");
+}
+
+#[test]
+fn cmd_init_remember_without_an_escalation_leaves_write_mode_unset() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    let mut options = no_git_options();
+    options.remember = true;
+    cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().to_path_buf(), options).unwrap();
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert!(registry.get("tmpl").unwrap().write_mode.is_none());
+}
+
+#[test]
+fn remember_write_mode_persists_the_escalated_mode() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("tmpl", "/tmp")]);
+
+    init::remember_write_mode(&Config::new(), "tmpl", &crate::config::WriteMode::SkipOverwrite);
+
+    let registry = Registry::load(&Config::new()).unwrap();
+    assert_eq!(registry.get("tmpl").unwrap().write_mode, Some(crate::config::WriteMode::SkipOverwrite));
+}
+
+#[test]
+fn stdin_snippet_is_stored_on_add_copied_by_init_and_deleted_on_remove() {
+    let config = IsolatedConfig::new();
+    let snippet = config.path().join("snippets/editorconfig");
+    let options = AddOptions { stdin: true, ..add_options("editorconfig") };
+    add::add_reading(&Config::new(), String::new(), options, &mut &b"root = true\n"[..]).unwrap();
+    assert_eq!(std::fs::read_to_string(&snippet).unwrap(), "root = true\n");
+
+    let target = tempdir().unwrap();
+    let options = InitOptions {
+        flags: InitFlags { git: Some(crate::config::GitMode::NoGit), ..Default::default() },
+        ..Default::default()
+    };
+    cmd_init(Config::new(), "editorconfig".into(), target.path().to_path_buf(), options).unwrap();
+    assert_eq!(std::fs::read_to_string(target.path().join("editorconfig")).unwrap(), "root = true\n");
+
+    cmd_remove(&Config::new(), vec!["editorconfig".into()], false, false, false).unwrap();
+    assert!(!snippet.exists());
+}
+
+#[test]
+fn failed_stdin_add_leaves_no_snippet_behind() {
+    let config = IsolatedConfig::new();
+    // A registry directory under a plain file can't be written, so the add fails at the save.
+    let blocker = config.path().join("blocker");
+    std::fs::write(&blocker, "").unwrap();
+    let mut settings = Config::new();
+    settings.flag_registry_dir = Some(blocker.join("registry"));
+    let options = AddOptions { stdin: true, ..add_options("snippet") };
+    assert!(add::add_reading(&settings, String::new(), options, &mut &b"x"[..]).is_err());
+    assert!(!config.path().join("snippets/snippet").exists());
+}
+
+#[test]
+fn cmd_init_no_commit_stages_without_committing() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "tmpl".into(),
+        target.path().to_path_buf(),
+        InitOptions {
+            flags: InitFlags {
+                git: Some(crate::config::GitMode::Fresh),
+                no_commit: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let head = std::process::Command::new("git")
+        .args(["rev-parse", "--verify", "HEAD"])
+        .current_dir(target.path())
+        .output()
+        .unwrap();
+    assert!(!head.status.success());
+    let staged = std::process::Command::new("git")
+        .args(["diff", "--cached", "--name-only"])
+        .current_dir(target.path())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&staged.stdout).trim(), "file.txt");
+}
+
+#[test]
+fn cmd_init_copies_single_file_template() {
+    let _config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    let file = template_dir.path().join("LICENSE");
+    std::fs::write(&file, "MIT").unwrap();
+    cmd_add(&Config::new(), file.to_str().unwrap().to_string(), add_options("license")).unwrap();
+    let target = tempdir().unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "license".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read_to_string(target.path().join("LICENSE")).unwrap(), "MIT");
+}
+
+#[test]
+fn cmd_init_copies_from_template_subdir() {
+    let config = IsolatedConfig::new();
+    let repo = tempdir().unwrap();
+    std::fs::create_dir_all(repo.path().join("templates/rust-lib/src")).unwrap();
+    std::fs::write(repo.path().join("templates/rust-lib/src/lib.rs"), "").unwrap();
+    std::fs::write(repo.path().join("README.md"), "monorepo").unwrap();
+    setup_registry(&config, vec![Template {
+        subdir: Some("templates/rust-lib".into()),
+        ..make_template("rust-lib", repo.path().to_str().unwrap())
+    }]);
+    let target = tempdir().unwrap();
+
+    cmd_init(
+        crate::config::Config::new(),
+        "rust-lib".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+
+    assert!(target.path().join("src/lib.rs").exists());
+    assert!(!target.path().join("README.md").exists());
+}
+
+#[test]
+fn cmd_init_errors_when_template_subdir_missing() {
+    let config = IsolatedConfig::new();
+    let repo = tempdir().unwrap();
+    setup_registry(&config, vec![Template {
+        subdir: Some("nope".into()),
+        ..make_template("broken", repo.path().to_str().unwrap())
+    }]);
+    let target = tempdir().unwrap();
+
+    let result = cmd_init(
+        crate::config::Config::new(),
+        "broken".into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    );
+
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::SubdirMissing { .. })
+    ));
+}
+
+#[test]
+fn cmd_update_fail_fast_reports_the_failure() {
+    let config = IsolatedConfig::new();
+    let plain = tempdir().unwrap();
+    // No upstream to pull from, so the update fails without touching the network.
+    let no_upstream = tempdir().unwrap();
+    git(no_upstream.path(), &["init"]);
+    std::fs::write(no_upstream.path().join("file.txt"), "v1").unwrap();
+    git(no_upstream.path(), &["add", "-A"]);
+    git(no_upstream.path(), &["commit", "-m", "initial"]);
+    setup_registry(
+        &config,
+        vec![
+            make_template("bad", no_upstream.path().to_str().unwrap()),
+            make_template("ok", plain.path().to_str().unwrap()),
+        ],
+    );
+    let err = cmd_update(&Config::new(), None, vec![], UpdateMode::Apply, true).unwrap_err().to_string();
+    assert!(err.contains("--fail-fast"));
+    assert!(err.contains("bad:"));
+
+    let err = cmd_update(&Config::new(), None, vec![], UpdateMode::Apply, false).unwrap_err().to_string();
+    assert!(err.contains("some templates failed to update"));
+}
+
+#[test]
+#[cfg(unix)]
+fn cmd_init_removes_created_target_when_post_init_fails() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "hello").unwrap();
+    let mut template = make_template("t", template_dir.path().to_str().unwrap());
+    template.post_init = Some("exit 1".into());
+    setup_registry(&config, vec![template]);
+    let parent = tempdir().unwrap();
+    let target = parent.path().join("new/project");
+
+    let result = cmd_init(crate::config::Config::new(), "t".into(), target.clone(), no_git_options());
+
+    assert!(result.is_err());
+    assert!(!parent.path().join("new").exists());
+}
+
+#[test]
+fn cmd_init_failed_commit_removes_only_copied_files() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::create_dir(template_dir.path().join("src")).unwrap();
+    std::fs::write(template_dir.path().join("src/lib.rs"), "").unwrap();
+    std::fs::write(template_dir.path().join("README.md"), "template").unwrap();
+    setup_registry(&config, vec![make_template("t", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    // A `.git` file that isn't a repository makes the template commit fail after the copy.
+    std::fs::write(target.path().join(".git"), "not a repo").unwrap();
+    std::fs::write(target.path().join("notes.txt"), "mine").unwrap();
+    let options = InitOptions {
+        flags: InitFlags {
+            git: Some(crate::config::GitMode::Fresh),
+            write_mode: Some(crate::config::WriteMode::Overwrite),
+            ..Default::default()
+        },
+        yes: true,
+        ..Default::default()
+    };
+
+    let result = cmd_init(crate::config::Config::new(), "t".into(), target.path().to_path_buf(), options);
+
+    assert!(result.is_err());
+    assert!(!target.path().join("README.md").exists());
+    assert!(!target.path().join("src").exists());
+    assert_eq!(std::fs::read_to_string(target.path().join("notes.txt")).unwrap(), "mine");
+    assert!(target.path().join(".git").is_file());
+}
+
+#[test]
+fn cmd_init_adds_gitignore_template_unless_the_template_has_one() {
+    let config = IsolatedConfig::new();
+    let plain = tempdir().unwrap();
+    std::fs::write(plain.path().join("main.rs"), "").unwrap();
+    let shipped = tempdir().unwrap();
+    std::fs::write(shipped.path().join(".gitignore"), "from-template\n").unwrap();
+    setup_registry(
+        &config,
+        vec![
+            make_template("plain", plain.path().to_str().unwrap()),
+            make_template("shipped", shipped.path().to_str().unwrap()),
+        ],
+    );
+    let mut settings = crate::config::Config::new();
+    settings.gitignore_template = Some("target/\n".into());
+
+    let first = tempdir().unwrap();
+    cmd_init(settings.clone(), "plain".into(), first.path().to_path_buf(), no_git_options()).unwrap();
+    assert_eq!(std::fs::read_to_string(first.path().join(".gitignore")).unwrap(), "target/\n");
+
+    let second = tempdir().unwrap();
+    cmd_init(settings.clone(), "shipped".into(), second.path().to_path_buf(), no_git_options()).unwrap();
+    assert_eq!(std::fs::read_to_string(second.path().join(".gitignore")).unwrap(), "from-template\n");
+
+    // Not even overwrite replaces the template's own file or one the target already had.
+    let overwrite = || InitOptions {
+        flags: InitFlags { write_mode: Some(crate::config::WriteMode::Overwrite), ..no_git_options().flags },
+        yes: true,
+        ..no_git_options()
+    };
+    let third = tempdir().unwrap();
+    cmd_init(settings.clone(), "shipped".into(), third.path().to_path_buf(), overwrite()).unwrap();
+    assert_eq!(std::fs::read_to_string(third.path().join(".gitignore")).unwrap(), "from-template\n");
+    let fourth = tempdir().unwrap();
+    std::fs::write(fourth.path().join(".gitignore"), "mine\n").unwrap();
+    cmd_init(settings, "plain".into(), fourth.path().to_path_buf(), overwrite()).unwrap();
+    assert_eq!(std::fs::read_to_string(fourth.path().join(".gitignore")).unwrap(), "mine\n");
+}
+
+#[test]
+fn cmd_change_sets_next_steps_and_init_renders_them() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![make_template("a", "/tmp/a")]);
+    cmd_change(&Config::new(), "a".into(),
+        ChangeOptions { next_steps: Some(Some("cd {{project}}\nnpm install".into())), ..empty_change_options() },
+    )
+    .unwrap();
+    let next_steps = Registry::load(&Config::new()).unwrap().get("a").unwrap().next_steps.clone().unwrap();
+    let rendered = init::render_next_steps(&next_steps, "a", std::path::Path::new("/work/my-app"));
+    assert_eq!(rendered, "cd my-app\nnpm install");
+}
+
+#[cfg(unix)]
+#[test]
+fn clear_git_hooks_removes_only_executable_hooks() {
+    use std::os::unix::fs::PermissionsExt;
+    let repo = tempdir().unwrap();
+    let hooks = repo.path().join(".git/hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    std::fs::write(hooks.join("pre-commit"), "#!/bin/sh\nexit 1\n").unwrap();
+    std::fs::set_permissions(hooks.join("pre-commit"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::write(hooks.join("pre-commit.sample"), "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(hooks.join("pre-commit.sample"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::write(hooks.join("README"), "notes").unwrap();
+
+    init::clear_git_hooks(repo.path()).unwrap();
+
+    assert!(!hooks.join("pre-commit").exists());
+    assert!(hooks.join("pre-commit.sample").exists());
+    assert!(hooks.join("README").exists());
+    // A repo without hooks is fine too.
+    init::clear_git_hooks(tempdir().unwrap().path()).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_init_preserve_clears_hooks_from_the_users_template_dir() {
+    use std::os::unix::fs::PermissionsExt;
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    git(template_dir.path(), &["init"]);
+    git(template_dir.path(), &["add", "-A"]);
+    git(template_dir.path(), &["commit", "-m", "initial"]);
+    setup_registry(&config, vec![make_template("repo", template_dir.path().to_str().unwrap())]);
+    // Stands in for the user's init.templateDir, which is where a clone's hooks come from.
+    let git_template = tempdir().unwrap();
+    let hook = git_template.path().join("hooks/post-commit");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(&hook, "#!/bin/sh\nexit 0\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    unsafe { std::env::set_var("GIT_TEMPLATE_DIR", git_template.path()); }
+
+    let mut init_config = Config::new();
+    init_config.clear_git_hooks = true;
+    let target = tempdir().unwrap();
+    let options = InitOptions {
+        flags: InitFlags { git: Some(crate::config::GitMode::Preserve), ..Default::default() },
+        ..Default::default()
+    };
+    let result = cmd_init(init_config, "repo".into(), target.path().to_path_buf(), options);
+    unsafe { std::env::remove_var("GIT_TEMPLATE_DIR"); }
+    result.unwrap();
+
+    assert!(target.path().join("file.txt").exists());
+    assert!(target.path().join(".git").is_dir());
+    assert!(!target.path().join(".git/hooks/post-commit").exists());
+}
+
+#[test]
+fn cmd_init_accepts_an_unregistered_path() {
+    let config = IsolatedConfig::new();
+    setup_registry(&config, vec![]);
+    let source = tempdir().unwrap();
+    std::fs::write(source.path().join("main.rs"), "fn main() {}").unwrap();
+    let target = tempdir().unwrap();
+    cmd_init(
+        crate::config::Config::new(),
+        source.path().to_str().unwrap().into(),
+        target.path().to_path_buf(),
+        no_git_options(),
+    )
+    .unwrap();
+    assert!(target.path().join("main.rs").exists());
+    assert!(Registry::load(&Config::new()).unwrap().templates.is_empty());
+}
+
+#[test]
+fn cmd_init_prefers_a_registered_name_over_a_same_named_path() {
+    let config = IsolatedConfig::new();
+    let registered = tempdir().unwrap();
+    std::fs::write(registered.path().join("registered.txt"), "").unwrap();
+    let shadow = tempdir().unwrap();
+    std::fs::write(shadow.path().join("path.txt"), "").unwrap();
+    let name = shadow.path().to_str().unwrap();
+    setup_registry(&config, vec![make_template(name, registered.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    cmd_init(crate::config::Config::new(), name.into(), target.path().to_path_buf(), no_git_options()).unwrap();
+    assert!(target.path().join("registered.txt").exists());
+    assert!(!target.path().join("path.txt").exists());
+}
+
+#[test]
+fn cmd_init_applies_the_template_manifest() {
+    let config = IsolatedConfig::new();
+    let source = tempdir().unwrap();
+    std::fs::write(source.path().join(".templative.toml"), "exclude = [\"fixtures\"]\ngit = \"no-git\"\n").unwrap();
+    std::fs::create_dir(source.path().join("fixtures")).unwrap();
+    std::fs::write(source.path().join("fixtures/big.bin"), "").unwrap();
+    std::fs::write(source.path().join("main.rs"), "").unwrap();
+    setup_registry(&config, vec![make_template("a", source.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    cmd_init(crate::config::Config::new(), "a".into(), target.path().to_path_buf(), InitOptions::default()).unwrap();
+    assert!(target.path().join("main.rs").exists());
+    assert!(!target.path().join("fixtures").exists());
+    assert!(!target.path().join(".templative.toml").exists());
+    assert!(!target.path().join(".git").exists());
+}
+
+#[test]
+fn cmd_init_amend_folds_files_into_head_commit() {
+    let config = IsolatedConfig::new();
+    let gitconfig = config.path().join("gitconfig");
+    std::fs::write(&gitconfig, "[user]\n\tname = Test\n\temail = test@test.com\n").unwrap();
+    unsafe { std::env::set_var("GIT_CONFIG_GLOBAL", &gitconfig); }
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let amend_options = || InitOptions {
+        flags: InitFlags {
+            git: Some(crate::config::GitMode::Fresh),
+            write_mode: Some(crate::config::WriteMode::Overwrite),
+            ..Default::default()
+        },
+        yes: true,
+        amend: true,
+        ..Default::default()
+    };
+    let commit_count = |dir: &std::path::Path| {
+        let output = std::process::Command::new("git")
+            .args(["rev-list", "--count", "HEAD"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let existing = tempdir().unwrap();
+    git(existing.path(), &["init"]);
+    std::fs::write(existing.path().join("README.md"), "mine").unwrap();
+    git(existing.path(), &["add", "-A"]);
+    git(existing.path(), &["commit", "-m", "initial"]);
+    let amended = cmd_init(crate::config::Config::new(), "tmpl".into(), existing.path().to_path_buf(), amend_options());
+
+    // An empty repo has nothing to amend, so a normal commit is made.
+    let empty = tempdir().unwrap();
+    git(empty.path(), &["init"]);
+    let fallback = cmd_init(crate::config::Config::new(), "tmpl".into(), empty.path().to_path_buf(), amend_options());
+    unsafe { std::env::remove_var("GIT_CONFIG_GLOBAL"); }
+
+    amended.unwrap();
+    assert_eq!(commit_count(existing.path()), "1");
+    let tracked = std::process::Command::new("git")
+        .args(["ls-files"])
+        .current_dir(existing.path())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&tracked.stdout), "README.md\nfile.txt\n");
+    fallback.unwrap();
+    assert_eq!(commit_count(empty.path()), "1");
+}
+
+#[test]
+fn cmd_init_assume_answers_ask_prompts() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("keep.txt"), "template").unwrap();
+    std::fs::write(template_dir.path().join("new.txt"), "template").unwrap();
+    setup_registry(&config, vec![make_template("asking", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+    std::fs::write(target.path().join("keep.txt"), "mine").unwrap();
+    let options = InitOptions {
+        flags: InitFlags { write_mode: Some(crate::config::WriteMode::Ask), ..no_git_options().flags },
+        assume: Some(AssumeAnswer::Skip),
+        ..no_git_options()
+    };
+
+    cmd_init(crate::config::Config::new(), "asking".into(), target.path().to_path_buf(), options).unwrap();
+    assert_eq!(std::fs::read_to_string(target.path().join("keep.txt")).unwrap(), "mine");
+    assert_eq!(std::fs::read_to_string(target.path().join("new.txt")).unwrap(), "template");
+}
+
+#[test]
+fn ask_without_a_terminal_needs_an_assumed_answer() {
+    use crate::config::WriteMode;
+    use init::non_interactive_write_mode;
+
+    assert!(matches!(
+        non_interactive_write_mode(&WriteMode::Ask, None, false),
+        Err(TemplativeError::AskNeedsTerminal)
+    ));
+    assert_eq!(non_interactive_write_mode(&WriteMode::Ask, None, true).unwrap(), WriteMode::Ask);
+    assert_eq!(
+        non_interactive_write_mode(&WriteMode::Ask, Some(AssumeAnswer::Overwrite), false).unwrap(),
+        WriteMode::Overwrite
+    );
+    assert_eq!(
+        non_interactive_write_mode(&WriteMode::Strict, Some(AssumeAnswer::Skip), false).unwrap(),
+        WriteMode::Strict
+    );
+}
+
+#[test]
+fn cmd_init_refuses_source_that_drifted_from_its_checksum() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "v1").unwrap();
+    setup_registry(&config, vec![make_template("pinned", template_dir.path().to_str().unwrap())]);
+
+    cmd_checksum(crate::config::Config::new(), "pinned".into(), true).unwrap();
+    let checksum = Registry::load(&Config::new()).unwrap().get("pinned").unwrap().checksum.clone().unwrap();
+    assert_eq!(checksum, crate::fs_copy::tree_checksum(template_dir.path(), false).unwrap());
+    let target = tempdir().unwrap();
+    cmd_init(crate::config::Config::new(), "pinned".into(), target.path().to_path_buf(), no_git_options()).unwrap();
+
+    std::fs::write(template_dir.path().join("file.txt"), "v2").unwrap();
+    let target = tempdir().unwrap();
+    let result = cmd_init(crate::config::Config::new(), "pinned".into(), target.path().to_path_buf(), no_git_options());
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::ChecksumMismatch { .. })
+    ));
+    assert!(!target.path().join("file.txt").exists());
+}
+
+#[test]
+fn cmd_cache_prune_removes_clones_and_worktrees_no_template_uses() {
+    let config = IsolatedConfig::new();
+    let url = "https://example.com/kept.git";
+    setup_registry(&config, vec![make_template("kept", url)]);
+    let kept = crate::utilities::cache_path_for_url(&Config::new(), url).unwrap();
+    let cache_dir = kept.parent().unwrap().to_path_buf();
+    let orphan = cache_dir.join("0123456789abcdef");
+    let stale_worktree = cache_dir.join("worktrees").join(kept.file_name().unwrap()).join("v0-00000000");
+    // A shared cache_dir can hold things templative didn't make.
+    let unrelated = cache_dir.join("pip");
+    let not_a_clone = cache_dir.join("fedcba9876543210");
+    for dir in [&kept.join(".git"), &orphan.join(".git"), &stale_worktree, &unrelated, &not_a_clone] {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    std::fs::write(stale_worktree.join(".git"), "gitdir: elsewhere").unwrap();
+
+    cmd_cache_prune(&Config::new(), true).unwrap();
+    assert!(orphan.exists());
+    assert!(stale_worktree.exists());
+
+    cmd_cache_prune(&Config::new(), false).unwrap();
+    assert!(kept.is_dir());
+    assert!(!orphan.exists());
+    assert!(!stale_worktree.exists());
+    assert!(unrelated.is_dir());
+    assert!(not_a_clone.is_dir());
+}
+
+#[test]
+fn cmd_init_runs_manifest_hooks_only_when_trusted() {
+    let config = IsolatedConfig::new();
+    let template_dir = tempdir().unwrap();
+    std::fs::write(template_dir.path().join("file.txt"), "content").unwrap();
+    std::fs::write(template_dir.path().join(".templative.toml"), "post_init = \"touch hooked.txt\"\n").unwrap();
+    setup_registry(&config, vec![make_template("tmpl", template_dir.path().to_str().unwrap())]);
+    let target = tempdir().unwrap();
+
+    let result = cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().join("untrusted"), no_git_options());
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<TemplativeError>(),
+        Some(TemplativeError::UntrustedHooks { .. })
+    ));
+    assert!(!target.path().join("untrusted").exists());
+
+    let declined = InitOptions { approve_hooks: Some(|_| Ok(false)), ..no_git_options() };
+    assert!(cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().join("declined"), declined).is_err());
+    assert!(!target.path().join("declined/hooked.txt").exists());
+
+    let trusted = InitOptions { trust_hooks: true, ..no_git_options() };
+    cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().join("trusted"), trusted).unwrap();
+    assert!(target.path().join("trusted/hooked.txt").exists());
+
+    let skipped = InitOptions { no_hooks: true, ..no_git_options() };
+    cmd_init(crate::config::Config::new(), "tmpl".into(), target.path().join("skipped"), skipped).unwrap();
+    assert!(!target.path().join("skipped/hooked.txt").exists());
+}
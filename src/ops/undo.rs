@@ -0,0 +1,24 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+use crate::registry::Registry;
+
+/// Restores the registry snapshot taken before the last change saved to it. The snapshot is
+/// used up, so a second `undo` has nothing to restore.
+pub fn cmd_undo(config: &Config) -> Result<()> {
+    // Held until the end so no other command saves between the check and the restore.
+    let _registry = Registry::load_for_update(config)?;
+    let path = Registry::registry_path(config)?;
+    let undo_path = Registry::undo_path(&path);
+    if !undo_path.exists() {
+        anyhow::bail!("nothing to undo");
+    }
+    Registry::read_from_path(&undo_path)
+        .with_context(|| format!("undo snapshot is unreadable: {}", undo_path.display()))?;
+    fs::rename(&undo_path, &path)
+        .with_context(|| format!("failed to restore registry: {}", path.display()))?;
+    println!("restored the registry from before the last change");
+    Ok(())
+}
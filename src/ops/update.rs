@@ -1,24 +1,69 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
 
 use anyhow::{Context, Result};
+use globset::Glob;
 
+use crate::config::Config;
 use crate::errors::TemplativeError;
 use crate::git::{self, RefKind};
 use crate::git_cache;
-use crate::registry::{Registry, Template};
+use crate::registry::{ListSort, Registry, Template};
 use crate::utilities;
 
-pub fn cmd_update(template_name: Option<String>, check: bool) -> Result<()> {
-    let registry = Registry::load()?;
+/// Whether `name` should be matched as a glob rather than looked up exactly.
+fn is_glob(name: &str) -> bool {
+    name.contains(['*', '?', '[', '{'])
+}
 
-    let templates: Vec<Template> = if let Some(ref name) = template_name {
-        let tmpl = registry
-            .get(name)
-            .ok_or_else(|| TemplativeError::TemplateNotFound { name: name.clone() })?;
-        vec![tmpl.clone()]
-    } else {
-        registry.templates_sorted().into_iter().cloned().collect()
+/// The templates `update` should touch: one by exact name, every match of a glob, or all of
+/// them, narrowed to those carrying every tag in `tags`. A name or tag that selects nothing
+/// is an error, so a typo isn't mistaken for "nothing to do".
+fn select_templates(registry: &Registry, template_name: Option<&str>, tags: &[String]) -> Result<Vec<Template>> {
+    let tagged = registry.templates_with_tags(tags, ListSort::Name);
+    let selected: Vec<Template> = match template_name {
+        Some(name) if is_glob(name) => {
+            let matcher = Glob::new(name)
+                .with_context(|| format!("invalid template pattern {:?}", name))?
+                .compile_matcher();
+            let matches: Vec<Template> =
+                tagged.into_iter().filter(|tmpl| matcher.is_match(&tmpl.name)).cloned().collect();
+            if matches.is_empty() {
+                return Err(TemplativeError::NoTemplatesMatch { pattern: name.to_string() }.into());
+            }
+            matches
+        }
+        Some(name) => {
+            let tmpl = registry
+                .get(name)
+                .ok_or_else(|| TemplativeError::TemplateNotFound { name: name.to_string() })?;
+            if !tags.iter().all(|tag| tmpl.has_tag(tag)) {
+                anyhow::bail!("template {} is not tagged {}", name, tags.join(", "));
+            }
+            vec![tmpl.clone()]
+        }
+        None => tagged.into_iter().cloned().collect(),
     };
+    if selected.is_empty() && !tags.is_empty() {
+        anyhow::bail!("no templates tagged {}", tags.join(", "));
+    }
+    Ok(selected)
+}
+
+/// With `fail_fast`, no further templates are started once one fails and that error is
+/// returned; otherwise every template is attempted and the failures are reported together.
+pub fn cmd_update(
+    config: &Config,
+    template_name: Option<String>,
+    tags: Vec<String>,
+    mode: UpdateMode,
+    fail_fast: bool,
+) -> Result<()> {
+    git::ensure_available()?;
+    let registry = Registry::load(config)?;
+    let templates = select_templates(&registry, template_name.as_deref(), &tags)?;
 
     if templates.is_empty() {
         println!("no templates registered");
@@ -26,13 +71,16 @@ pub fn cmd_update(template_name: Option<String>, check: bool) -> Result<()> {
     }
 
     let mut errors: Vec<String> = Vec::new();
-    for tmpl in &templates {
-        match update_template(tmpl, check) {
-            Ok(status) => println!("{}: {}", tmpl.name, status),
-            Err(err) => errors.push(format!("{}: {:#}", tmpl.name, err)),
+    for (name, result) in run_updates(config, &templates, mode, fail_fast) {
+        match result {
+            Ok(status) => println!("{}: {}", name, status),
+            Err(err) => errors.push(format!("{}: {:#}", name, err)),
         }
     }
 
+    if fail_fast && let Some(first) = errors.first() {
+        anyhow::bail!("update stopped after a failure (--fail-fast):\n{}", first);
+    }
     if !errors.is_empty() {
         anyhow::bail!("some templates failed to update:\n{}", errors.join("\n"));
     }
@@ -40,67 +88,187 @@ pub fn cmd_update(template_name: Option<String>, check: bool) -> Result<()> {
     Ok(())
 }
 
-pub(crate) fn update_template(tmpl: &Template, check: bool) -> Result<String> {
+const MAX_UPDATE_THREADS: usize = 8;
+
+/// Updates templates on a bounded pool of worker threads, returning results sorted by name
+/// so output is deterministic regardless of completion order.
+/// With `fail_fast`, workers stop picking up templates after the first failure; templates
+/// never started are left out of the results.
+fn run_updates(
+    config: &Config,
+    templates: &[Template],
+    mode: UpdateMode,
+    fail_fast: bool,
+) -> Vec<(String, Result<String>)> {
+    // Templates with the same location share a repo (URL templates share a cache dir), so work
+    // is serialized per location to stop two workers fetching or resetting the same repo.
+    let location_locks: HashMap<&str, Mutex<()>> = templates
+        .iter()
+        .map(|tmpl| (tmpl.location.as_str(), Mutex::new(())))
+        .collect();
+    let next_index = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let results = Mutex::new(Vec::with_capacity(templates.len()));
+    let workers = templates.len().min(MAX_UPDATE_THREADS);
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(tmpl) = templates.get(next_index.fetch_add(1, Ordering::Relaxed)) {
+                    if fail_fast && failed.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let _location_guard = location_locks[tmpl.location.as_str()]
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner);
+                    let result = update_template(config, tmpl, mode);
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    results
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push((tmpl.name.clone(), result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(PoisonError::into_inner);
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    results
+}
+
+/// Status line for `--check`, e.g. "update available (3 behind)". Falls back to the plain
+/// behind/up-to-date answer when there is no upstream to count against. With `log`, the
+/// incoming commits follow, one per line.
+fn check_status(repo: &Path, log: bool) -> String {
+    let status = behind_status(repo);
+    if !log || !status.starts_with("update available") {
+        return status;
+    }
+    let incoming = git::upstream_ref(repo)
+        .map(|upstream| git::log_between(repo, "HEAD", upstream))
+        .unwrap_or_else(|| Ok(Vec::new()));
+    match incoming {
+        Ok(commits) => commits.iter().fold(status, |status, commit| format!("{}\n  {}", status, commit)),
+        Err(err) => format!("{}\n  (couldn't list incoming commits: {:#})", status, err),
+    }
+}
+
+fn behind_status(repo: &Path) -> String {
+    match git::ahead_behind(repo) {
+        Ok((0, 0)) => "up to date".into(),
+        Ok((ahead, 0)) => format!("up to date ({} ahead)", ahead),
+        Ok((0, behind)) => format!("update available ({} behind)", behind),
+        Ok((ahead, behind)) => format!("update available ({} behind, {} ahead)", behind, ahead),
+        Err(_) if git::is_behind_remote(repo) => "update available".into(),
+        Err(_) => "up to date".into(),
+    }
+}
+
+/// How `update` treats each template. Every mode fetches; only `Apply` touches the checkout.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMode {
+    Apply,
+    /// Report how far behind each template is, and the commands `Apply` would run. With
+    /// `log`, list the incoming commits too.
+    Check { log: bool },
+    /// Print the commands `Apply` would run without running them.
+    DryRun,
+}
+
+/// The change `update` makes to a template's repo once it has been fetched.
+enum UpdateStep {
+    Checkout(String),
+    ResetHard(Option<String>),
+    PullFfOnly,
+}
+
+impl UpdateStep {
+    /// Picks the step for `tmpl`, or `None` when it is pinned to a tag or commit.
+    fn plan(tmpl: &Template, repo: &Path, is_url: bool) -> Option<Self> {
+        if tmpl.commit.is_some() {
+            return None;
+        }
+        match tmpl.git_ref {
+            Some(ref git_ref) => match git::classify_ref(repo, git_ref) {
+                RefKind::Branch => Some(Self::Checkout(git_ref.clone())),
+                RefKind::Tag | RefKind::Commit => None,
+            },
+            None if is_url => Some(Self::ResetHard(tmpl.default_branch.clone())),
+            None => Some(Self::PullFfOnly),
+        }
+    }
+
+    fn command(&self) -> String {
+        match self {
+            Self::Checkout(git_ref) => format!("checkout {}", git_ref),
+            Self::ResetHard(branch) => format!("reset --hard origin/{}", branch.as_deref().unwrap_or("HEAD")),
+            Self::PullFfOnly => "pull --ff-only".into(),
+        }
+    }
+
+    fn apply(&self, repo: &Path) -> Result<()> {
+        match self {
+            Self::Checkout(git_ref) => git::checkout_ref(repo, git_ref),
+            Self::ResetHard(branch) => git::reset_hard_origin(repo, branch.as_deref()).context("reset failed"),
+            Self::PullFfOnly => git::pull_ff_only(repo).context("pull failed"),
+        }
+    }
+}
+
+/// One "would run" line per git command, fetch included, so the mutation can be audited.
+fn describe_commands(repo: &Path, step: Option<&UpdateStep>) -> String {
+    std::iter::once("fetch origin".to_string())
+        .chain(step.map(UpdateStep::command))
+        .map(|command| format!("\n  would run: git -C {} {}", repo.display(), command))
+        .collect()
+}
+
+/// Runs or reports `step` against an already fetched repo, according to `mode`.
+fn finish_update(repo: &Path, step: Option<UpdateStep>, mode: UpdateMode) -> Result<String> {
+    match (mode, step) {
+        (UpdateMode::Check { log }, step) => {
+            Ok(format!("{}{}", check_status(repo, log), describe_commands(repo, step.as_ref())))
+        }
+        (_, None) => Ok("skipped (pinned to immutable ref)".into()),
+        (UpdateMode::DryRun, Some(step)) => Ok(format!("dry run{}", describe_commands(repo, Some(&step)))),
+        (UpdateMode::Apply, Some(step)) => {
+            step.apply(repo)?;
+            Ok("updated".into())
+        }
+    }
+}
+
+pub(crate) fn update_template(config: &Config, tmpl: &Template, mode: UpdateMode) -> Result<String> {
     if utilities::is_git_url(&tmpl.location) {
-        update_url_template(tmpl, check)
+        update_url_template(config, tmpl, mode)
     } else {
-        update_local_template(tmpl, check)
+        update_local_template(config, tmpl, mode)
     }
 }
 
-fn update_url_template(tmpl: &Template, check: bool) -> Result<String> {
-    let cache_path = utilities::cache_path_for_url(&tmpl.location)?;
+fn update_url_template(config: &Config, tmpl: &Template, mode: UpdateMode) -> Result<String> {
+    let cache_path = utilities::cache_path_for_url(config, &tmpl.location)?;
     if !cache_path.exists() {
-        git_cache::ensure_cached(&tmpl.location)?;
-    }
-    git::fetch_origin(&cache_path).context("fetch failed")?;
-    if check {
-        return Ok(if git::is_behind_remote(&cache_path) {
-            "update available".into()
-        } else {
-            "up to date".into()
-        });
-    }
-    if let Some(ref git_ref) = tmpl.git_ref {
-        match git::classify_ref(&cache_path, git_ref) {
-            RefKind::Branch => {
-                git::checkout_ref(&cache_path, git_ref)?;
-                Ok("updated".into())
-            }
-            RefKind::Tag | RefKind::Commit => Ok("skipped (pinned to immutable ref)".into()),
-        }
-    } else {
-        git::reset_hard_origin(&cache_path).context("reset failed")?;
-        Ok("updated".into())
+        git_cache::ensure_cached(config, &tmpl.location)?;
     }
+    git::fetch_origin(&cache_path, config.network()).context("fetch failed")?;
+    git_cache::record_fetch(&cache_path);
+    let step = UpdateStep::plan(tmpl, &cache_path, true);
+    finish_update(&cache_path, step, mode)
 }
 
-fn update_local_template(tmpl: &Template, check: bool) -> Result<String> {
-    let path = PathBuf::from(&tmpl.location);
+fn update_local_template(config: &Config, tmpl: &Template, mode: UpdateMode) -> Result<String> {
+    let path = utilities::expand_location(&tmpl.location)?;
     if !git::is_git_repo(&path) {
         return Ok("skipped (not a git repository)".into());
     }
     // Fetch is non-fatal: no remote configured is fine
-    let _ = git::fetch_origin(&path);
-    if check {
-        return Ok(if git::is_behind_remote(&path) {
-            "update available".into()
-        } else {
-            "up to date".into()
-        });
-    }
-    if let Some(ref git_ref) = tmpl.git_ref {
-        match git::classify_ref(&path, git_ref) {
-            RefKind::Branch => {
-                git::checkout_ref(&path, git_ref)?;
-                Ok("updated".into())
-            }
-            RefKind::Tag | RefKind::Commit => Ok("skipped (pinned to immutable ref)".into()),
-        }
-    } else {
-        git::pull_ff_only(&path).context("pull failed")?;
-        Ok("updated".into())
-    }
+    let _ = git::fetch_origin(&path, config.network());
+    let step = UpdateStep::plan(tmpl, &path, false);
+    finish_update(&path, step, mode)
 }
 
 #[cfg(test)]
@@ -119,9 +287,57 @@ mod tests {
             git_ref: None,
             exclude: None,
             write_mode: None,
+            target_subdir: None,
+            tags: None,
+            default_branch: None,
+            created_at: None,
+            last_used: None,
+            subdir: None,
+            git_ref_kind: None,
+            gitignore_template: None,
+            commit: None,
+            next_steps: None,
+            max_file_size: None,
+            exclude_binaries: None,
+            checksum: None,
         }
     }
 
+    fn registry_of(names: &[(&str, &[&str])]) -> Registry {
+        let mut registry = Registry::new();
+        for (name, tags) in names {
+            let mut tmpl = make_template(name, "/nonexistent");
+            tmpl.tags = Some(tags.iter().map(|tag| tag.to_string()).collect());
+            registry.templates.push(tmpl);
+        }
+        registry
+    }
+
+    fn selected_names(templates: Vec<Template>) -> Vec<String> {
+        templates.into_iter().map(|tmpl| tmpl.name).collect()
+    }
+
+    #[test]
+    fn select_templates_expands_globs_and_filters_by_tag() {
+        let registry = registry_of(&[("web-app", &["frontend"]), ("web-api", &[]), ("cli", &["frontend"])]);
+        let selected = select_templates(&registry, Some("web-*"), &[]).unwrap();
+        assert_eq!(selected_names(selected), vec!["web-api", "web-app"]);
+        let selected = select_templates(&registry, None, &["frontend".to_string()]).unwrap();
+        assert_eq!(selected_names(selected), vec!["cli", "web-app"]);
+        let selected = select_templates(&registry, Some("web-*"), &["frontend".to_string()]).unwrap();
+        assert_eq!(selected_names(selected), vec!["web-app"]);
+    }
+
+    #[test]
+    fn select_templates_errors_when_nothing_matches() {
+        let registry = registry_of(&[("cli", &[])]);
+        let err = select_templates(&registry, Some("web-*"), &[]).unwrap_err();
+        assert!(matches!(err.downcast_ref::<TemplativeError>(), Some(TemplativeError::NoTemplatesMatch { .. })));
+        assert!(select_templates(&registry, None, &["frontend".to_string()]).is_err());
+        let selected = select_templates(&registry, Some("cli"), &[]).unwrap();
+        assert_eq!(selected_names(selected), vec!["cli"]);
+    }
+
     fn git(dir: &std::path::Path, args: &[&str]) {
         let status = std::process::Command::new("git")
             .args(args)
@@ -146,7 +362,7 @@ mod tests {
     fn local_non_git_dir_is_skipped() {
         let dir = tempdir().unwrap();
         let tmpl = make_template("test", dir.path().to_str().unwrap());
-        let result = update_template(&tmpl, false).unwrap();
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::Apply).unwrap();
         assert_eq!(result, "skipped (not a git repository)");
     }
 
@@ -154,7 +370,7 @@ mod tests {
     fn local_non_git_dir_check_is_skipped() {
         let dir = tempdir().unwrap();
         let tmpl = make_template("test", dir.path().to_str().unwrap());
-        let result = update_template(&tmpl, true).unwrap();
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::Check { log: false }).unwrap();
         assert_eq!(result, "skipped (not a git repository)");
     }
 
@@ -163,8 +379,8 @@ mod tests {
         let dir = tempdir().unwrap();
         setup_repo(dir.path());
         let tmpl = make_template("test", dir.path().to_str().unwrap());
-        let result = update_template(&tmpl, true).unwrap();
-        assert_eq!(result, "up to date");
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::Check { log: false }).unwrap();
+        assert!(result.starts_with("up to date\n"));
     }
 
     #[test]
@@ -186,8 +402,73 @@ mod tests {
         git(remote.path(), &["commit", "-m", "update"]);
 
         let tmpl = make_template("test", local.path().to_str().unwrap());
-        let result = update_template(&tmpl, true).unwrap();
-        assert_eq!(result, "update available");
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::Check { log: false }).unwrap();
+        let repo = local.path().display();
+        assert_eq!(
+            result,
+            format!(
+                "update available (1 behind)\n  would run: git -C {repo} fetch origin\n  would run: git -C {repo} pull --ff-only"
+            )
+        );
+
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::Check { log: true }).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[0], "update available (1 behind)");
+        assert!(lines[1].starts_with("  ") && lines[1].ends_with(" update"), "{}", lines[1]);
+        assert!(lines[2].starts_with("  would run:"));
+    }
+
+    #[test]
+    fn dry_run_fetches_but_leaves_checkout_alone() {
+        let remote = tempdir().unwrap();
+        setup_repo(remote.path());
+        let local = tempdir().unwrap();
+        git(
+            local.path().parent().unwrap(),
+            &["clone", remote.path().to_str().unwrap(), local.path().to_str().unwrap()],
+        );
+        std::fs::write(remote.path().join("file.txt"), "v2").unwrap();
+        git(remote.path(), &["add", "-A"]);
+        git(remote.path(), &["commit", "-m", "update"]);
+
+        let tmpl = make_template("test", local.path().to_str().unwrap());
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::DryRun).unwrap();
+        assert!(result.starts_with("dry run\n"));
+        assert!(result.ends_with("pull --ff-only"));
+        assert_eq!(std::fs::read_to_string(local.path().join("file.txt")).unwrap(), "v1");
+        assert_eq!(git::ahead_behind(local.path()).unwrap(), (0, 1));
+    }
+
+    #[test]
+    fn run_updates_returns_every_result_sorted_by_name() {
+        let dirs: Vec<_> = (0..12).map(|_| tempdir().unwrap()).collect();
+        let templates: Vec<Template> = dirs
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, dir)| make_template(&format!("t{:02}", index), dir.path().to_str().unwrap()))
+            .collect();
+        let results = run_updates(&Config::new(), &templates, UpdateMode::Apply, false);
+        let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+        let expected: Vec<String> = (0..12).map(|index| format!("t{:02}", index)).collect();
+        assert_eq!(names, expected);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+    }
+
+    #[test]
+    fn run_updates_collects_failures_alongside_successes() {
+        let plain = tempdir().unwrap();
+        // A repo without an upstream can't `pull --ff-only`, which fails without any network.
+        let no_upstream = tempdir().unwrap();
+        setup_repo(no_upstream.path());
+        let templates = vec![
+            make_template("ok", plain.path().to_str().unwrap()),
+            make_template("bad", no_upstream.path().to_str().unwrap()),
+        ];
+        let results = run_updates(&Config::new(), &templates, UpdateMode::Apply, false);
+        assert_eq!(results[0].0, "bad");
+        assert!(results[0].1.is_err());
+        assert!(results[1].1.is_ok());
     }
 
     #[test]
@@ -197,7 +478,7 @@ mod tests {
         git(dir.path(), &["tag", "v1.0"]);
         let mut tmpl = make_template("test", dir.path().to_str().unwrap());
         tmpl.git_ref = Some("v1.0".into());
-        let result = update_template(&tmpl, false).unwrap();
+        let result = update_template(&Config::new(), &tmpl, UpdateMode::Apply).unwrap();
         assert_eq!(result, "skipped (pinned to immutable ref)");
     }
 }
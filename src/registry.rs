@@ -1,16 +1,35 @@
-use std::fs;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 
-use crate::config::{GitMode, WriteMode};
+use crate::config::{Config, GitMode, WriteMode};
 use crate::errors::TemplativeError;
+use crate::git::RefKind;
 
 const REGISTRY_VERSION: u32 = 2;
+/// Oldest registry version that `migrate` knows how to upgrade.
+const MIN_MIGRATABLE_VERSION: u32 = 1;
 const REGISTRY_FILENAME: &str = "templates.json";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Upgrades raw registry JSON one version at a time from `from` to `to`.
+/// Each step is the home for that version's field renames or reshaping.
+fn migrate(mut value: serde_json::Value, from: u32, to: u32) -> Result<serde_json::Value> {
+    for version in from..to {
+        value = match version {
+            // v1 -> v2: no structural changes; only the version number moves.
+            1 => value,
+            _ => anyhow::bail!("no migration from registry version {}", version),
+        };
+    }
+    value["version"] = serde_json::Value::from(to);
+    Ok(value)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Template {
     pub name: String,
     pub location: String,
@@ -28,12 +47,86 @@ pub struct Template {
     pub exclude: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub write_mode: Option<WriteMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_subdir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// The remote's default branch, recorded at `add` for URL templates so cache updates
+    /// don't depend on `origin/HEAD` being set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    /// RFC3339 time the template was added. Absent for templates added by older versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// RFC3339 time of the last successful `init` from this template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    /// Folder inside the template source (repo or directory) to use as the template root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+    /// What `git_ref` resolved to when it was set, so `list` can notice it changing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_ref_kind: Option<RefKind>,
+    /// Overrides the config's `gitignore_template` for this template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gitignore_template: Option<String>,
+    /// A commit to pin to. Checked out in place of `git_ref` (an init `--ref` still wins) and
+    /// never moved by `update`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Printed after a successful `init`, with `{{project}}`, `{{template}}` and `{{target}}`
+    /// filled in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_steps: Option<String>,
+    /// Overrides the config's `max_file_size` for this template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    /// Overrides the config's `exclude_binaries` for this template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_binaries: Option<bool>,
+    /// Expected `fs_copy::tree_checksum` of the template source; `init` refuses to copy a
+    /// source that no longer matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+/// Row order for `list`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ListSort {
+    #[default]
+    Name,
+    Location,
+    /// Most recently added first; templates without `created_at` last.
+    Added,
+}
+
+impl Template {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.as_ref().is_some_and(|tags| tags.iter().any(|own| own == tag))
+    }
+
+    /// True for a local template whose location no longer exists. URL templates are never
+    /// missing. Errors when the location uses a variable that isn't set here, which says
+    /// nothing about whether the template exists on a machine where it is.
+    pub fn is_location_missing(&self) -> Result<bool> {
+        if crate::utilities::is_git_url(&self.location) {
+            return Ok(false);
+        }
+        Ok(!crate::utilities::expand_location(&self.location)?.exists())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Registry {
     pub version: u32,
     pub templates: Vec<Template>,
+    /// Exclusive lock on `templates.json.lock`, held by registries from `load_for_update`
+    /// until they are dropped.
+    #[serde(skip)]
+    lock: Option<Arc<File>>,
+    /// The file this registry was loaded from, which `save` writes back to.
+    #[serde(skip)]
+    path: Option<PathBuf>,
 }
 
 impl Registry {
@@ -41,15 +134,17 @@ impl Registry {
         Self {
             version: REGISTRY_VERSION,
             templates: Vec::new(),
+            lock: None,
+            path: None,
         }
     }
 
-    pub fn registry_path() -> Result<PathBuf> {
-        Ok(crate::utilities::config_dir()?.join(REGISTRY_FILENAME))
+    pub fn registry_path(config: &Config) -> Result<PathBuf> {
+        Ok(crate::utilities::registry_dir(config)?.join(REGISTRY_FILENAME))
     }
 
-    pub fn load() -> Result<Self> {
-        let path = Self::registry_path()?;
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = Self::registry_path(config)?;
         let registry = Self::load_from_path(&path)?;
         if !path.exists() {
             registry.save_to_path(&path)?;
@@ -57,35 +152,166 @@ impl Registry {
         Ok(registry)
     }
 
+    /// Loads the registry for a read-modify-write. Blocks until no other templative process
+    /// holds the registry lock, and keeps it until the returned registry is dropped, so
+    /// concurrent updates can't overwrite each other.
+    pub fn load_for_update(config: &Config) -> Result<Self> {
+        let path = Self::registry_path(config)?;
+        let registry = Self::load_for_update_from_path(&path)?;
+        if !path.exists() {
+            registry.save_to_path(&path)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn load_for_update_from_path(path: &Path) -> Result<Self> {
+        let lock = Self::acquire_lock(path)?;
+        let mut registry = Self::load_from_path(path)?;
+        registry.lock = Some(Arc::new(lock));
+        Ok(registry)
+    }
+
+    /// `templates.json` -> `templates.json.lock`, locked exclusively.
+    fn acquire_lock(path: &Path) -> Result<File> {
+        let parent = path.parent().context("registry path has no parent")?;
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create config dir: {}", parent.display()))?;
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".lock");
+        let lock_path = path.with_file_name(file_name);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("failed to open registry lock: {}", lock_path.display()))?;
+        file.lock_exclusive()
+            .with_context(|| format!("failed to lock registry: {}", lock_path.display()))?;
+        Ok(file)
+    }
+
     pub fn load_from_path(path: &Path) -> Result<Self> {
         if !path.exists() {
-            return Ok(Self::new());
+            return Ok(Self { path: Some(path.to_path_buf()), ..Self::new() });
         }
+        let (mut registry, found) = Self::parse_file(path)?;
+        registry.path = Some(path.to_path_buf());
+        if found == REGISTRY_VERSION {
+            return Ok(registry);
+        }
+        let backup_path = Self::backup_path(path);
+        fs::copy(path, &backup_path)
+            .with_context(|| format!("failed to back up registry: {}", backup_path.display()))?;
+        registry.save_to_path(path)?;
+        eprintln!(
+            "note: upgraded registry from version {} to {} (backup at {})",
+            found,
+            REGISTRY_VERSION,
+            backup_path.display()
+        );
+        Ok(registry)
+    }
+
+    /// Reads a registry file, such as an export, without touching it: an older version is
+    /// migrated in memory only.
+    pub fn read_from_path(path: &Path) -> Result<Self> {
+        Self::parse_file(path).map(|(registry, _)| registry)
+    }
+
+    /// The registry at `path`, migrated to the current version, and the version it was in.
+    fn parse_file(path: &Path) -> Result<(Self, u32)> {
         let contents = fs::read_to_string(path)
             .with_context(|| format!("failed to read registry: {}", path.display()))?;
-        let registry: Self = serde_json::from_str(&contents)
+        let value: serde_json::Value = serde_json::from_str(&contents)
             .with_context(|| format!("failed to parse registry: {}", path.display()))?;
-        if registry.version != REGISTRY_VERSION {
-            return Err(TemplativeError::UnsupportedRegistryVersion {
-                found: registry.version,
-                expected: REGISTRY_VERSION,
-                path: path.display().to_string(),
+        let raw_version = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .with_context(|| format!("registry has no version: {}", path.display()))?;
+        let found = match u32::try_from(raw_version) {
+            Ok(found) if (MIN_MIGRATABLE_VERSION..=REGISTRY_VERSION).contains(&found) => found,
+            _ => {
+                return Err(TemplativeError::UnsupportedRegistryVersion {
+                    found: raw_version,
+                    expected: REGISTRY_VERSION,
+                    path: path.display().to_string(),
+                }
+                .into());
             }
-            .into());
-        }
-        Ok(registry)
+        };
+        let migrated = if found == REGISTRY_VERSION { value } else { migrate(value, found, REGISTRY_VERSION)? };
+        let registry = serde_json::from_value(migrated)
+            .with_context(|| format!("failed to parse registry: {}", path.display()))?;
+        Ok((registry, found))
     }
 
+    /// `templates.json` -> `templates.json.bak-<unix seconds>`
+    fn backup_path(path: &Path) -> PathBuf {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".bak-{}", timestamp));
+        path.with_file_name(file_name)
+    }
+
+    /// Saves a change the user made, first copying the registry as it was over the undo
+    /// snapshot so `templative undo` can put it back. Only one level is kept. A save that
+    /// changes nothing writes nothing, so it doesn't use up that level; a failed snapshot just
+    /// warns, and the change still goes ahead.
     pub fn save(&self) -> Result<()> {
-        self.save_to_path(&Self::registry_path()?)
+        let path = self.loaded_path()?;
+        let contents = self.to_json()?;
+        match fs::read_to_string(path) {
+            Ok(previous) if previous == contents => return Ok(()),
+            Ok(_) => Self::save_undo_snapshot(path),
+            Err(_) => {}
+        }
+        Self::write_contents(path, &contents)
+    }
+
+    /// Saves bookkeeping such as `last_used`, leaving the undo snapshot alone so `undo` still
+    /// reverts the user's last change (and drops the bookkeeping made since).
+    pub fn save_keeping_undo(&self) -> Result<()> {
+        self.save_to_path(self.loaded_path()?)
+    }
+
+    fn loaded_path(&self) -> Result<&Path> {
+        self.path.as_deref().context("registry was not loaded from a file, so there's nowhere to save it")
+    }
+
+    /// `templates.json` -> `templates.json.undo`
+    pub fn undo_path(path: &Path) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".undo");
+        path.with_file_name(file_name)
+    }
+
+    /// Copies the registry file at `path` over its undo snapshot, warning on failure.
+    fn save_undo_snapshot(path: &Path) {
+        let undo_path = Self::undo_path(path);
+        if let Err(err) = fs::copy(path, &undo_path) {
+            crate::utilities::warn(format_args!(
+                "couldn't save undo snapshot {}: {}",
+                undo_path.display(),
+                err
+            ));
+        }
+    }
+
+    fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("failed to serialize registry")
     }
 
     pub fn save_to_path(&self, path: &Path) -> Result<()> {
+        Self::write_contents(path, &self.to_json()?)
+    }
+
+    fn write_contents(path: &Path, contents: &str) -> Result<()> {
         let parent = path.parent().context("registry path has no parent")?;
         fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create config dir: {}", parent.display()))?;
-        let contents =
-            serde_json::to_string_pretty(self).context("failed to serialize registry")?;
+            .with_context(|| format!("failed to create registry dir: {}", parent.display()))?;
         let temp_path = path.with_extension("tmp");
         fs::write(&temp_path, contents)
             .with_context(|| format!("failed to write registry: {}", temp_path.display()))?;
@@ -105,6 +331,14 @@ impl Registry {
         Ok(())
     }
 
+    /// Adds the template, replacing any existing entry with the same name in place.
+    pub fn add_or_replace(&mut self, template: Template) {
+        match self.get_mut(&template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+
     pub fn remove(&mut self, name: &str) -> Result<()> {
         let pos = self
             .templates
@@ -125,9 +359,35 @@ impl Registry {
         self.templates.iter_mut().find(|tmpl| tmpl.name == name)
     }
 
+    /// Sorted templates carrying every tag in `tags`. An empty slice matches everything.
+    pub fn templates_with_tags(&self, tags: &[String], sort: ListSort) -> Vec<&Template> {
+        self.templates_sorted_by(sort)
+            .into_iter()
+            .filter(|tmpl| tags.iter().all(|tag| tmpl.has_tag(tag)))
+            .collect()
+    }
+
     pub fn templates_sorted(&self) -> Vec<&Template> {
+        self.templates_sorted_by(ListSort::Name)
+    }
+
+    /// Templates ordered by `sort`, with ties broken by name.
+    pub fn templates_sorted_by(&self, sort: ListSort) -> Vec<&Template> {
         let mut sorted: Vec<&Template> = self.templates.iter().collect();
-        sorted.sort_by(|a, b| a.name.cmp(&b.name));
+        sorted.sort_by(|a, b| {
+            let primary = match sort {
+                ListSort::Name => std::cmp::Ordering::Equal,
+                ListSort::Location => a.location.cmp(&b.location),
+                // RFC3339 UTC strings order chronologically; `None` sorts after every `Some`.
+                ListSort::Added => match (&a.created_at, &b.created_at) {
+                    (Some(a_time), Some(b_time)) => b_time.cmp(a_time),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                },
+            };
+            primary.then_with(|| a.name.cmp(&b.name))
+        });
         sorted
     }
 }
@@ -153,9 +413,44 @@ mod tests {
             git_ref: None,
             exclude: None,
             write_mode: None,
+            target_subdir: None,
+            tags: None,
+            default_branch: None,
+            created_at: None,
+            last_used: None,
+            subdir: None,
+            git_ref_kind: None,
+            gitignore_template: None,
+            commit: None,
+            next_steps: None,
+            max_file_size: None,
+            exclude_binaries: None,
+            checksum: None,
         }
     }
 
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("templates.json");
+        std::thread::scope(|scope| {
+            for name in ["first", "second"] {
+                let path = &path;
+                scope.spawn(move || {
+                    let mut registry = Registry::load_for_update_from_path(path).unwrap();
+                    // Widen the window between load and save so an unlocked registry would
+                    // lose one of the writes.
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    registry.add(Template { name: name.into(), ..make_template(None) }).unwrap();
+                    registry.save_to_path(path).unwrap();
+                });
+            }
+        });
+        let registry = Registry::load_from_path(&path).unwrap();
+        assert!(registry.get("first").is_some());
+        assert!(registry.get("second").is_some());
+    }
+
     #[test]
     fn load_missing_file_returns_empty_registry() {
         let temp = tempfile::tempdir().unwrap();
@@ -189,6 +484,67 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn rejects_version_below_migratable() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("templates.json");
+        std::fs::write(&path, r#"{"version": 0, "templates": []}"#).unwrap();
+        let result = Registry::load_from_path(&path);
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::UnsupportedRegistryVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_version_too_large_for_u32() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("templates.json");
+        // 2^32 + 1 would wrap to a migratable version 1.
+        std::fs::write(&path, r#"{"version": 4294967297, "templates": []}"#).unwrap();
+        let result = Registry::read_from_path(&path);
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::UnsupportedRegistryVersion { found: 4294967297, .. })
+        ));
+    }
+
+    #[test]
+    fn v1_registry_is_upgraded_and_backed_up() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("templates.json");
+        let original = r#"{"version": 1, "templates": [{"name": "foo", "location": "/path"}]}"#;
+        std::fs::write(&path, original).unwrap();
+
+        let registry = Registry::load_from_path(&path).unwrap();
+        assert_eq!(registry.version, REGISTRY_VERSION);
+        assert_eq!(registry.templates[0].name, "foo");
+
+        let on_disk = Registry::load_from_path(&path).unwrap();
+        assert_eq!(on_disk.version, REGISTRY_VERSION);
+
+        let backups: Vec<_> = std::fs::read_dir(temp.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("templates.json.bak-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read_to_string(backups[0].path()).unwrap(), original);
+    }
+
+    #[test]
+    fn read_from_path_migrates_without_touching_the_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("export.json");
+        let original = r#"{"version": 1, "templates": [{"name": "foo", "location": "/path"}]}"#;
+        std::fs::write(&path, original).unwrap();
+
+        let registry = Registry::read_from_path(&path).unwrap();
+        assert_eq!(registry.version, REGISTRY_VERSION);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
     #[test]
     fn old_registry_without_git_field_deserializes_cleanly() {
         let temp = tempfile::tempdir().unwrap();
@@ -232,6 +588,98 @@ mod tests {
         assert_eq!(loaded.templates[0].git, Some(GitMode::NoGit));
     }
 
+    #[test]
+    fn add_or_replace_replaces_existing_entry() {
+        let mut registry = Registry::new();
+        registry.add(make_template(Some(GitMode::NoGit))).unwrap();
+        registry.add_or_replace(Template {
+            location: "/new/path".into(),
+            ..make_template(None)
+        });
+        assert_eq!(registry.templates.len(), 1);
+        assert_eq!(registry.templates[0].location, "/new/path");
+        assert!(registry.templates[0].git.is_none());
+    }
+
+    #[test]
+    fn add_errors_on_existing_name() {
+        let mut registry = Registry::new();
+        registry.add(make_template(None)).unwrap();
+        let result = registry.add(make_template(None));
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TemplativeError>(),
+            Some(TemplativeError::TemplateExists { .. })
+        ));
+    }
+
+    #[test]
+    fn templates_sorted_by_added_puts_newest_first_and_untimed_last() {
+        let mut registry = Registry::new();
+        registry.templates.push(Template { name: "old".into(), created_at: Some("2023-01-01T00:00:00Z".into()), ..make_template(None) });
+        registry.templates.push(Template { name: "legacy".into(), ..make_template(None) });
+        registry.templates.push(Template { name: "new".into(), created_at: Some("2024-01-01T00:00:00Z".into()), ..make_template(None) });
+        let names: Vec<&str> = registry.templates_sorted_by(ListSort::Added).iter().map(|tmpl| tmpl.name.as_str()).collect();
+        assert_eq!(names, vec!["new", "old", "legacy"]);
+    }
+
+    #[test]
+    fn templates_sorted_by_location() {
+        let mut registry = Registry::new();
+        registry.templates.push(Template { name: "a".into(), location: "/z".into(), ..make_template(None) });
+        registry.templates.push(Template { name: "b".into(), location: "/a".into(), ..make_template(None) });
+        let names: Vec<&str> = registry.templates_sorted_by(ListSort::Location).iter().map(|tmpl| tmpl.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn templates_with_tags_requires_all_tags() {
+        let mut registry = Registry::new();
+        registry.templates.push(Template {
+            name: "both".into(),
+            tags: Some(vec!["web".into(), "rust".into()]),
+            ..make_template(None)
+        });
+        registry.templates.push(Template {
+            name: "web-only".into(),
+            tags: Some(vec!["web".into()]),
+            ..make_template(None)
+        });
+        registry.templates.push(Template { name: "none".into(), ..make_template(None) });
+
+        let names = |tags: &[&str]| -> Vec<String> {
+            let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+            registry.templates_with_tags(&tags, ListSort::Name).iter().map(|tmpl| tmpl.name.clone()).collect()
+        };
+        assert_eq!(names(&["web"]), vec!["both", "web-only"]);
+        assert_eq!(names(&["web", "rust"]), vec!["both"]);
+        assert_eq!(names(&[]).len(), 3);
+    }
+
+    #[test]
+    fn is_location_missing_checks_local_paths_only() {
+        let temp = tempfile::tempdir().unwrap();
+        let present = Template {
+            location: temp.path().to_string_lossy().into_owned(),
+            ..make_template(None)
+        };
+        let absent = Template {
+            location: "/this/path/does/not/exist/ever".into(),
+            ..make_template(None)
+        };
+        let url = Template {
+            location: "https://github.com/user/repo".into(),
+            ..make_template(None)
+        };
+        assert!(!present.is_location_missing().unwrap());
+        assert!(absent.is_location_missing().unwrap());
+        assert!(!url.is_location_missing().unwrap());
+        let unset = Template {
+            location: "$TEMPLATIVE_TEST_SURELY_UNSET/site".into(),
+            ..make_template(None)
+        };
+        assert!(unset.is_location_missing().is_err());
+    }
+
     #[test]
     fn skips_none_fields_in_json() {
         let temp = tempfile::tempdir().unwrap();
@@ -1,44 +1,311 @@
-use crate::config::{Config, GitMode, WriteMode};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use anyhow::Context;
+
+use crate::config::{
+    Config, GitMode, HookCwd, PathTokenStyle, PreserveStrategy, SymlinkFallback, SymlinkMode, UpdateOnInit, WriteMode,
+};
+use crate::errors::TemplativeError;
+use crate::manifest::{Manifest, MANIFEST_FILE};
+use crate::fs_copy::{CopyOptions, FileFilter};
 use crate::registry::Template;
 
+/// CLI overrides for a single `init` invocation. Unset fields fall through to the template
+/// and then the config.
+#[derive(Debug, Default)]
+pub struct InitFlags {
+    pub git: Option<GitMode>,
+    pub write_mode: Option<WriteMode>,
+    pub git_ref: Option<String>,
+    /// Extra exclude patterns appended to the merged config + template list.
+    pub exclude: Vec<String>,
+    /// Use only `exclude`, ignoring config and template patterns.
+    pub exclude_only: bool,
+    /// Copy symlink targets instead of recreating links (only turns the config default on).
+    pub follow_symlinks: bool,
+    pub commit_message: Option<String>,
+    /// Stage the Fresh copy without committing (only turns the config default on).
+    pub no_commit: bool,
+    /// `--var NAME[=VALUE]` values: filled into path tokens, and deciding the manifest's
+    /// conditional paths.
+    pub vars: BTreeMap<String, String>,
+}
+
+/// Whether a `--var` value switches a conditional path on. An unset variable is false.
+pub fn is_truthy(value: Option<&String>) -> bool {
+    value.is_some_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+}
+
 /// Merged settings for a single `init` invocation.
-/// Resolution order: CLI flag > template field > config default.
+/// Resolution order: CLI flag > template field > template manifest > config default.
+/// (The manifest only covers `git`, the hooks, extra `exclude` patterns, `renames`,
+/// `path_token_style` and conditional paths.)
+/// (`git_ref` has no config default: flag > template `commit` > template `git_ref`.)
 #[derive(Debug)]
 pub struct ResolvedOptions {
     pub git: GitMode,
     pub pre_init: Option<String>,
     pub post_init: Option<String>,
+    /// The hooks above that come from the template's manifest rather than the user's own
+    /// settings. `init` only runs them once they're trusted; see `InitOptions::trust_hooks`.
+    pub manifest_hooks: Vec<String>,
     pub git_ref: Option<String>,
+    /// The template's pinned commit, dropped when a `--ref` flag is given; see `checkout_ref()`.
+    pub commit: Option<String>,
     pub exclude: Vec<String>,
     pub write_mode: WriteMode,
+    pub target_subdir: Option<String>,
+    pub subdir: Option<String>,
+    pub update_on_init: UpdateOnInit,
+    pub cache_ttl_secs: Option<u64>,
+    pub no_cache: bool,
+    pub symlink_mode: SymlinkMode,
+    /// Manifest, then config.
+    pub path_token_style: PathTokenStyle,
+    pub symlink_fallback: SymlinkFallback,
+    pub hook_timeout_secs: Option<u64>,
+    pub pre_init_cwd: HookCwd,
+    pub follow_symlinks: bool,
+    pub preserve_strategy: PreserveStrategy,
+    pub clear_git_hooks: bool,
+    /// Unrendered; see `commit_message()`.
+    pub commit_message_template: String,
+    pub no_commit: bool,
+    /// Template, then config `gitignore_template`; see `gitignore_contents()`.
+    pub gitignore_template: Option<String>,
+    /// Template, then config `max_file_size` and `exclude_binaries`.
+    pub file_filter: FileFilter,
+    pub keep_empty_dirs: bool,
+    /// From the manifest only; see `CopyOptions::renames`.
+    pub renames: BTreeMap<String, String>,
+    /// From `--var` only.
+    pub vars: BTreeMap<String, String>,
 }
 
+/// Used when neither `--commit-message` nor `commit_message` is set.
+const DEFAULT_COMMIT_MESSAGE: &str = "adding template {name}";
+
 impl ResolvedOptions {
     pub fn build(
         config: &Config,
         template: &Template,
-        git_flag: Option<GitMode>,
-        write_mode_flag: Option<WriteMode>,
-    ) -> Self {
-        let mut exclude = config.exclude.clone();
-        if let Some(ref template_exclude) = template.exclude {
-            for pattern in template_exclude {
-                if !exclude.contains(pattern) {
-                    exclude.push(pattern.clone());
-                }
-            }
-        }
-        Self {
-            git: git_flag.or_else(|| template.git.clone()).unwrap_or_else(|| config.git.clone()),
-            pre_init: template.pre_init.clone(),
-            post_init: template.post_init.clone(),
-            git_ref: template.git_ref.clone(),
+        manifest: Option<&Manifest>,
+        flags: &InitFlags,
+    ) -> Result<Self, TemplativeError> {
+        let manifest_exclude = manifest.and_then(|manifest| manifest.exclude.as_deref()).unwrap_or_default();
+        let (config_exclude, manifest_exclude, template_exclude): (&[String], &[String], &[String]) =
+            if flags.exclude_only {
+                (&[], &[], &[])
+            } else {
+                (&config.exclude, manifest_exclude, template.exclude.as_deref().unwrap_or_default())
+            };
+        // The manifest configures the template; it isn't part of it.
+        let manifest_file = manifest.map(|_| format!("/{}", MANIFEST_FILE));
+        // Conditional paths whose variable is off are left out like anchored excludes, even
+        // under `--exclude-only`.
+        let conditional_exclude: Vec<String> = manifest
+            .and_then(|manifest| manifest.conditionals.as_deref())
+            .unwrap_or_default()
+            .iter()
+            .filter(|conditional| !is_truthy(flags.vars.get(conditional.when.trim())))
+            .map(|conditional| {
+                let path = conditional.path.trim().trim_start_matches("./").trim_matches('/');
+                format!("/{}", globset::escape(path))
+            })
+            .collect();
+        let exclude = normalize_excludes(
+            config_exclude
+                .iter()
+                .chain(manifest_exclude)
+                .chain(template_exclude)
+                .chain(&flags.exclude)
+                .chain(&manifest_file)
+                .chain(&conditional_exclude),
+        )?;
+        let manifest_hooks = manifest
+            .map(|manifest| {
+                let pre_init = manifest.pre_init.as_ref().filter(|_| template.pre_init.is_none());
+                let post_init = manifest.post_init.as_ref().filter(|_| template.post_init.is_none());
+                pre_init.into_iter().chain(post_init).cloned().collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            git: flags.git.clone()
+                .or_else(|| template.git.clone())
+                .or_else(|| manifest.and_then(|manifest| manifest.git.clone()))
+                .unwrap_or_else(|| config.git.clone()),
+            pre_init: template.pre_init.clone()
+                .or_else(|| manifest.and_then(|manifest| manifest.pre_init.clone())),
+            post_init: template.post_init.clone()
+                .or_else(|| manifest.and_then(|manifest| manifest.post_init.clone())),
+            manifest_hooks,
+            git_ref: flags.git_ref.clone().or_else(|| template.git_ref.clone()),
+            commit: if flags.git_ref.is_some() { None } else { template.commit.clone() },
             exclude,
-            write_mode: write_mode_flag
+            write_mode: flags.write_mode.clone()
                 .or_else(|| template.write_mode.clone())
                 .unwrap_or_else(|| config.write_mode.clone()),
+            target_subdir: template.target_subdir.clone(),
+            subdir: template.subdir.clone(),
+            update_on_init: config.update_on_init.clone(),
+            cache_ttl_secs: config.cache_ttl_secs,
+            no_cache: config.no_cache,
+            symlink_mode: config.symlink_mode.clone(),
+            path_token_style: manifest
+                .and_then(|manifest| manifest.path_token_style.clone())
+                .unwrap_or_else(|| config.path_token_style.clone()),
+            symlink_fallback: config.symlink_fallback.clone(),
+            hook_timeout_secs: config.hook_timeout_secs,
+            pre_init_cwd: config.pre_init_cwd.clone(),
+            follow_symlinks: flags.follow_symlinks || config.follow_symlinks,
+            preserve_strategy: config.preserve_strategy.clone(),
+            clear_git_hooks: config.clear_git_hooks,
+            commit_message_template: flags.commit_message.clone()
+                .or_else(|| config.commit_message.clone())
+                .unwrap_or_else(|| DEFAULT_COMMIT_MESSAGE.to_string()),
+            no_commit: flags.no_commit || config.no_commit,
+            gitignore_template: template.gitignore_template.clone()
+                .or_else(|| config.gitignore_template.clone()),
+            file_filter: FileFilter {
+                max_file_size: template.max_file_size.or(config.max_file_size),
+                exclude_binaries: template.exclude_binaries.unwrap_or(config.exclude_binaries),
+            },
+            keep_empty_dirs: config.keep_empty_dirs,
+            renames: normalize_renames(manifest.and_then(|manifest| manifest.renames.as_ref()))?,
+            vars: flags.vars.clone(),
+        })
+    }
+
+    /// The copy settings for `fs_copy::copy_template`.
+    pub fn copy_options(&self) -> CopyOptions {
+        CopyOptions {
+            symlink_mode: self.symlink_mode.clone(),
+            path_token_style: self.path_token_style.clone(),
+            path_tokens: BTreeMap::new(),
+            symlink_fallback: self.symlink_fallback.clone(),
+            follow_symlinks: self.follow_symlinks,
+            filter: self.file_filter.clone(),
+            keep_empty_dirs: self.keep_empty_dirs,
+            renames: self.renames.clone(),
+        }
+    }
+
+    /// The ref init checks out: the pinned commit if there is one, else the git ref.
+    pub fn checkout_ref(&self) -> Option<&str> {
+        self.commit.as_deref().or(self.git_ref.as_deref())
+    }
+
+    /// The `.gitignore` to add after copying, if any. A one-line value that looks like a path
+    /// (it contains `/`, or starts with `~` or `$`) is read from that file, and it's an error if
+    /// it can't be; anything else is used as the content itself.
+    pub fn gitignore_contents(&self) -> anyhow::Result<Option<String>> {
+        let Some(ref value) = self.gitignore_template else {
+            return Ok(None);
+        };
+        if gitignore_template_is_path(value) {
+            let path = crate::utilities::expand_location(value).context("failed to expand gitignore_template")?;
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read gitignore_template: {}", path.display()))?;
+            return Ok(Some(contents));
+        }
+        let mut contents = value.clone();
+        if !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        Ok(Some(contents))
+    }
+
+    /// Renders the commit message, filling `{name}` with the template name, `{date}` with
+    /// today's UTC date (`YYYY-MM-DD`) and `{ref}` with the git ref in use (empty if none).
+    pub fn commit_message(&self, template_name: &str) -> Result<String, TemplativeError> {
+        let now = crate::utilities::now_rfc3339();
+        let message = render_commit_message(
+            &self.commit_message_template,
+            template_name,
+            &now[..10],
+            self.checkout_ref().unwrap_or(""),
+        );
+        if message.trim().is_empty() {
+            return Err(TemplativeError::EmptyCommitMessage);
+        }
+        Ok(message)
+    }
+}
+
+/// Whether a `gitignore_template` value names a file rather than being the content. Content
+/// with a single pattern like `target/` can end in a newline to be taken as content.
+fn gitignore_template_is_path(value: &str) -> bool {
+    !value.contains('\n') && (value.contains('/') || value.starts_with('~') || value.starts_with('$'))
+}
+
+/// Trims each pattern, drops empties and duplicates (keeping first-seen order), and rejects
+/// patterns that would match the template root itself. A `!pattern` entry removes that exact
+/// pattern from the ones before it, so a template can drop a config default such as
+/// `node_modules`; it doesn't re-include files the way a gitignore negation does.
+fn normalize_excludes<'a>(
+    patterns: impl Iterator<Item = &'a String>,
+) -> Result<Vec<String>, TemplativeError> {
+    let mut exclude: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            continue;
+        }
+        if let Some(removed) = pattern.strip_prefix('!') {
+            let removed = removed.trim();
+            exclude.retain(|existing| existing != removed);
+            continue;
+        }
+        if matches!(pattern, "/" | "." | "./") {
+            return Err(TemplativeError::ExcludeMatchesEverything { pattern: pattern.to_string() });
+        }
+        if !exclude.iter().any(|existing| existing == pattern) {
+            exclude.push(pattern.to_string());
+        }
+    }
+    Ok(exclude)
+}
+
+/// Strips `./` and trailing slashes from both sides of each rename, and rejects renames that
+/// are empty, absolute, climb out with `..` or write into `.git` (which is never copied to),
+/// and two renames that write to the same path.
+fn normalize_renames(renames: Option<&BTreeMap<String, String>>) -> Result<BTreeMap<String, String>, TemplativeError> {
+    let mut normalized: BTreeMap<String, String> = BTreeMap::new();
+    for (from, to) in renames.into_iter().flatten() {
+        let (Some(from_key), Some(to_path)) = (rename_path(from), rename_path(to)) else {
+            return Err(TemplativeError::InvalidRename { from: from.clone(), to: to.clone() });
+        };
+        let first = to_path.split('/').next().unwrap_or_default();
+        if first.eq_ignore_ascii_case(".git") {
+            return Err(TemplativeError::InvalidRename { from: from.clone(), to: to.clone() });
+        }
+        if let Some((other, _)) = normalized.iter().find(|(_, existing)| **existing == to_path) {
+            return Err(TemplativeError::DuplicateRename { first: other.clone(), second: from_key, to: to_path });
+        }
+        normalized.insert(from_key, to_path);
+    }
+    Ok(normalized)
+}
+
+/// `path` as `/`-joined normal components, or `None` if it has none or leaves the root.
+fn rename_path(path: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => return None,
         }
     }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn render_commit_message(template: &str, name: &str, date: &str, git_ref: &str) -> String {
+    template
+        .replace("{name}", name)
+        .replace("{date}", date)
+        .replace("{ref}", git_ref)
 }
 
 #[cfg(test)]
@@ -52,6 +319,29 @@ mod tests {
             exclude: vec!["node_modules".into(), ".DS_Store".into()],
             write_mode: WriteMode::Strict,
             color: true,
+            update_on_init: UpdateOnInit::OnlyUrl,
+            no_cache: false,
+            symlink_mode: SymlinkMode::Auto,
+            path_token_style: PathTokenStyle::Braces,
+            symlink_fallback: SymlinkFallback::Error,
+            hook_timeout_secs: None,
+            pre_init_cwd: HookCwd::Target,
+            follow_symlinks: false,
+            preserve_strategy: PreserveStrategy::Full,
+            clear_git_hooks: false,
+            git_retries: 0,
+            cache_dir: None,
+            commit_message: None,
+            no_commit: false,
+            dangerous_paths: Vec::new(),
+            gitignore_template: None,
+            cache_ttl_secs: None,
+            max_file_size: None,
+            exclude_binaries: false,
+            keep_empty_dirs: true,
+            flag_config_dir: None,
+            flag_registry_dir: None,
+            show_progress: false,
         }
     }
 
@@ -66,6 +356,19 @@ mod tests {
             git_ref: None,
             exclude: None,
             write_mode: None,
+            target_subdir: None,
+            tags: None,
+            default_branch: None,
+            created_at: None,
+            last_used: None,
+            subdir: None,
+            git_ref_kind: None,
+            gitignore_template: None,
+            commit: None,
+            next_steps: None,
+            max_file_size: None,
+            exclude_binaries: None,
+            checksum: None,
         }
     }
 
@@ -74,9 +377,9 @@ mod tests {
         let resolved = ResolvedOptions::build(
             &make_config(GitMode::Fresh),
             &make_template(Some(GitMode::Fresh)),
-            Some(GitMode::NoGit),
             None,
-        );
+            &InitFlags { git: Some(GitMode::NoGit), ..Default::default() },
+        ).unwrap();
         assert_eq!(resolved.git, GitMode::NoGit);
     }
 
@@ -86,8 +389,8 @@ mod tests {
             &make_config(GitMode::Fresh),
             &make_template(Some(GitMode::Preserve)),
             None,
-            None,
-        );
+            &InitFlags::default(),
+        ).unwrap();
         assert_eq!(resolved.git, GitMode::Preserve);
     }
 
@@ -97,8 +400,8 @@ mod tests {
             &make_config(GitMode::NoGit),
             &make_template(None),
             None,
-            None,
-        );
+            &InitFlags::default(),
+        ).unwrap();
         assert_eq!(resolved.git, GitMode::NoGit);
     }
 
@@ -108,8 +411,8 @@ mod tests {
             &make_config(GitMode::Fresh),
             &make_template(None),
             None,
-            None,
-        );
+            &InitFlags::default(),
+        ).unwrap();
         assert_eq!(resolved.git, GitMode::Fresh);
     }
 
@@ -117,16 +420,58 @@ mod tests {
     fn git_ref_resolves_from_template() {
         let mut template = make_template(None);
         template.git_ref = Some("v1.0".into());
-        let resolved = ResolvedOptions::build(&make_config(GitMode::Fresh), &template, None, None);
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &template,
+            None,
+            &InitFlags::default(),
+        ).unwrap();
         assert_eq!(resolved.git_ref.as_deref(), Some("v1.0"));
     }
 
+    #[test]
+    fn git_ref_flag_overrides_template() {
+        let mut template = make_template(None);
+        template.git_ref = Some("v1.0".into());
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &template,
+            None,
+            &InitFlags { git_ref: Some("v2.0".into()), ..Default::default() },
+        ).unwrap();
+        assert_eq!(resolved.git_ref.as_deref(), Some("v2.0"));
+    }
+
+    #[test]
+    fn target_subdir_resolves_from_template() {
+        let mut template = make_template(None);
+        template.target_subdir = Some("docs".into());
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &template,
+            None,
+            &InitFlags::default(),
+        ).unwrap();
+        assert_eq!(resolved.target_subdir.as_deref(), Some("docs"));
+    }
+
+    #[test]
+    fn target_subdir_defaults_to_none() {
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &make_template(None),
+            None,
+            &InitFlags::default(),
+        ).unwrap();
+        assert!(resolved.target_subdir.is_none());
+    }
+
     #[test]
     fn template_exclude_extends_config_exclude() {
         let config = make_config(GitMode::Fresh);
         let mut template = make_template(None);
         template.exclude = Some(vec!["dist".into(), "*.log".into()]);
-        let resolved = ResolvedOptions::build(&config, &template, None, None);
+        let resolved = ResolvedOptions::build(&config, &template, None, &InitFlags::default()).unwrap();
         assert!(resolved.exclude.contains(&"node_modules".to_string()));
         assert!(resolved.exclude.contains(&".DS_Store".to_string()));
         assert!(resolved.exclude.contains(&"dist".to_string()));
@@ -136,17 +481,117 @@ mod tests {
     #[test]
     fn none_template_exclude_uses_config_list() {
         let config = make_config(GitMode::Fresh);
-        let resolved = ResolvedOptions::build(&config, &make_template(None), None, None);
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
         assert_eq!(resolved.exclude, vec!["node_modules", ".DS_Store"]);
     }
 
+    #[test]
+    fn exclude_flag_appends_to_merged_list() {
+        let config = make_config(GitMode::Fresh);
+        let mut template = make_template(None);
+        template.exclude = Some(vec!["dist".into()]);
+        let resolved = ResolvedOptions::build(
+            &config,
+            &template,
+            None,
+            &InitFlags { exclude: vec!["tests".into()], ..Default::default() },
+        ).unwrap();
+        assert_eq!(resolved.exclude, vec!["node_modules", ".DS_Store", "dist", "tests"]);
+    }
+
+    #[test]
+    fn bang_entry_removes_an_earlier_pattern() {
+        let config = make_config(GitMode::Fresh);
+        let mut template = make_template(None);
+        template.exclude = Some(vec!["!node_modules".into(), "dist".into(), "!missing".into()]);
+        let resolved = ResolvedOptions::build(
+            &config,
+            &template,
+            None,
+            &InitFlags { exclude: vec!["!dist".into()], ..Default::default() },
+        ).unwrap();
+        assert_eq!(resolved.exclude, vec![".DS_Store"]);
+    }
+
+    #[test]
+    fn exclude_only_replaces_merged_list() {
+        let config = make_config(GitMode::Fresh);
+        let mut template = make_template(None);
+        template.exclude = Some(vec!["dist".into()]);
+        let resolved = ResolvedOptions::build(
+            &config,
+            &template,
+            None,
+            &InitFlags { exclude: vec!["tests".into()], exclude_only: true, ..Default::default() },
+        ).unwrap();
+        assert_eq!(resolved.exclude, vec!["tests"]);
+    }
+
+    #[test]
+    fn follow_symlinks_flag_or_config_enables() {
+        let mut config = make_config(GitMode::Fresh);
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+        assert!(!resolved.follow_symlinks);
+        let resolved = ResolvedOptions::build(
+            &config,
+            &make_template(None),
+            None,
+            &InitFlags { follow_symlinks: true, ..Default::default() },
+        ).unwrap();
+        assert!(resolved.follow_symlinks);
+        config.follow_symlinks = true;
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+        assert!(resolved.follow_symlinks);
+    }
+
+    #[test]
+    fn render_commit_message_fills_placeholders() {
+        assert_eq!(
+            render_commit_message("chore: scaffold {name}@{ref} on {date}", "web", "2024-05-01", "v1"),
+            "chore: scaffold web@v1 on 2024-05-01"
+        );
+    }
+
+    #[test]
+    fn commit_message_flag_overrides_config_and_default() {
+        let mut config = make_config(GitMode::Fresh);
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+        assert_eq!(resolved.commit_message("web").unwrap(), "adding template web");
+        config.commit_message = Some("feat: {name}".into());
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+        assert_eq!(resolved.commit_message("web").unwrap(), "feat: web");
+        let resolved = ResolvedOptions::build(
+            &config,
+            &make_template(None),
+            None,
+            &InitFlags { commit_message: Some("chore: {name}".into()), ..Default::default() },
+        ).unwrap();
+        assert_eq!(resolved.commit_message("web").unwrap(), "chore: web");
+    }
+
+    #[test]
+    fn empty_commit_message_is_rejected() {
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &make_template(None),
+            None,
+            &InitFlags { commit_message: Some("  {ref} ".into()), ..Default::default() },
+        ).unwrap();
+        assert!(matches!(resolved.commit_message("web"), Err(TemplativeError::EmptyCommitMessage)));
+    }
+
     #[test]
     fn write_mode_flag_overrides_template_and_config() {
         let mut config = make_config(GitMode::Fresh);
         config.write_mode = WriteMode::Strict;
         let mut template = make_template(None);
         template.write_mode = Some(WriteMode::NoOverwrite);
-        let resolved = ResolvedOptions::build(&config, &template, None, Some(WriteMode::Overwrite));
+        let resolved = ResolvedOptions::build(
+            &config,
+            &template,
+            None,
+            &InitFlags { write_mode: Some(WriteMode::Overwrite), ..Default::default() },
+        ).unwrap();
         assert_eq!(resolved.write_mode, WriteMode::Overwrite);
     }
 
@@ -156,7 +601,7 @@ mod tests {
         config.write_mode = WriteMode::Strict;
         let mut template = make_template(None);
         template.write_mode = Some(WriteMode::SkipOverwrite);
-        let resolved = ResolvedOptions::build(&config, &template, None, None);
+        let resolved = ResolvedOptions::build(&config, &template, None, &InitFlags::default()).unwrap();
         assert_eq!(resolved.write_mode, WriteMode::SkipOverwrite);
     }
 
@@ -164,7 +609,149 @@ mod tests {
     fn write_mode_config_used_when_neither_set() {
         let mut config = make_config(GitMode::Fresh);
         config.write_mode = WriteMode::NoOverwrite;
-        let resolved = ResolvedOptions::build(&config, &make_template(None), None, None);
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
         assert_eq!(resolved.write_mode, WriteMode::NoOverwrite);
     }
+
+    #[test]
+    fn overlapping_excludes_are_trimmed_and_deduped() {
+        let config = make_config(GitMode::Fresh);
+        let mut template = make_template(None);
+        template.exclude = Some(vec![" node_modules ".into(), "".into(), "dist".into()]);
+        let resolved = ResolvedOptions::build(
+            &config,
+            &template,
+            None,
+            &InitFlags { exclude: vec!["dist".into()], ..Default::default() },
+        )
+        .unwrap();
+        assert_eq!(resolved.exclude, vec!["node_modules", ".DS_Store", "dist"]);
+    }
+
+    #[test]
+    fn exclude_matching_template_root_is_rejected() {
+        for pattern in ["/", "."] {
+            let result = ResolvedOptions::build(
+                &make_config(GitMode::Fresh),
+                &make_template(None),
+                None,
+                &InitFlags { exclude: vec![pattern.into()], ..Default::default() },
+            );
+            assert!(matches!(result, Err(TemplativeError::ExcludeMatchesEverything { .. })));
+        }
+    }
+
+    #[test]
+    fn gitignore_template_reads_a_file_or_uses_the_value() {
+        let mut config = make_config(GitMode::Fresh);
+        config.gitignore_template = Some("*.log".into());
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+        assert_eq!(resolved.gitignore_contents().unwrap().as_deref(), Some("*.log\n"));
+        config.gitignore_template = Some("target/\n".into());
+        let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+        assert_eq!(resolved.gitignore_contents().unwrap().as_deref(), Some("target/\n"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("gitignore");
+        std::fs::write(&file, "node_modules/\n").unwrap();
+        let mut template = make_template(None);
+        template.gitignore_template = Some(file.to_string_lossy().into_owned());
+        let resolved = ResolvedOptions::build(&config, &template, None, &InitFlags::default()).unwrap();
+        assert_eq!(resolved.gitignore_contents().unwrap().as_deref(), Some("node_modules/\n"));
+    }
+
+    #[test]
+    fn gitignore_template_that_looks_like_a_path_must_be_readable() {
+        let mut config = make_config(GitMode::Fresh);
+        for value in ["~/no/such/gitignore", "$TEMPLATIVE_TEST_SURELY_UNSET/gitignore", "target/"] {
+            config.gitignore_template = Some(value.into());
+            let resolved = ResolvedOptions::build(&config, &make_template(None), None, &InitFlags::default()).unwrap();
+            assert!(resolved.gitignore_contents().is_err(), "{} should be read as a path", value);
+        }
+    }
+
+    fn make_manifest() -> Manifest {
+        Manifest {
+            exclude: Some(vec!["fixtures".into()]),
+            pre_init: Some("echo manifest".into()),
+            post_init: Some("echo manifest".into()),
+            git: Some(GitMode::Preserve),
+            ..Manifest::default()
+        }
+    }
+
+    #[test]
+    fn manifest_overrides_config() {
+        let manifest = make_manifest();
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &make_template(None),
+            Some(&manifest),
+            &InitFlags::default(),
+        ).unwrap();
+        assert_eq!(resolved.git, GitMode::Preserve);
+        assert_eq!(resolved.pre_init.as_deref(), Some("echo manifest"));
+        assert_eq!(resolved.post_init.as_deref(), Some("echo manifest"));
+    }
+
+    #[test]
+    fn template_and_flag_override_manifest() {
+        let manifest = make_manifest();
+        let mut template = make_template(Some(GitMode::NoGit));
+        template.post_init = Some("echo template".into());
+        let resolved =
+            ResolvedOptions::build(&make_config(GitMode::Fresh), &template, Some(&manifest), &InitFlags::default())
+                .unwrap();
+        assert_eq!(resolved.git, GitMode::NoGit);
+        assert_eq!(resolved.pre_init.as_deref(), Some("echo manifest"));
+        assert_eq!(resolved.post_init.as_deref(), Some("echo template"));
+        assert_eq!(resolved.manifest_hooks, vec!["echo manifest".to_string()]);
+
+        let resolved = ResolvedOptions::build(
+            &make_config(GitMode::Fresh),
+            &template,
+            Some(&manifest),
+            &InitFlags { git: Some(GitMode::Fresh), ..Default::default() },
+        ).unwrap();
+        assert_eq!(resolved.git, GitMode::Fresh);
+    }
+
+    #[test]
+    fn manifest_excludes_are_merged_and_the_manifest_is_excluded() {
+        let manifest = make_manifest();
+        let mut template = make_template(None);
+        template.exclude = Some(vec!["dist".into()]);
+        let resolved =
+            ResolvedOptions::build(&make_config(GitMode::Fresh), &template, Some(&manifest), &InitFlags::default())
+                .unwrap();
+        assert!(resolved.exclude.contains(&"fixtures".to_string()));
+        assert!(resolved.exclude.contains(&"dist".to_string()));
+        assert!(resolved.exclude.contains(&"/.templative.toml".to_string()));
+
+        let resolved = ResolvedOptions::build(&make_config(GitMode::Fresh), &template, None, &InitFlags::default())
+            .unwrap();
+        assert!(!resolved.exclude.contains(&"/.templative.toml".to_string()));
+    }
+
+    #[test]
+    fn manifest_renames_are_normalized_and_checked() {
+        let mut manifest = make_manifest();
+        manifest.renames = Some([("./gitignore".to_string(), ".gitignore".to_string())].into());
+        let resolved =
+            ResolvedOptions::build(&make_config(GitMode::Fresh), &make_template(None), Some(&manifest), &InitFlags::default())
+                .unwrap();
+        assert_eq!(resolved.renames.get("gitignore").map(String::as_str), Some(".gitignore"));
+
+        for (from, to) in [("env", "../.env"), ("env", "/etc/env"), ("", ".env"), ("hook", ".git/hooks/pre-commit")] {
+            manifest.renames = Some([(from.to_string(), to.to_string())].into());
+            let result =
+                ResolvedOptions::build(&make_config(GitMode::Fresh), &make_template(None), Some(&manifest), &InitFlags::default());
+            assert!(matches!(result, Err(TemplativeError::InvalidRename { .. })), "{} -> {}", from, to);
+        }
+
+        manifest.renames = Some([("a".to_string(), "out".to_string()), ("./b".to_string(), "out/".to_string())].into());
+        let result =
+            ResolvedOptions::build(&make_config(GitMode::Fresh), &make_template(None), Some(&manifest), &InitFlags::default());
+        assert!(matches!(result, Err(TemplativeError::DuplicateRename { .. })));
+    }
 }
@@ -1,11 +1,40 @@
+use std::io::Read;
 use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use crate::config::Config;
 
 #[cfg(not(unix))]
 use directories::ProjectDirs;
 
-pub fn config_dir() -> Result<PathBuf> {
+/// Where `templates.json` lives: `--registry-dir`, then `TEMPLATIVE_REGISTRY_DIR`, then the
+/// config directory. Lets several registries share one config.
+pub fn registry_dir(config: &Config) -> Result<PathBuf> {
+    if let Some(ref dir) = config.flag_registry_dir {
+        return Ok(dir.clone());
+    }
+    if let Some(override_dir) = std::env::var_os("TEMPLATIVE_REGISTRY_DIR") {
+        return Ok(PathBuf::from(override_dir));
+    }
+    config_dir(config)
+}
+
+/// The config directory: the `--config` file's directory, then `default_config_dir()`.
+pub fn config_dir(config: &Config) -> Result<PathBuf> {
+    match config.flag_config_dir {
+        Some(ref dir) => Ok(dir.clone()),
+        None => default_config_dir(),
+    }
+}
+
+/// Where `config.json` is looked for without `--config`: `TEMPLATIVE_CONFIG_DIR`, then the
+/// platform default.
+pub fn default_config_dir() -> Result<PathBuf> {
     if let Some(override_dir) = std::env::var_os("TEMPLATIVE_CONFIG_DIR") {
         return Ok(PathBuf::from(override_dir));
     }
@@ -32,39 +61,220 @@ pub fn config_dir() -> Result<PathBuf> {
     }
 }
 
-pub fn is_dangerous_path(path: &std::path::Path) -> bool {
+/// System directories no init should ever write into. Any filesystem root and `$HOME` are
+/// checked separately.
+const DANGEROUS_PATHS: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/lib", "/proc", "/sbin", "/sys", "/usr", "/var",
+    "/System", "/Library", "/Applications",
+];
+
+/// Whether `path` is a filesystem root, `$HOME`, a built-in system directory or one of
+/// `extra` (the `dangerous_paths` config). Both sides are canonicalized where possible, so
+/// symlinks such as macOS's `/etc -> /private/etc` can't slip past the check.
+pub fn is_dangerous_path(path: &std::path::Path, extra: &[PathBuf]) -> bool {
+    let canonical = |candidate: &std::path::Path| {
+        candidate.canonicalize().unwrap_or_else(|_| candidate.to_path_buf())
+    };
+    let path = canonical(path);
+    if path.parent().is_none() {
+        return true;
+    }
     let home = std::env::var_os("HOME").map(PathBuf::from);
-    path == std::path::Path::new("/") || home.as_deref().map_or(false, |home_dir| path == home_dir)
+    DANGEROUS_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .chain(home)
+        .chain(extra.iter().cloned())
+        .any(|dangerous| canonical(&dangerous) == path)
 }
 
-pub fn run_hook(command: &str, working_dir: &std::path::Path) -> Result<()> {
+/// Runs a hook through the platform shell in `working_dir`. With `timeout_secs` set, the hook
+/// is killed once it runs longer than that and an error is returned. With `stream` the hook
+/// writes straight to the terminal as it runs; otherwise its output is captured and only
+/// stderr is shown, in the error, if it fails.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(working_dir, timeout_secs)))]
+pub fn run_hook(command: &str, working_dir: &std::path::Path, timeout_secs: Option<u64>, stream: bool) -> Result<()> {
     #[cfg(unix)]
-    let output = std::process::Command::new("sh")
-        .args(["-c", command])
-        .current_dir(working_dir)
-        .output()
-        .context("failed to execute hook")?;
+    let mut process = std::process::Command::new("sh");
+    #[cfg(unix)]
+    process.args(["-c", command]);
+    #[cfg(not(unix))]
+    let mut process = std::process::Command::new("cmd");
     #[cfg(not(unix))]
-    let output = std::process::Command::new("cmd")
-        .args(["/c", command])
+    process.args(["/c", command]);
+    let output = || if stream { Stdio::inherit() } else { Stdio::piped() };
+    let mut child = process
         .current_dir(working_dir)
-        .output()
+        .stdin(Stdio::null())
+        .stdout(output())
+        .stderr(output())
+        .spawn()
         .context("failed to execute hook")?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Drain both pipes on their own threads so a chatty hook can't block on a full pipe
+    // while we poll for exit. Streamed output has no pipes, so these read nothing.
+    let stdout_reader = drain(child.stdout.take());
+    let stderr_reader = drain(child.stderr.take());
+
+    let deadline = timeout_secs.map(|secs| (secs, Instant::now() + Duration::from_secs(secs)));
+    let status = loop {
+        if let Some(status) = child.try_wait().context("failed to wait for hook")? {
+            break status;
+        }
+        if let Some((secs, deadline)) = deadline
+            && Instant::now() >= deadline
+        {
+            let _ = child.kill();
+            let _ = child.wait();
+            // The readers are left detached: grandchildren of the shell may still hold the
+            // pipes open, and joining would block until they exit.
+            anyhow::bail!("hook timed out after {}s", secs);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let _ = stdout_reader.join();
+    let stderr = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        if stream {
+            // The hook's own output is already on the terminal.
+            anyhow::bail!("hook failed: {}", status);
+        }
+        let stderr = String::from_utf8_lossy(&stderr);
         anyhow::bail!("hook failed: {}", stderr.trim());
     }
     Ok(())
 }
 
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// The default template name for a location: its last path segment, minus any `.git`.
+pub fn name_from_location(location: &str) -> String {
+    let last = location.trim_end_matches(['/', '\\']).rsplit(['/', '\\', ':']).next().unwrap_or("");
+    let name = last.trim_end_matches(".git");
+    if name.is_empty() { "template".to_string() } else { name.to_string() }
+}
+
 pub fn is_git_url(url: &str) -> bool {
     url.starts_with("https://")
         || url.starts_with("http://")
         || url.starts_with("git@")
         || url.starts_with("git://")
+        || url.starts_with("ssh://")
+        || is_scp_like(url)
+}
+
+/// Matches git's scp-like syntax, `[user@]host:path`: a colon before the first slash. A
+/// single-letter host is a Windows drive (`C:\foo`, `C:/foo`), not a remote.
+fn is_scp_like(url: &str) -> bool {
+    let Some((prefix, path)) = url.split_once(':') else {
+        return false;
+    };
+    let host = prefix.rsplit('@').next().unwrap_or(prefix);
+    host.len() > 1
+        && host != "file"
+        && !prefix.contains(['/', '\\'])
+        && !path.is_empty()
+        && !path.starts_with('\\')
+        && !path.starts_with("//")
 }
 
-fn fnv1a_hash(input: &str) -> u64 {
+/// Whether `location` is written with a `~` or `$VAR` for `expand_location` to fill in. Such
+/// locations are stored as written rather than canonicalized, so they expand per machine.
+pub fn location_uses_variables(location: &str) -> bool {
+    location.starts_with('~') || location.contains('$')
+}
+
+/// Expands a leading `~` and any `$VAR` / `${VAR}` in a local template location, so one
+/// registry can point at e.g. `$WORK/templates/site` on every machine. Locations are stored
+/// unexpanded and expanded on use; git URLs are returned untouched. An unset variable is an
+/// error rather than an empty string, which would silently point somewhere else.
+pub fn expand_location(location: &str) -> Result<PathBuf> {
+    if is_git_url(location) {
+        return Ok(PathBuf::from(location));
+    }
+    let lookup = |variable: &str| {
+        std::env::var(variable).map_err(|_| crate::errors::TemplativeError::LocationVariableUnset {
+            variable: variable.to_string(),
+            location: location.to_string(),
+        })
+    };
+    let mut expanded = String::with_capacity(location.len());
+    let mut rest = location;
+    if rest == "~" || rest.starts_with("~/") {
+        expanded.push_str(&lookup("HOME")?);
+        rest = &rest[1..];
+    }
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (variable, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(close) => (&braced[..close], &braced[close + 1..]),
+                None => ("", after),
+            }
+        } else {
+            let end = after
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if variable.is_empty() || variable.starts_with(|ch: char| ch.is_ascii_digit()) {
+            // Not a variable reference (`$`, `$5`, an unclosed `${`): keep the `$` literally.
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        expanded.push_str(&lookup(variable)?);
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    Ok(PathBuf::from(expanded))
+}
+
+/// The current UTC time as RFC3339 (`2024-05-01T12:34:56Z`). Lexicographic order matches
+/// chronological order, so stored timestamps sort as plain strings.
+pub fn now_rfc3339() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    format_rfc3339(secs)
+}
+
+fn format_rfc3339(unix_secs: u64) -> String {
+    let days = (unix_secs / 86_400) as i64;
+    let secs_of_day = unix_secs % 86_400;
+    // Civil-from-days (Howard Hinnant), valid for the proleptic Gregorian calendar.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+pub(crate) fn fnv1a_hash(input: &str) -> u64 {
     // FNV-1a 64-bit: standard constants from https://www.isthe.com/chongo/tech/comp/fnv/
     const OFFSET_BASIS: u64 = 14695981039346656037; // 0xcbf29ce484222325
     const PRIME: u64 = 1099511628211;               // 0x00000100000001b3
@@ -76,8 +286,64 @@ fn fnv1a_hash(input: &str) -> u64 {
     hash
 }
 
-pub fn cache_path_for_url(url: &str) -> Result<PathBuf> {
-    Ok(config_dir()?.join("cache").join(format!("{:016x}", fnv1a_hash(url))))
+/// Whether the config directory was pointed somewhere explicitly (`--config` or
+/// `TEMPLATIVE_CONFIG_DIR`) rather than left at the platform default.
+fn config_dir_overridden(config: &Config) -> bool {
+    config.flag_config_dir.is_some() || std::env::var_os("TEMPLATIVE_CONFIG_DIR").is_some()
+}
+
+/// Where cached clones lived before they moved to the XDG cache directory.
+pub fn legacy_cache_dir(config: &Config) -> Result<PathBuf> {
+    Ok(config_dir(config)?.join("cache"))
+}
+
+/// Where cached clones of URL templates live when nothing overrides it. On unix that's
+/// `$XDG_CACHE_HOME/templative`, then `~/.cache/templative`; elsewhere, or when neither is
+/// available, `legacy_cache_dir()`. An explicitly chosen config directory keeps its cache
+/// beside it, so a separate setup stays self-contained.
+pub fn default_cache_dir(config: &Config) -> Result<PathBuf> {
+    if config_dir_overridden(config) {
+        return legacy_cache_dir(config);
+    }
+    #[cfg(unix)]
+    {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")));
+        if let Some(base) = base {
+            return Ok(base.join("templative"));
+        }
+    }
+    legacy_cache_dir(config)
+}
+
+/// The cache directory: `TEMPLATIVE_CACHE_DIR`, then `cache_dir` from config, then
+/// `default_cache_dir()`.
+pub fn cache_dir(config: &Config) -> Result<PathBuf> {
+    if let Some(override_dir) = std::env::var_os("TEMPLATIVE_CACHE_DIR") {
+        return Ok(PathBuf::from(override_dir));
+    }
+    match config.cache_dir {
+        Some(ref dir) => Ok(dir.clone()),
+        None => default_cache_dir(config),
+    }
+}
+
+/// Where `add --stdin` keeps single-file templates.
+pub fn snippets_dir(config: &Config) -> Result<PathBuf> {
+    Ok(config_dir(config)?.join("snippets"))
+}
+
+pub fn cache_path_for_url(config: &Config, url: &str) -> Result<PathBuf> {
+    Ok(cache_dir(config)?.join(format!("{:016x}", fnv1a_hash(url))))
+}
+
+/// Whether `path` is only plain names, so joining it onto a directory can't climb out of it
+/// (`..`), replace it (an absolute path) or name the directory itself (empty or `.`).
+pub fn is_plain_relative(path: &std::path::Path) -> bool {
+    let mut components = path.components().peekable();
+    components.peek().is_some() && components.all(|component| matches!(component, std::path::Component::Normal(_)))
 }
 
 pub fn is_dir_empty(path: &std::path::Path) -> Result<bool> {
@@ -86,10 +352,145 @@ pub fn is_dir_empty(path: &std::path::Path) -> Result<bool> {
     Ok(entries.next().is_none())
 }
 
+/// Whether output is coloured, decided once in `run()` from `--color`/`--no-color`,
+/// `NO_COLOR` and the config, in that order. Left to all of them, colour is on only when the
+/// stream it's for is a terminal, so piped output carries no escape codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn resolve(force_color: bool, no_color: bool, config_color: bool, is_terminal: bool) -> Self {
+        let enabled = if no_color {
+            false
+        } else if force_color {
+            true
+        } else if std::env::var_os("NO_COLOR").is_some() {
+            false
+        } else {
+            config_color && is_terminal
+        };
+        if enabled { ColorChoice::Always } else { ColorChoice::Never }
+    }
+
+    pub fn enabled(self) -> bool {
+        self == ColorChoice::Always
+    }
+}
+
+/// Whether `warn()` colours its prefix. Unlike the other settings this is process-wide, as
+/// stderr is: embedders that never call `set_color_choice` get plain warnings.
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Applies `choice` to `warn()` for the rest of the process.
+pub fn set_color_choice(choice: ColorChoice) {
+    COLOR_ENABLED.store(choice.enabled(), Ordering::Relaxed);
+}
+
+/// Prints `message` to stderr behind a `warning:` prefix, yellow when colour is on.
+pub fn warn(message: std::fmt::Arguments) {
+    if COLOR_ENABLED.load(Ordering::Relaxed) {
+        eprintln!("{} {}", "warning:".yellow().bold(), message);
+    } else {
+        eprintln!("warning: {}", message);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn color_flags_override_config() {
+        assert_eq!(ColorChoice::resolve(false, true, true, true), ColorChoice::Never);
+        assert_eq!(ColorChoice::resolve(true, false, false, true), ColorChoice::Always);
+        assert_eq!(ColorChoice::resolve(true, true, true, true), ColorChoice::Never);
+    }
+
+    #[test]
+    fn color_defaults_to_off_when_not_a_terminal() {
+        let _lock = crate::test_env::ENV_LOCK.lock().unwrap();
+        let saved = std::env::var_os("NO_COLOR");
+        unsafe { std::env::remove_var("NO_COLOR"); }
+        let piped = ColorChoice::resolve(false, false, true, false);
+        let terminal = ColorChoice::resolve(false, false, true, true);
+        let forced = ColorChoice::resolve(true, false, true, false);
+        if let Some(value) = saved {
+            unsafe { std::env::set_var("NO_COLOR", value); }
+        }
+        assert_eq!(piped, ColorChoice::Never);
+        assert_eq!(terminal, ColorChoice::Always);
+        assert_eq!(forced, ColorChoice::Always);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn is_dangerous_path_covers_roots_and_system_dirs() {
+        assert!(is_dangerous_path(std::path::Path::new("/"), &[]));
+        assert!(is_dangerous_path(std::path::Path::new("/usr"), &[]));
+        assert!(is_dangerous_path(std::path::Path::new("/etc/"), &[]));
+        assert!(!is_dangerous_path(std::path::Path::new("/usr/local/share/project"), &[]));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn is_dangerous_path_sees_through_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let link = temp.path().join("etc-link");
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+        assert!(is_dangerous_path(&link, &[]));
+    }
+
+    #[test]
+    fn is_dangerous_path_includes_configured_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("precious");
+        std::fs::create_dir(&project).unwrap();
+        assert!(!is_dangerous_path(&project, &[]));
+        assert!(is_dangerous_path(&project, std::slice::from_ref(&project)));
+    }
+
+    #[test]
+    fn expand_location_expands_tilde_and_variables() {
+        let _guard = crate::test_env::ENV_LOCK.lock().unwrap();
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(expand_location("~").unwrap(), PathBuf::from(&home));
+        assert_eq!(
+            expand_location("~/templates/site").unwrap(),
+            PathBuf::from(format!("{}/templates/site", home))
+        );
+        assert_eq!(expand_location("$HOME/a").unwrap(), PathBuf::from(format!("{}/a", home)));
+        assert_eq!(expand_location("${HOME}_b/c").unwrap(), PathBuf::from(format!("{}_b/c", home)));
+        assert_eq!(expand_location("/price/$5/~x").unwrap(), PathBuf::from("/price/$5/~x"));
+    }
+
+    #[test]
+    fn expand_location_errors_on_unset_variable() {
+        let err = expand_location("$TEMPLATIVE_SURELY_UNSET_VAR/site").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::errors::TemplativeError>(),
+            Some(crate::errors::TemplativeError::LocationVariableUnset { variable, .. })
+                if variable == "TEMPLATIVE_SURELY_UNSET_VAR"
+        ));
+    }
+
+    #[test]
+    fn expand_location_leaves_urls_alone() {
+        let url = "https://host/$USER/repo";
+        assert_eq!(expand_location(url).unwrap(), PathBuf::from(url));
+    }
+
+    #[test]
+    fn name_from_location_takes_last_segment_without_git_suffix() {
+        assert_eq!(name_from_location("https://github.com/user/rust-lib.git"), "rust-lib");
+        assert_eq!(name_from_location("git@github.com:user/site/"), "site");
+        assert_eq!(name_from_location("git@host:repo.git"), "repo");
+        assert_eq!(name_from_location("/home/me/templates/cli"), "cli");
+        assert_eq!(name_from_location("/"), "template");
+    }
+
     #[test]
     fn is_git_url_recognises_https() {
         assert!(is_git_url("https://github.com/user/repo"));
@@ -110,6 +511,24 @@ mod tests {
         assert!(is_git_url("git://example.com/repo"));
     }
 
+    #[test]
+    fn is_git_url_recognises_ssh_scheme() {
+        assert!(is_git_url("ssh://git@host/repo"));
+    }
+
+    #[test]
+    fn is_git_url_recognises_scp_like_host_path() {
+        assert!(is_git_url("github.com:u/r"));
+        assert!(is_git_url("deploy@example.com:repos/site.git"));
+    }
+
+    #[test]
+    fn is_git_url_rejects_windows_drive_paths() {
+        assert!(!is_git_url("C:\\template"));
+        assert!(!is_git_url("C:/template"));
+        assert!(!is_git_url("file:///srv/template"));
+    }
+
     #[test]
     fn is_git_url_rejects_local_paths() {
         assert!(!is_git_url("/path/to/template"));
@@ -121,7 +540,7 @@ mod tests {
     #[cfg(unix)]
     fn run_hook_succeeds_on_valid_command() {
         let temp = tempfile::tempdir().unwrap();
-        let result = run_hook("true", temp.path());
+        let result = run_hook("true", temp.path(), None, false);
         assert!(result.is_ok());
     }
 
@@ -129,7 +548,7 @@ mod tests {
     #[cfg(unix)]
     fn run_hook_fails_on_failing_command() {
         let temp = tempfile::tempdir().unwrap();
-        let result = run_hook("false", temp.path());
+        let result = run_hook("false", temp.path(), None, false);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("hook failed"));
     }
@@ -140,27 +559,108 @@ mod tests {
         let temp = tempfile::tempdir().unwrap();
         std::fs::write(temp.path().join("sentinel"), "").unwrap();
         // Succeeds only if cwd is the temp dir (the file exists there).
-        let result = run_hook("test -f sentinel", temp.path());
+        let result = run_hook("test -f sentinel", temp.path(), None, false);
         assert!(result.is_ok());
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn run_hook_times_out() {
+        let temp = tempfile::tempdir().unwrap();
+        let started = Instant::now();
+        let result = run_hook("sleep 5", temp.path(), Some(1), false);
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert!(result.unwrap_err().to_string().contains("hook timed out after 1s"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_hook_within_timeout_succeeds() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(run_hook("true", temp.path(), Some(5), false).is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_hook_streamed_still_reports_failure() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(run_hook("true", temp.path(), None, true).is_ok());
+        let result = run_hook("exit 3", temp.path(), Some(5), true);
+        assert!(result.unwrap_err().to_string().contains("hook failed: exit status: 3"));
+    }
+
+    #[test]
+    fn format_rfc3339_known_instants() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_rfc3339(1_714_566_896), "2024-05-01T12:34:56Z");
+    }
+
+    #[test]
+    fn cache_dir_env_override_wins() {
+        let _guard = crate::test_env::ENV_LOCK.lock().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        unsafe { std::env::set_var("TEMPLATIVE_CACHE_DIR", dir.path()); }
+        let path = cache_path_for_url(&Config::new(), "https://github.com/user/repo");
+        unsafe { std::env::remove_var("TEMPLATIVE_CACHE_DIR"); }
+        assert_eq!(path.unwrap().parent().unwrap(), dir.path());
+    }
+
+    #[test]
+    fn cache_and_registry_dirs_come_from_the_config_passed_in() {
+        let _guard = crate::test_env::ENV_LOCK.lock().unwrap();
+        let first = Config { cache_dir: Some(PathBuf::from("/caches/first")), ..Config::new() };
+        let second = Config {
+            cache_dir: Some(PathBuf::from("/caches/second")),
+            flag_registry_dir: Some(PathBuf::from("/registries/second")),
+            ..Config::new()
+        };
+        assert_eq!(cache_dir(&first).unwrap(), PathBuf::from("/caches/first"));
+        assert_eq!(cache_dir(&second).unwrap(), PathBuf::from("/caches/second"));
+        assert_eq!(registry_dir(&second).unwrap(), PathBuf::from("/registries/second"));
+    }
+
+    #[test]
+    fn default_cache_dir_follows_xdg_unless_config_dir_is_overridden() {
+        let _lock = crate::test_env::ENV_LOCK.lock().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let saved_config_dir = std::env::var_os("TEMPLATIVE_CONFIG_DIR");
+        unsafe {
+            std::env::remove_var("TEMPLATIVE_CONFIG_DIR");
+            std::env::set_var("XDG_CACHE_HOME", temp.path());
+        }
+        let xdg = default_cache_dir(&Config::new()).unwrap();
+        unsafe { std::env::set_var("TEMPLATIVE_CONFIG_DIR", temp.path().join("config")); }
+        let overridden = default_cache_dir(&Config::new()).unwrap();
+        unsafe {
+            std::env::remove_var("XDG_CACHE_HOME");
+            match saved_config_dir {
+                Some(dir) => std::env::set_var("TEMPLATIVE_CONFIG_DIR", dir),
+                None => std::env::remove_var("TEMPLATIVE_CONFIG_DIR"),
+            }
+        }
+        #[cfg(unix)]
+        assert_eq!(xdg, temp.path().join("templative"));
+        assert_eq!(overridden, temp.path().join("config/cache"));
+    }
+
     #[test]
     fn cache_path_for_url_is_deterministic() {
-        let path1 = cache_path_for_url("https://github.com/user/repo").unwrap();
-        let path2 = cache_path_for_url("https://github.com/user/repo").unwrap();
+        let path1 = cache_path_for_url(&Config::new(), "https://github.com/user/repo").unwrap();
+        let path2 = cache_path_for_url(&Config::new(), "https://github.com/user/repo").unwrap();
         assert_eq!(path1, path2);
     }
 
     #[test]
     fn cache_path_for_url_differs_for_different_urls() {
-        let path1 = cache_path_for_url("https://github.com/user/repo-a").unwrap();
-        let path2 = cache_path_for_url("https://github.com/user/repo-b").unwrap();
+        let path1 = cache_path_for_url(&Config::new(), "https://github.com/user/repo-a").unwrap();
+        let path2 = cache_path_for_url(&Config::new(), "https://github.com/user/repo-b").unwrap();
         assert_ne!(path1, path2);
     }
 
     #[test]
     fn cache_path_for_url_ends_with_hex_segment() {
-        let path = cache_path_for_url("https://github.com/user/repo").unwrap();
+        let path = cache_path_for_url(&Config::new(), "https://github.com/user/repo").unwrap();
         let hex = path.file_name().unwrap().to_string_lossy();
         assert_eq!(hex.len(), 16);
         assert!(hex.chars().all(|character| character.is_ascii_hexdigit()));